@@ -1,6 +1,23 @@
 use crate::error::ParseDiagnostic;
 use kdl::{KdlDocument, KdlNode, KdlValue};
-use miette::NamedSource;
+use miette::{NamedSource, SourceSpan};
+
+/// An error at `span`, with `help` suggesting how to fix it.
+pub fn error_diagnostic(
+    input: NamedSource<String>,
+    span: SourceSpan,
+    message: String,
+    help: String,
+) -> ParseDiagnostic {
+    ParseDiagnostic {
+        input,
+        span,
+        message: Some(message),
+        label: None,
+        help: Some(help),
+        severity: miette::Severity::Error,
+    }
+}
 
 pub fn get_single_argument_value<'a>(
     input: NamedSource<String>,
@@ -12,4 +12,16 @@ SECTIONS {
     {
         KEEP(*(.boot2));
     } > BOOT2
-} INSERT BEFORE .text;
\ No newline at end of file
+} INSERT BEFORE .text;
+/* The firmware info is placed at a fixed address after the vector table so the flash tool can find it.
+ * Keep in sync with rukaibox_config::FIRMWARE_INFO_OFFSET */
+_firmware_info = ORIGIN(FLASH) + 0x100;
+_stext = _firmware_info + 0x100;
+
+SECTIONS {
+    /* ### Firmware info */
+    .firmware_info _firmware_info :
+    {
+        KEEP(*(.firmware_info));
+    } > FLASH
+} INSERT AFTER .vector_table;
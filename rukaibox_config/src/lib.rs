@@ -7,69 +7,724 @@ pub const RP2040_FLASH_SIZE: usize = 1024 * 1024 * 16; // 16 MiB
 pub const FIRMWARE_OFFSET: usize = 0;
 pub const FIRMWARE_SIZE: usize = 1024 * 1024 * 15; // 15 MiB
 pub const CONFIG_OFFSET: usize = 1024 * 1024 * 15;
-pub const CONFIG_SIZE: usize = 256; // 10 KiB
+pub const CONFIG_SIZE: usize = 1024 * 8; // 8 KiB, a config with every list at capacity takes just over 5 KiB
+
+/// The config.kdl source that the config was created from, exposed by the firmware's USB drive mode.
+/// Stored as:
+/// * text size: u32 big endian
+/// * utf-8 text
+pub const CONFIG_KDL_OFFSET: usize = CONFIG_OFFSET + 1024 * 64;
+pub const CONFIG_KDL_SIZE: usize = 1024 * 32; // 32 KiB
+
+/// Bindings changed on the controller via remap mode are stored here, as a complete config in the same format as the config.
+/// When present it is used instead of the config, reverting the remaps or flashing a new config erases it.
+pub const REMAPPED_CONFIG_OFFSET: usize = CONFIG_OFFSET + 1024 * 32;
+
+/// The version of the config.kdl format.
+/// The flash tool upgrades config.kdl files from older versions to this version.
+pub const CONFIG_VERSION: u32 = 16;
+
+/// The firmware places its [`FirmwareInfo`] at this offset into flash, just after the vector table.
+/// Keep in sync with the `.firmware_info` section in memory.x
+pub const FIRMWARE_INFO_OFFSET: usize = 0x200;
+
+/// The config is stored as:
+/// * config size: u32 big endian
+/// * schema fingerprint: u32 big endian
+/// * rkyv serialized [`Config`]
+pub const CONFIG_HEADER_SIZE: usize = 8;
+
+/// Scratch space for serializing a [`Config`], enough for a config with every list at capacity.
+pub const CONFIG_SCRATCH_SIZE: usize = 1024;
+
+/// A fingerprint of the stored [`Config`] schema.
+///
+/// Firmware and config are only compatible when built against the same fingerprint.
+/// It is derived from [`CONFIG_VERSION`], which changes with every change to the config types,
+/// along with the size and alignment of [`ArchivedConfig`] to catch a layout change that missed a version bump.
+/// rkyv archives have the same layout on the host and the device, so the flash tool and firmware agree on it.
+/// Changes that don't affect the stored config, such as to comments or methods, keep the same fingerprint.
+pub const SCHEMA_FINGERPRINT: u32 = fnv1a(&[
+    CONFIG_VERSION,
+    size_of::<ArchivedConfig>() as u32,
+    align_of::<ArchivedConfig>() as u32,
+]);
+
+/// Hashes the big endian bytes of `words`.
+const fn fnv1a(words: &[u32]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    let mut i = 0;
+    while i < words.len() * 4 {
+        hash ^= words[i / 4].to_be_bytes()[i % 4] as u32;
+        hash = hash.wrapping_mul(0x01000193);
+        i += 1;
+    }
+    hash
+}
+
+/// Embedded in the firmware image at [`FIRMWARE_INFO_OFFSET`] so the flash tool can check
+/// which config schema the firmware on a device expects.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FirmwareInfo {
+    pub schema_fingerprint: u32,
+}
+
+impl FirmwareInfo {
+    pub const MAGIC: [u8; 4] = *b"RKBX";
+    pub const SIZE: usize = 8;
+
+    pub const CURRENT: FirmwareInfo = FirmwareInfo {
+        schema_fingerprint: SCHEMA_FINGERPRINT,
+    };
+
+    pub const fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let magic = Self::MAGIC;
+        let fingerprint = self.schema_fingerprint.to_be_bytes();
+        [
+            magic[0],
+            magic[1],
+            magic[2],
+            magic[3],
+            fingerprint[0],
+            fingerprint[1],
+            fingerprint[2],
+            fingerprint[3],
+        ]
+    }
+
+    /// Returns None if the bytes do not start with [`FirmwareInfo::MAGIC`],
+    /// this occurs when the firmware predates `FirmwareInfo` or is not a rukaibox firmware at all.
+    pub fn from_bytes(bytes: &[u8]) -> Option<FirmwareInfo> {
+        if bytes.len() < Self::SIZE || bytes[0..4] != Self::MAGIC {
+            return None;
+        }
+        Some(FirmwareInfo {
+            schema_fingerprint: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+        })
+    }
+}
 
 use arrayvec::ArrayVec;
 use rkyv::{Archive, Deserialize, Serialize};
 
+/// The most profiles a [`Config`] can hold.
+pub const MAX_PROFILES: usize = 3;
+
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default)]
 #[rkyv(derive(Debug))]
 pub struct Config {
     pub version: u32,
-    pub profiles: ArrayVec<Profile, 3>,
+    pub profiles: ArrayVec<Profile, MAX_PROFILES>,
 }
 
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default)]
 #[rkyv(derive(Debug))]
 pub struct Profile {
+    /// Switches to this profile when all of these buttons are held, they are not sent to the game until released.
+    /// An empty combination never switches to the profile.
     pub activation_combination: ArrayVec<PhysicalButton, 10>,
+    /// Milliseconds `activation_combination` must be held for before switching, 0 switches as soon as it is complete.
+    pub activation_hold_ms: u32,
     pub logic: BaseLogic,
-    pub socd: SocdType,
+    pub socd: SocdTypes,
     pub buttons: LogicalButtonToPhysicalButton,
+    /// Logical buttons pressed while several physical buttons are held together, in addition to `buttons`.
+    ///
+    /// Every chord is matched against the physical buttons as they are held, so a button can be part of several chords.
+    /// A consuming chord releases its members from `buttons` from the poll it completes on,
+    /// until each member is physically released, so a chord always takes priority over the single button bindings of its members.
+    /// Members are not delayed while waiting for the rest of the chord, so a member pressed on an earlier poll
+    /// activates its own binding until the chord completes.
+    pub chords: ArrayVec<Chord, 8>,
+    /// Override some of `buttons` while active, listed from highest to lowest priority.
+    ///
+    /// Each logical button uses its binding from the highest priority active layer that lists it, or `buttons` otherwise.
+    /// A layer's physical buttons are removed from the bindings of lower priority layers and `buttons`,
+    /// so that they only activate the layer's logical buttons.
+    pub layers: ArrayVec<Layer, 4>,
+    /// Physical buttons that activate one logical button when tapped and another when held.
+    /// A tap-hold button only activates its tap and hold buttons, never its bindings in `buttons`.
+    pub tap_holds: ArrayVec<TapHold, 4>,
+    /// Logical buttons that can stay pressed without being held, e.g. mod-y for menu navigation or a lightshield level.
+    /// Latches apply to the logical button after bindings, chords and tap-holds, and are all released when the profile changes.
+    pub latches: ArrayVec<Latch, 4>,
+    /// The stick coordinates for `logic`, with any overrides from config.kdl applied.
+    pub coordinates: Coordinates,
+    /// Checked in order, the first overlay with all conditions met replaces the coordinates chosen by `logic`.
+    pub overlays: ArrayVec<Overlay, 8>,
+    /// Milliseconds after releasing down that horizontal SOCD resolves to the last pressed direction, 0 disables it.
+    /// Only used by [`BaseLogic::ProjectPlus`].
+    pub ledgedash_socd_override_ms: u32,
+    /// The analog trigger values for `logic`, with any overrides from config.kdl applied.
+    pub triggers: TriggerLevels,
+    /// Only used by [`BaseLogic::Ultimate`].
+    pub cstick_mode: CstickMode,
+    /// Replaces the main stick coordinate while a diagonal is entered by holding down and then a horizontal direction,
+    /// until down is released. None disables it.
+    pub crouch_walk_prevention: Option<Coordinate>,
+    pub travel_time: TravelTime,
+    /// Checked by the flash tool and again by the firmware when the config is loaded.
+    pub ruleset: Ruleset,
+}
+
+impl Profile {
+    /// Calls `report` for every setting of the profile that breaks its [`Ruleset`].
+    pub fn check_ruleset(&self, mut report: impl FnMut(RulesetViolation)) {
+        match self.ruleset {
+            Ruleset::Unrestricted => {}
+            Ruleset::Melee2024 => self.check_melee_2024(&mut report),
+        }
+    }
+
+    /// Returns the first setting of the profile that breaks its [`Ruleset`], if any.
+    pub fn ruleset_violation(&self) -> Option<RulesetViolation> {
+        let mut first = None;
+        self.check_ruleset(|violation| {
+            first.get_or_insert(violation);
+        });
+        first
+    }
+
+    fn check_melee_2024(&self, report: &mut impl FnMut(RulesetViolation)) {
+        // Melee treats an offset of 80 as 1.0, so nothing further out can be produced by a calibrated analog stick.
+        let max = 80;
+        let in_range = |coordinate: &Coordinate| coordinate.x <= max && coordinate.y <= max;
+
+        if self.logic != BaseLogic::Melee {
+            report(RulesetViolation::Logic);
+        }
+        // Releasing a direction must not reactivate the opposite direction that is still held.
+        if !matches!(
+            self.socd.stick_horizontal,
+            SocdType::SecondInputPriorityNoReactivation | SocdType::Neutral
+        ) {
+            report(RulesetViolation::Socd {
+                axis: "stick-horizontal",
+            });
+        }
+        // Vertical may prioritize a direction, e.g. up for jumping, but must not reactivate a held direction either.
+        if self.socd.stick_vertical == SocdType::SecondInputPriority {
+            report(RulesetViolation::Socd {
+                axis: "stick-vertical",
+            });
+        }
+        // The c-stick and dpad only trigger discrete actions in melee, so any SOCD type is allowed on them.
+        for name in self.logic.coordinate_names() {
+            if let Some(coordinate) = self.coordinates.get(name)
+                && !in_range(coordinate)
+            {
+                report(RulesetViolation::Coordinate { name, max });
+            }
+        }
+        for (index, overlay) in self.overlays.iter().enumerate() {
+            // Coordinates may only depend on the inputs the melee logic itself uses.
+            for condition in &overlay.conditions {
+                let allowed = match condition {
+                    OverlayCondition::Held(button) => !matches!(
+                        button,
+                        LogicalButton::A
+                            | LogicalButton::X
+                            | LogicalButton::Y
+                            | LogicalButton::Z
+                            | LogicalButton::Start
+                            | LogicalButton::DpadUp
+                            | LogicalButton::DpadDown
+                            | LogicalButton::DpadLeft
+                            | LogicalButton::DpadRight
+                    ),
+                    OverlayCondition::Horizontal
+                    | OverlayCondition::Vertical
+                    | OverlayCondition::Diagonal => true,
+                };
+                if !allowed {
+                    report(RulesetViolation::OverlayCondition {
+                        index,
+                        condition: condition.name(),
+                    });
+                }
+            }
+            if !overlay.stick.iter().chain(&overlay.cstick).all(in_range) {
+                report(RulesetViolation::OverlayCoordinate { index, max });
+            }
+        }
+        if let Some(coordinate) = &self.crouch_walk_prevention
+            && !in_range(coordinate)
+        {
+            report(RulesetViolation::CrouchWalkPrevention { max });
+        }
+        // A physical button must always do the same thing, regardless of what else is held or how long it is held for.
+        for (index, chord) in self.chords.iter().enumerate() {
+            if chord.consume {
+                report(RulesetViolation::Chord { index });
+            }
+        }
+        for index in 0..self.layers.len() {
+            report(RulesetViolation::Layer { index });
+        }
+        for index in 0..self.tap_holds.len() {
+            report(RulesetViolation::TapHold { index });
+        }
+        // Every input must come from a button that is being held.
+        for index in 0..self.latches.len() {
+            report(RulesetViolation::Latch { index });
+        }
+    }
+}
+
+/// A tournament ruleset that a profile must comply with.
+///
+/// This lets tournament organizers check a controller by reading the ruleset of each profile with the flash tool.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
+#[rkyv(derive(Debug))]
+pub enum Ruleset {
+    /// Any setting is allowed.
+    #[default]
+    Unrestricted,
+    /// Requires:
+    /// * the melee logic
+    /// * `stick-horizontal` SOCD of second-input-priority-no-reactivation or neutral
+    /// * `stick-vertical` SOCD other than second-input-priority, any SOCD type is allowed on the c-stick and dpad
+    /// * every coordinate, overlay coordinate and the crouch walk prevention coordinate at most 80 on each axis
+    /// * overlays only conditioned on mod-x, mod-y, b, the triggers and stick directions
+    /// * no consuming chords, forwarding chords are allowed
+    /// * no layers, tap-holds or latches
+    Melee2024,
+}
+
+impl Ruleset {
+    /// The kebab-case names of every ruleset, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] = &["unrestricted", "melee-2024"];
+
+    /// The ruleset named by each entry of [`Ruleset::NAMES`].
+    pub const VALUES: &'static [Ruleset] = &[Ruleset::Unrestricted, Ruleset::Melee2024];
+
+    /// The kebab-case name of the ruleset, as used in config.kdl.
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+}
+
+/// A profile setting that is not allowed by the profile's [`Ruleset`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RulesetViolation {
+    /// The profile's logic is not allowed.
+    Logic,
+    /// The SOCD type of the named axis is not allowed.
+    Socd { axis: &'static str },
+    /// The named coordinate of the profile's logic is beyond `max` on an axis.
+    Coordinate { name: &'static str, max: u8 },
+    /// The overlay at `index` has a condition that is not allowed.
+    OverlayCondition {
+        index: usize,
+        condition: &'static str,
+    },
+    /// A coordinate of the overlay at `index` is beyond `max` on an axis.
+    OverlayCoordinate { index: usize, max: u8 },
+    /// The crouch walk prevention coordinate is beyond `max` on an axis.
+    CrouchWalkPrevention { max: u8 },
+    /// The chord at `index` is not allowed.
+    Chord { index: usize },
+    /// The layer at `index` is not allowed.
+    Layer { index: usize },
+    /// The tap-hold at `index` is not allowed.
+    TapHold { index: usize },
+    /// The latch at `index` is not allowed.
+    Latch { index: usize },
+}
+
+impl core::fmt::Display for RulesetViolation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RulesetViolation::Logic => write!(f, "The profile logic is not allowed"),
+            RulesetViolation::Socd { axis } => write!(f, "SOCD type for `{axis}` is not allowed"),
+            RulesetViolation::Coordinate { name, max } => {
+                write!(f, "Coordinate `{name}` must be at most {max} on each axis")
+            }
+            RulesetViolation::OverlayCondition { index, condition } => write!(
+                f,
+                "Overlay {} condition `{condition}` is not allowed",
+                index + 1
+            ),
+            RulesetViolation::OverlayCoordinate { index, max } => write!(
+                f,
+                "Overlay {} coordinates must be at most {max} on each axis",
+                index + 1
+            ),
+            RulesetViolation::CrouchWalkPrevention { max } => write!(
+                f,
+                "Crouch walk prevention coordinate must be at most {max} on each axis"
+            ),
+            RulesetViolation::Chord { index } => {
+                write!(
+                    f,
+                    "Chord {} consumes its buttons, which is not allowed",
+                    index + 1
+                )
+            }
+            RulesetViolation::Layer { index } => write!(f, "Layer {} is not allowed", index + 1),
+            RulesetViolation::TapHold { index } => {
+                write!(f, "Tap-hold {} is not allowed", index + 1)
+            }
+            RulesetViolation::Latch { index } => write!(f, "Latch {} is not allowed", index + 1),
+        }
+    }
+}
+
+/// How long each stick takes to move between positions, 0 moves instantly.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct TravelTime {
+    pub stick_micros: u32,
+    pub cstick_micros: u32,
 }
 
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
 #[rkyv(derive(Debug))]
 pub struct LogicalButtonToPhysicalButton {
-    pub mod_x: PhysicalButton,
-    pub mod_y: PhysicalButton,
-
-    pub start: PhysicalButton,
-    pub a: PhysicalButton,
-    pub b: PhysicalButton,
-    pub x: PhysicalButton,
-    pub y: PhysicalButton,
-    pub z: PhysicalButton,
-
-    pub dpad_up: PhysicalButton,
-    pub dpad_down: PhysicalButton,
-    pub dpad_left: PhysicalButton,
-    pub dpad_right: PhysicalButton,
-
-    pub l_digital: PhysicalButton,
-    pub r_digital: PhysicalButton,
-    pub l_analog: PhysicalButton,
-    pub r_analog: PhysicalButton,
-
-    pub stick_left: PhysicalButton,
-    pub stick_right: PhysicalButton,
-    pub stick_up: PhysicalButton,
-    /// quick hack to work around lack of OR
-    pub stick_up2: PhysicalButton,
-    pub stick_down: PhysicalButton,
-
-    pub cstick_left: PhysicalButton,
-    pub cstick_right: PhysicalButton,
-    pub cstick_up: PhysicalButton,
-    pub cstick_down: PhysicalButton,
+    pub mod_x: Binding,
+    pub mod_y: Binding,
+
+    pub start: Binding,
+    pub a: Binding,
+    pub b: Binding,
+    pub x: Binding,
+    pub y: Binding,
+    pub z: Binding,
+
+    pub dpad_up: Binding,
+    pub dpad_down: Binding,
+    pub dpad_left: Binding,
+    pub dpad_right: Binding,
+
+    pub l_digital: Binding,
+    pub r_digital: Binding,
+    pub l_analog: Binding,
+    pub r_analog: Binding,
+
+    pub stick_left: Binding,
+    pub stick_right: Binding,
+    pub stick_up: Binding,
+    pub stick_down: Binding,
+
+    pub cstick_left: Binding,
+    pub cstick_right: Binding,
+    pub cstick_up: Binding,
+    pub cstick_down: Binding,
 }
 
+impl LogicalButtonToPhysicalButton {
+    /// Bind `physical` to `logical` alongside its existing bindings,
+    /// removing any other bindings of `physical` so that it only activates `logical`.
+    /// When `logical` already has 4 bindings, the oldest one is replaced.
+    pub fn assign(&mut self, logical: LogicalButton, physical: PhysicalButton) {
+        if physical == PhysicalButton::None {
+            return;
+        }
+        self.unbind(physical);
+        let binding = &mut self.get_mut(logical).buttons;
+        if binding.is_full() {
+            binding.remove(0);
+        }
+        binding.push(physical);
+    }
+
+    /// Remove `physical` from every binding.
+    pub fn unbind(&mut self, physical: PhysicalButton) {
+        for field in self.fields_mut() {
+            field.buttons.retain(|button| *button != physical);
+        }
+    }
+
+    pub fn get(&self, logical: LogicalButton) -> &Binding {
+        match logical {
+            LogicalButton::LAnalog => &self.l_analog,
+            LogicalButton::RAnalog => &self.r_analog,
+            LogicalButton::LDigital => &self.l_digital,
+            LogicalButton::RDigital => &self.r_digital,
+            LogicalButton::StickUp => &self.stick_up,
+            LogicalButton::StickDown => &self.stick_down,
+            LogicalButton::StickLeft => &self.stick_left,
+            LogicalButton::StickRight => &self.stick_right,
+            LogicalButton::CstickUp => &self.cstick_up,
+            LogicalButton::CstickDown => &self.cstick_down,
+            LogicalButton::CstickLeft => &self.cstick_left,
+            LogicalButton::CstickRight => &self.cstick_right,
+            LogicalButton::DpadUp => &self.dpad_up,
+            LogicalButton::DpadDown => &self.dpad_down,
+            LogicalButton::DpadLeft => &self.dpad_left,
+            LogicalButton::DpadRight => &self.dpad_right,
+            LogicalButton::ModX => &self.mod_x,
+            LogicalButton::ModY => &self.mod_y,
+            LogicalButton::Start => &self.start,
+            LogicalButton::A => &self.a,
+            LogicalButton::B => &self.b,
+            LogicalButton::X => &self.x,
+            LogicalButton::Y => &self.y,
+            LogicalButton::Z => &self.z,
+        }
+    }
+
+    pub fn get_mut(&mut self, logical: LogicalButton) -> &mut Binding {
+        match logical {
+            LogicalButton::LAnalog => &mut self.l_analog,
+            LogicalButton::RAnalog => &mut self.r_analog,
+            LogicalButton::LDigital => &mut self.l_digital,
+            LogicalButton::RDigital => &mut self.r_digital,
+            LogicalButton::StickUp => &mut self.stick_up,
+            LogicalButton::StickDown => &mut self.stick_down,
+            LogicalButton::StickLeft => &mut self.stick_left,
+            LogicalButton::StickRight => &mut self.stick_right,
+            LogicalButton::CstickUp => &mut self.cstick_up,
+            LogicalButton::CstickDown => &mut self.cstick_down,
+            LogicalButton::CstickLeft => &mut self.cstick_left,
+            LogicalButton::CstickRight => &mut self.cstick_right,
+            LogicalButton::DpadUp => &mut self.dpad_up,
+            LogicalButton::DpadDown => &mut self.dpad_down,
+            LogicalButton::DpadLeft => &mut self.dpad_left,
+            LogicalButton::DpadRight => &mut self.dpad_right,
+            LogicalButton::ModX => &mut self.mod_x,
+            LogicalButton::ModY => &mut self.mod_y,
+            LogicalButton::Start => &mut self.start,
+            LogicalButton::A => &mut self.a,
+            LogicalButton::B => &mut self.b,
+            LogicalButton::X => &mut self.x,
+            LogicalButton::Y => &mut self.y,
+            LogicalButton::Z => &mut self.z,
+        }
+    }
+
+    fn fields_mut(&mut self) -> [&mut Binding; 24] {
+        [
+            &mut self.mod_x,
+            &mut self.mod_y,
+            &mut self.start,
+            &mut self.a,
+            &mut self.b,
+            &mut self.x,
+            &mut self.y,
+            &mut self.z,
+            &mut self.dpad_up,
+            &mut self.dpad_down,
+            &mut self.dpad_left,
+            &mut self.dpad_right,
+            &mut self.l_digital,
+            &mut self.r_digital,
+            &mut self.l_analog,
+            &mut self.r_analog,
+            &mut self.stick_left,
+            &mut self.stick_right,
+            &mut self.stick_up,
+            &mut self.stick_down,
+            &mut self.cstick_left,
+            &mut self.cstick_right,
+            &mut self.cstick_up,
+            &mut self.cstick_down,
+        ]
+    }
+}
+
+/// The physical buttons bound to a logical button, pressing any of them activates the logical button.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct Binding {
+    pub buttons: ArrayVec<PhysicalButton, 4>,
+}
+
+impl Binding {
+    /// A binding to the single button `physical`, or an empty binding for [`PhysicalButton::None`].
+    pub fn new(physical: PhysicalButton) -> Self {
+        let mut binding = Binding::default();
+        if physical != PhysicalButton::None {
+            binding.buttons.push(physical);
+        }
+        binding
+    }
+
+    pub fn contains(&self, physical: PhysicalButton) -> bool {
+        self.buttons.contains(&physical)
+    }
+}
+
+/// A logical button that is pressed while all of `buttons` are held together.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct Chord {
+    /// At least 2 buttons, since a single button is a regular [`Binding`].
+    pub buttons: ArrayVec<PhysicalButton, 4>,
+    pub logical: LogicalButton,
+    /// Release `buttons` from their own bindings while the chord is active, see [`Profile::chords`].
+    pub consume: bool,
+}
+
+/// An alternate set of bindings for some logical buttons, e.g. for navigating menus.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[rkyv(derive(Debug))]
+pub struct Layer {
+    pub activation: LayerActivation,
+    pub bindings: ArrayVec<LayerBinding, 12>,
+}
+
+/// How a [`Layer`] is activated.
+/// The activation button only controls the layer, it never activates any of its own bindings.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+#[rkyv(derive(Debug))]
+pub enum LayerActivation {
+    /// Active while the button is held.
+    Hold(PhysicalButton),
+    /// Pressing the button switches the layer on or off.
+    Toggle(PhysicalButton),
+}
+
+impl LayerActivation {
+    /// The kebab-case names of every activation, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] = &["hold", "toggle"];
+
+    pub fn button(self) -> PhysicalButton {
+        match self {
+            LayerActivation::Hold(button) | LayerActivation::Toggle(button) => button,
+        }
+    }
+}
+
+/// Replaces the binding of `logical` while a [`Layer`] is active.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct LayerBinding {
+    pub logical: LogicalButton,
+    pub binding: Binding,
+}
+
+/// A physical button that activates `tap` when tapped and `hold` when held.
+///
+/// Nothing is activated until the press is decided, which happens on the first poll where either:
+/// * the button has been held for `tapping_term_ms`, or another button was pressed with `hold_on_other_key_press`,
+///   then `hold` is active until the button is released.
+/// * the button is released, then `tap` is active for a short time so the game sees it.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct TapHold {
+    pub button: PhysicalButton,
+    pub tap: LogicalButton,
+    pub hold: LogicalButton,
+    pub tapping_term_ms: u32,
+    /// Decide on hold as soon as another physical button is pressed, instead of waiting for the tapping term.
+    pub hold_on_other_key_press: bool,
+    /// Milliseconds `tap` stays active for after a tap, [`TapHold::DEFAULT_TAP_MS`] unless set in config.kdl.
+    pub tap_ms: u32,
+}
+
+impl TapHold {
+    /// Two frames at 60 fps rounded up, so the game sees the tap even if it only reads the controller once per frame.
+    pub const DEFAULT_TAP_MS: u32 = 34;
+}
+
+/// A logical button that can stay pressed without being held.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct Latch {
+    pub logical: LogicalButton,
+    pub kind: LatchKind,
+}
+
+/// How a [`Latch`] is switched on and off.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
+#[rkyv(derive(Debug))]
+pub enum LatchKind {
+    /// Each press switches the button between pressed and released.
+    #[default]
+    Toggle,
+    /// The button is pressed while held as usual, but a double tap locks it pressed until it is pressed again.
+    /// Two presses are a double tap when the second starts within [`LatchKind::DOUBLE_TAP_MS`] of the first.
+    Lock,
+}
+
+impl LatchKind {
+    /// The kebab-case names of every latch kind, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] = &["toggle", "lock"];
+
+    /// The latch kind named by each entry of [`LatchKind::NAMES`].
+    pub const VALUES: &'static [LatchKind] = &[LatchKind::Toggle, LatchKind::Lock];
+
+    /// The double tap window of [`LatchKind::Lock`], the usual double click speed of desktop operating systems.
+    pub const DOUBLE_TAP_MS: u32 = 300;
+}
+
+/// How the SOCD (simultaneous opposing cardinal directions) of each axis are resolved.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct SocdTypes {
+    pub stick_horizontal: SocdType,
+    pub stick_vertical: SocdType,
+    pub cstick_horizontal: SocdType,
+    pub cstick_vertical: SocdType,
+    /// Used for both axes of the dpad.
+    pub dpad: SocdType,
+}
+
+impl SocdTypes {
+    /// The kebab-case names of every axis, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] = &[
+        "stick-horizontal",
+        "stick-vertical",
+        "cstick-horizontal",
+        "cstick-vertical",
+        "dpad",
+    ];
+
+    /// Uses `socd` for every axis.
+    pub const fn new(socd: SocdType) -> Self {
+        SocdTypes {
+            stick_horizontal: socd,
+            stick_vertical: socd,
+            cstick_horizontal: socd,
+            cstick_vertical: socd,
+            dpad: socd,
+        }
+    }
+
+    /// Returns the type of the axis with the given kebab-case name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut SocdType> {
+        match name {
+            "stick-horizontal" => Some(&mut self.stick_horizontal),
+            "stick-vertical" => Some(&mut self.stick_vertical),
+            "cstick-horizontal" => Some(&mut self.cstick_horizontal),
+            "cstick-vertical" => Some(&mut self.cstick_vertical),
+            "dpad" => Some(&mut self.dpad),
+            _ => None,
+        }
+    }
+}
+
+/// How an axis is resolved while both of its directions are held.
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
 #[rkyv(derive(Debug))]
 pub enum SocdType {
+    /// The direction pressed last is active, releasing it reactivates the other direction.
     #[default]
     SecondInputPriority,
+    /// The direction pressed last is active, the other direction stays inactive until it is pressed again.
+    SecondInputPriorityNoReactivation,
+    /// The direction pressed first stays active.
+    FirstInputPriority,
+    /// Neither direction is active.
     Neutral,
+    /// Up is active on vertical axes and left is active on horizontal axes, e.g. the up priority used by hitboxes.
+    UpOrLeftPriority,
+    /// Down is active on vertical axes and right is active on horizontal axes.
+    DownOrRightPriority,
+}
+
+/// Whether the C-stick sends smash or tilt magnitudes, for use with the matching in-game C-stick setting.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
+#[rkyv(derive(Debug))]
+pub enum CstickMode {
+    /// Always full magnitude.
+    #[default]
+    Smash,
+    /// Tilt magnitude, full magnitude while mod-x is held.
+    TiltUnlessModX,
+    /// Tilt magnitude, full magnitude while mod-y is held.
+    TiltUnlessModY,
 }
 
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
@@ -79,6 +734,503 @@ pub enum BaseLogic {
     ProjectPlus,
     Rivals2,
     Ultimate,
+    Melee,
+    Rivals1,
+}
+
+impl BaseLogic {
+    /// The kebab-case names of the coordinates used by this logic, as used in config.kdl.
+    pub fn coordinate_names(self) -> &'static [&'static str] {
+        match self {
+            BaseLogic::ProjectPlus => ProjectPlusCoordinates::NAMES,
+            BaseLogic::Rivals2 => Rivals2Coordinates::NAMES,
+            BaseLogic::Ultimate => UltimateCoordinates::NAMES,
+            BaseLogic::Melee => MeleeCoordinates::NAMES,
+            BaseLogic::Rivals1 => Rivals1Coordinates::NAMES,
+        }
+    }
+}
+
+/// A stick coordinate, as an offset from the center of the stick in the direction the stick is held.
+///
+/// For example `(72, 61)` held down-left is sent as stick value `(128 - 72, 128 - 61)`.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
+#[rkyv(derive(Debug))]
+pub struct Coordinate {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Coordinate {
+    /// The largest offset that fits within the stick range in both directions.
+    pub const MAX: u8 = 127;
+
+    pub const fn new(x: u8, y: u8) -> Self {
+        Coordinate { x, y }
+    }
+}
+
+/// The named stick coordinates used by a profile, the variant always matches the profile's [`BaseLogic`].
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[rkyv(derive(Debug))]
+pub enum Coordinates {
+    ProjectPlus(ProjectPlusCoordinates),
+    Rivals2(Rivals2Coordinates),
+    Ultimate(UltimateCoordinates),
+    Melee(MeleeCoordinates),
+    Rivals1(Rivals1Coordinates),
+}
+
+impl Default for Coordinates {
+    fn default() -> Self {
+        Coordinates::new(BaseLogic::default())
+    }
+}
+
+impl Coordinates {
+    /// The default coordinates for `logic`.
+    pub fn new(logic: BaseLogic) -> Self {
+        match logic {
+            BaseLogic::ProjectPlus => Coordinates::ProjectPlus(Default::default()),
+            BaseLogic::Rivals2 => Coordinates::Rivals2(Default::default()),
+            BaseLogic::Ultimate => Coordinates::Ultimate(Default::default()),
+            BaseLogic::Melee => Coordinates::Melee(Default::default()),
+            BaseLogic::Rivals1 => Coordinates::Rivals1(Default::default()),
+        }
+    }
+
+    /// Returns the coordinate with the kebab-case `name`, or None if there is no coordinate with that name.
+    pub fn get(&self, name: &str) -> Option<&Coordinate> {
+        match self {
+            Coordinates::ProjectPlus(coordinates) => coordinates.get(name),
+            Coordinates::Rivals2(coordinates) => coordinates.get(name),
+            Coordinates::Ultimate(coordinates) => coordinates.get(name),
+            Coordinates::Melee(coordinates) => coordinates.get(name),
+            Coordinates::Rivals1(coordinates) => coordinates.get(name),
+        }
+    }
+
+    /// Returns the coordinate with the kebab-case `name`, or None if there is no coordinate with that name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Coordinate> {
+        match self {
+            Coordinates::ProjectPlus(coordinates) => coordinates.get_mut(name),
+            Coordinates::Rivals2(coordinates) => coordinates.get_mut(name),
+            Coordinates::Ultimate(coordinates) => coordinates.get_mut(name),
+            Coordinates::Melee(coordinates) => coordinates.get_mut(name),
+            Coordinates::Rivals1(coordinates) => coordinates.get_mut(name),
+        }
+    }
+}
+
+/// Replaces the coordinates chosen by the profile's logic while all of the conditions are met.
+///
+/// This allows character specific angles, e.g. mod-x + diagonal + b + cstick-up, without adding a new [`BaseLogic`].
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
+#[rkyv(derive(Debug))]
+pub struct Overlay {
+    pub conditions: ArrayVec<OverlayCondition, 6>,
+    /// Applied in the direction the main stick is held.
+    pub stick: Option<Coordinate>,
+    /// Applied in the direction the C-stick is held.
+    pub cstick: Option<Coordinate>,
+}
+
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Clone, Copy)]
+#[rkyv(derive(Debug))]
+pub enum OverlayCondition {
+    /// The button is held, after SOCD has been resolved.
+    Held(LogicalButton),
+    /// The main stick is held left or right, but not up or down.
+    Horizontal,
+    /// The main stick is held up or down, but not left or right.
+    Vertical,
+    /// The main stick is held both horizontally and vertically.
+    Diagonal,
+}
+
+impl OverlayCondition {
+    /// The kebab-case names of every condition, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] = &[
+        "horizontal",
+        "vertical",
+        "diagonal",
+        "l-analog",
+        "r-analog",
+        "l-digital",
+        "r-digital",
+        "stick-up",
+        "stick-down",
+        "stick-left",
+        "stick-right",
+        "cstick-up",
+        "cstick-down",
+        "cstick-left",
+        "cstick-right",
+        "dpad-up",
+        "dpad-down",
+        "dpad-left",
+        "dpad-right",
+        "mod-x",
+        "mod-y",
+        "start",
+        "a",
+        "b",
+        "x",
+        "y",
+        "z",
+    ];
+
+    /// The condition named by each entry of [`OverlayCondition::NAMES`].
+    pub const VALUES: &'static [OverlayCondition] = &[
+        OverlayCondition::Horizontal,
+        OverlayCondition::Vertical,
+        OverlayCondition::Diagonal,
+        OverlayCondition::Held(LogicalButton::LAnalog),
+        OverlayCondition::Held(LogicalButton::RAnalog),
+        OverlayCondition::Held(LogicalButton::LDigital),
+        OverlayCondition::Held(LogicalButton::RDigital),
+        OverlayCondition::Held(LogicalButton::StickUp),
+        OverlayCondition::Held(LogicalButton::StickDown),
+        OverlayCondition::Held(LogicalButton::StickLeft),
+        OverlayCondition::Held(LogicalButton::StickRight),
+        OverlayCondition::Held(LogicalButton::CstickUp),
+        OverlayCondition::Held(LogicalButton::CstickDown),
+        OverlayCondition::Held(LogicalButton::CstickLeft),
+        OverlayCondition::Held(LogicalButton::CstickRight),
+        OverlayCondition::Held(LogicalButton::DpadUp),
+        OverlayCondition::Held(LogicalButton::DpadDown),
+        OverlayCondition::Held(LogicalButton::DpadLeft),
+        OverlayCondition::Held(LogicalButton::DpadRight),
+        OverlayCondition::Held(LogicalButton::ModX),
+        OverlayCondition::Held(LogicalButton::ModY),
+        OverlayCondition::Held(LogicalButton::Start),
+        OverlayCondition::Held(LogicalButton::A),
+        OverlayCondition::Held(LogicalButton::B),
+        OverlayCondition::Held(LogicalButton::X),
+        OverlayCondition::Held(LogicalButton::Y),
+        OverlayCondition::Held(LogicalButton::Z),
+    ];
+
+    /// The kebab-case name of the condition, as used in config.kdl.
+    pub fn name(self) -> &'static str {
+        let index = Self::VALUES.iter().position(|x| *x == self).unwrap();
+        Self::NAMES[index]
+    }
+}
+
+/// The analog trigger values sent by a profile, from 0 (released) to 255 (fully pressed).
+///
+/// Holding a modifier with an analog trigger button selects a different level, e.g. for several lightshield strengths.
+#[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Clone)]
+#[rkyv(derive(Debug))]
+pub struct TriggerLevels {
+    /// Sent while an analog trigger button is held.
+    pub analog: u8,
+    /// Sent instead of `analog` while mod-x is also held.
+    pub analog_mod_x: u8,
+    /// Sent instead of `analog` while mod-y is also held.
+    pub analog_mod_y: u8,
+    /// Sent while a digital trigger button is held, 0 only sends the digital press.
+    pub digital: u8,
+}
+
+impl TriggerLevels {
+    /// The kebab-case names of every level, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] =
+        &["analog", "analog-mod-x", "analog-mod-y", "digital"];
+
+    /// The default levels for `logic`.
+    pub const fn new(logic: BaseLogic) -> Self {
+        match logic {
+            // Ultimate ignores digital triggers, so pressing the analog trigger will probably do what the user intended.
+            BaseLogic::Ultimate => TriggerLevels {
+                analog: 140,
+                analog_mod_x: 140,
+                analog_mod_y: 140,
+                digital: 140,
+            },
+            // The B0XX lightshield value.
+            BaseLogic::ProjectPlus | BaseLogic::Rivals2 | BaseLogic::Melee | BaseLogic::Rivals1 => {
+                TriggerLevels {
+                    analog: 49,
+                    analog_mod_x: 49,
+                    analog_mod_y: 49,
+                    digital: 0,
+                }
+            }
+        }
+    }
+
+    /// Returns the level with the given kebab-case name.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut u8> {
+        match name {
+            "analog" => Some(&mut self.analog),
+            "analog-mod-x" => Some(&mut self.analog_mod_x),
+            "analog-mod-y" => Some(&mut self.analog_mod_y),
+            "digital" => Some(&mut self.digital),
+            _ => None,
+        }
+    }
+}
+
+impl Default for TriggerLevels {
+    fn default() -> Self {
+        TriggerLevels::new(BaseLogic::default())
+    }
+}
+
+/// Defines a table of named coordinates along with their default values.
+macro_rules! coordinate_table {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident $kdl_name:literal = ($x:literal, $y:literal),
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Clone)]
+        #[rkyv(derive(Debug))]
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: Coordinate,
+            )*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name {
+                    $($field: Coordinate::new($x, $y),)*
+                }
+            }
+        }
+
+        impl $name {
+            pub const NAMES: &'static [&'static str] = &[$($kdl_name,)*];
+
+            pub fn get(&self, name: &str) -> Option<&Coordinate> {
+                match name {
+                    $($kdl_name => Some(&self.$field),)*
+                    _ => None,
+                }
+            }
+
+            pub fn get_mut(&mut self, name: &str) -> Option<&mut Coordinate> {
+                match name {
+                    $($kdl_name => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+coordinate_table! {
+    /// Coordinates used by [`BaseLogic::ProjectPlus`].
+    pub struct ProjectPlusCoordinates {
+        mod_x_diagonal_cstick_right "mod-x-diagonal-cstick-right" = (72, 61),
+        mod_x_diagonal_cstick_left "mod-x-diagonal-cstick-left" = (84, 50),
+        mod_x_diagonal_cstick_down "mod-x-diagonal-cstick-down" = (82, 36),
+        mod_x_diagonal_cstick_up "mod-x-diagonal-cstick-up" = (77, 55),
+        mod_x_diagonal_shield "mod-x-diagonal-shield" = (82, 35),
+        mod_x_diagonal_b "mod-x-diagonal-b" = (85, 31),
+        mod_x_diagonal "mod-x-diagonal" = (70, 34),
+        mod_x_vertical "mod-x-vertical" = (0, 60),
+        mod_x_horizontal "mod-x-horizontal" = (70, 0),
+
+        mod_y_diagonal_cstick_right "mod-y-diagonal-cstick-right" = (62, 72),
+        mod_y_diagonal_cstick_left "mod-y-diagonal-cstick-left" = (40, 84),
+        mod_y_diagonal_cstick_down "mod-y-diagonal-cstick-down" = (34, 82),
+        mod_y_diagonal_cstick_up "mod-y-diagonal-cstick-up" = (55, 77),
+        mod_y_diagonal_shield "mod-y-diagonal-shield" = (51, 82),
+        mod_y_diagonal_b "mod-y-diagonal-b" = (28, 85),
+        mod_y_diagonal "mod-y-diagonal" = (28, 58),
+        mod_y_vertical "mod-y-vertical" = (0, 70),
+        mod_y_horizontal "mod-y-horizontal" = (35, 0),
+
+        diagonal_up "diagonal-up" = (83, 93),
+        max "max" = (100, 100),
+
+        /// Angled smash attacks, the y value is applied in the direction of the main stick.
+        cstick_mod_x_horizontal "cstick-mod-x-horizontal" = (65, 23),
+        /// ASDI slideoff, the Melee angle scaled up to match `cstick-max`.
+        cstick_diagonal "cstick-diagonal" = (52, 85),
+        cstick_max "cstick-max" = (100, 100),
+    }
+}
+
+coordinate_table! {
+    /// Coordinates used by [`BaseLogic::Rivals2`].
+    ///
+    /// Values taken from: https://github.com/JonnyHaystack/HayBox/blob/52188f41209a18c03e0c1d151679c32025a48962/src/modes/Rivals2.cpp#L109
+    pub struct Rivals2Coordinates {
+        mod_x_diagonal_a "mod-x-diagonal-a" = (69, 53),
+        mod_x_diagonal_z "mod-x-diagonal-z" = (53, 42),
+        mod_x_diagonal_b "mod-x-diagonal-b" = (123, 51),
+        mod_x_diagonal "mod-x-diagonal" = (68, 42),
+        mod_x_diagonal_shield "mod-x-diagonal-shield" = (76, 42),
+        mod_x_vertical "mod-x-vertical" = (0, 53),
+        mod_x_horizontal "mod-x-horizontal" = (76, 0),
+
+        mod_y_diagonal_z "mod-y-diagonal-z" = (42, 53),
+        mod_y_diagonal_b "mod-y-diagonal-b" = (51, 123),
+        mod_y_diagonal "mod-y-diagonal" = (42, 68),
+        mod_y_vertical "mod-y-vertical" = (0, 90),
+        mod_y_horizontal "mod-y-horizontal" = (53, 0),
+
+        diagonal_shield "diagonal-shield" = (92, 92),
+        diagonal "diagonal" = (92, 96),
+        max "max" = (127, 127),
+
+        /// Angled smash attacks, the y value is applied in the direction of the main stick.
+        cstick_mod_x_horizontal "cstick-mod-x-horizontal" = (65, 23),
+        cstick_max "cstick-max" = (127, 127),
+    }
+}
+
+coordinate_table! {
+    /// Coordinates used by [`BaseLogic::Rivals1`].
+    ///
+    /// Unlike Rivals 2 an offset of 80 is treated as a full press, so the values are much smaller.
+    ///
+    /// Based on the B0XX values used by: https://github.com/JonnyHaystack/HayBox/blob/52188f41209a18c03e0c1d151679c32025a48962/src/modes/RivalsOfAether.cpp
+    pub struct Rivals1Coordinates {
+        /// Up B angles, the C-stick selects between angles from 28 to 39 degrees.
+        mod_x_diagonal_cstick_down "mod-x-diagonal-cstick-down" = (53, 43),
+        mod_x_diagonal_cstick_left "mod-x-diagonal-cstick-left" = (53, 39),
+        mod_x_diagonal_cstick_up "mod-x-diagonal-cstick-up" = (56, 33),
+        mod_x_diagonal_cstick_right "mod-x-diagonal-cstick-right" = (49, 26),
+        mod_x_diagonal_shield "mod-x-diagonal-shield" = (51, 30),
+        mod_x_diagonal "mod-x-diagonal" = (53, 35),
+        /// Below the tilt threshold so that tilts and shield drops work.
+        mod_x_vertical "mod-x-vertical" = (0, 44),
+        /// A walk that will also do tilts, without reaching the dash threshold.
+        mod_x_horizontal "mod-x-horizontal" = (66, 0),
+
+        mod_y_diagonal_cstick_down "mod-y-diagonal-cstick-down" = (43, 53),
+        mod_y_diagonal_cstick_left "mod-y-diagonal-cstick-left" = (39, 53),
+        mod_y_diagonal_cstick_up "mod-y-diagonal-cstick-up" = (33, 56),
+        mod_y_diagonal_cstick_right "mod-y-diagonal-cstick-right" = (26, 49),
+        mod_y_diagonal_shield "mod-y-diagonal-shield" = (38, 70),
+        mod_y_diagonal "mod-y-diagonal" = (35, 53),
+        /// Tap jumps and shield drops without fast falling.
+        mod_y_vertical "mod-y-vertical" = (0, 67),
+        /// A slow walk.
+        mod_y_horizontal "mod-y-horizontal" = (44, 0),
+
+        diagonal "diagonal" = (56, 56),
+        max "max" = (80, 80),
+
+        /// Angled tilts and smash attacks, the y value is applied in the direction of the main stick.
+        cstick_mod_x_horizontal "cstick-mod-x-horizontal" = (80, 59),
+        cstick_max "cstick-max" = (80, 80),
+    }
+}
+
+coordinate_table! {
+    /// Coordinates used by [`BaseLogic::Ultimate`].
+    pub struct UltimateCoordinates {
+        mod_x_diagonal_b_cstick_right "mod-x-diagonal-b-cstick-right" = (67, 35),
+        mod_x_diagonal_b_cstick_up "mod-x-diagonal-b-cstick-up" = (67, 39),
+        mod_x_diagonal_b_cstick_left "mod-x-diagonal-b-cstick-left" = (67, 49),
+        mod_x_diagonal_b_cstick_down "mod-x-diagonal-b-cstick-down" = (67, 55),
+        mod_x_diagonal_b "mod-x-diagonal-b" = (67, 44),
+        mod_x_diagonal_cstick_right "mod-x-diagonal-cstick-right" = (53, 28),
+        mod_x_diagonal_cstick_up "mod-x-diagonal-cstick-up" = (53, 31),
+        mod_x_diagonal_cstick_left "mod-x-diagonal-cstick-left" = (53, 39),
+        mod_x_diagonal_cstick_down "mod-x-diagonal-cstick-down" = (53, 43),
+        mod_x_diagonal_shield "mod-x-diagonal-shield" = (51, 30),
+        mod_x_diagonal_a "mod-x-diagonal-a" = (70, 34),
+        mod_x_diagonal "mod-x-diagonal" = (53, 35),
+        mod_x_horizontal_b "mod-x-horizontal-b" = (66, 0),
+        mod_x_horizontal_shield "mod-x-horizontal-shield" = (51, 0),
+        mod_x_horizontal "mod-x-horizontal" = (53, 0),
+        mod_x_vertical_shield "mod-x-vertical-shield" = (0, 51),
+        mod_x_vertical "mod-x-vertical" = (0, 60),
+
+        mod_y_diagonal_shield_left "mod-y-diagonal-shield-left" = (40, 68),
+        mod_y_diagonal_shield "mod-y-diagonal-shield" = (38, 70),
+        mod_y_diagonal_a "mod-y-diagonal-a" = (38, 69),
+        mod_y_diagonal_b_cstick_right "mod-y-diagonal-b-cstick-right" = (35, 67),
+        mod_y_diagonal_b_cstick_up "mod-y-diagonal-b-cstick-up" = (39, 67),
+        mod_y_diagonal_b_cstick_left "mod-y-diagonal-b-cstick-left" = (49, 67),
+        mod_y_diagonal_b_cstick_down "mod-y-diagonal-b-cstick-down" = (55, 67),
+        mod_y_diagonal_b "mod-y-diagonal-b" = (44, 67),
+        mod_y_diagonal_cstick_right "mod-y-diagonal-cstick-right" = (28, 53),
+        mod_y_diagonal_cstick_up "mod-y-diagonal-cstick-up" = (31, 53),
+        mod_y_diagonal_cstick_left "mod-y-diagonal-cstick-left" = (49, 53),
+        mod_y_diagonal_cstick_down "mod-y-diagonal-cstick-down" = (43, 53),
+        mod_y_diagonal "mod-y-diagonal" = (35, 53),
+        mod_y_horizontal "mod-y-horizontal" = (35, 0),
+        mod_y_vertical_a "mod-y-vertical-a" = (0, 36),
+        mod_y_vertical "mod-y-vertical" = (0, 53),
+
+        max "max" = (100, 100),
+
+        /// ASDI slideoff
+        cstick_diagonal "cstick-diagonal" = (42, 68),
+        /// Used instead of `cstick-max` when [`Profile::cstick_mode`] selects tilts.
+        cstick_tilt "cstick-tilt" = (53, 53),
+        /// Angled smash attacks, the y value is applied in the direction of the main stick.
+        cstick_mod_x_horizontal "cstick-mod-x-horizontal" = (65, 44),
+        cstick_max "cstick-max" = (100, 100),
+    }
+}
+
+coordinate_table! {
+    /// Coordinates used by [`BaseLogic::Melee`].
+    ///
+    /// Melee treats an offset of 80 as 1.0 and clamps anything further out, so 80 is used for full cardinals.
+    ///
+    /// Based on the B0XX values used by: https://github.com/JonnyHaystack/HayBox/blob/52188f41209a18c03e0c1d151679c32025a48962/src/modes/Melee20Button.cpp
+    pub struct MeleeCoordinates {
+        /// Firefox angles, the C-stick selects between angles from 23 to 41 degrees.
+        mod_x_diagonal_cstick_down "mod-x-diagonal-cstick-down" = (56, 29),
+        mod_x_diagonal_cstick_left "mod-x-diagonal-cstick-left" = (63, 39),
+        mod_x_diagonal_cstick_up "mod-x-diagonal-cstick-up" = (56, 41),
+        mod_x_diagonal_cstick_right "mod-x-diagonal-cstick-right" = (49, 42),
+        /// Extended firefox angles, the same angles as above but further from the center.
+        mod_x_diagonal_b_cstick_down "mod-x-diagonal-b-cstick-down" = (70, 36),
+        mod_x_diagonal_b_cstick_left "mod-x-diagonal-b-cstick-left" = (68, 42),
+        mod_x_diagonal_b_cstick_up "mod-x-diagonal-b-cstick-up" = (59, 43),
+        mod_x_diagonal_b_cstick_right "mod-x-diagonal-b-cstick-right" = (51, 43),
+        mod_x_diagonal_b "mod-x-diagonal-b" = (73, 31),
+        /// Shallow wavedash.
+        mod_x_diagonal_shield "mod-x-diagonal-shield" = (51, 30),
+        mod_x_diagonal "mod-x-diagonal" = (59, 25),
+        mod_x_vertical "mod-x-vertical" = (0, 43),
+        mod_x_horizontal "mod-x-horizontal" = (53, 0),
+
+        mod_y_diagonal_cstick_down "mod-y-diagonal-cstick-down" = (29, 56),
+        mod_y_diagonal_cstick_left "mod-y-diagonal-cstick-left" = (39, 63),
+        mod_y_diagonal_cstick_up "mod-y-diagonal-cstick-up" = (41, 56),
+        mod_y_diagonal_cstick_right "mod-y-diagonal-cstick-right" = (42, 49),
+        mod_y_diagonal_b_cstick_down "mod-y-diagonal-b-cstick-down" = (36, 70),
+        mod_y_diagonal_b_cstick_left "mod-y-diagonal-b-cstick-left" = (42, 68),
+        mod_y_diagonal_b_cstick_up "mod-y-diagonal-b-cstick-up" = (43, 59),
+        mod_y_diagonal_b_cstick_right "mod-y-diagonal-b-cstick-right" = (43, 51),
+        mod_y_diagonal_b "mod-y-diagonal-b" = (31, 73),
+        /// Steep wavedash.
+        mod_y_diagonal_shield_down "mod-y-diagonal-shield-down" = (40, 68),
+        mod_y_diagonal_shield "mod-y-diagonal-shield" = (38, 70),
+        mod_y_diagonal "mod-y-diagonal" = (25, 59),
+        mod_y_vertical "mod-y-vertical" = (0, 59),
+        mod_y_horizontal "mod-y-horizontal" = (27, 0),
+
+        /// Slightly less than 45 degrees so that shield dropping works and wavedashing isn't perfectly horizontal.
+        diagonal_shield_down "diagonal-shield-down" = (56, 55),
+        diagonal "diagonal" = (56, 56),
+        max "max" = (80, 80),
+
+        /// ASDI slideoff.
+        cstick_diagonal "cstick-diagonal" = (42, 68),
+        /// Angled forward smash, the y value is applied in the direction of the main stick.
+        cstick_mod_x_horizontal "cstick-mod-x-horizontal" = (68, 42),
+        cstick_max "cstick-max" = (80, 80),
+    }
 }
 
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
@@ -116,6 +1268,60 @@ pub enum PhysicalButton {
     None,
 }
 
+impl PhysicalButton {
+    /// The kebab-case names of every physical button, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] = &[
+        "start",
+        "left-pinky",
+        "left-ring",
+        "left-middle",
+        "left-index",
+        "left-middle2",
+        "left-thumb-left",
+        "left-thumb-right",
+        "right-index",
+        "right-middle",
+        "right-ring",
+        "right-pinky",
+        "right-index2",
+        "right-middle2",
+        "right-ring2",
+        "right-pinky2",
+        "right-thumb-left",
+        "right-thumb-right",
+        "right-thumb-up",
+        "right-thumb-down",
+        "right-thumb-middle",
+        "none",
+    ];
+
+    /// The button named by each entry of [`PhysicalButton::NAMES`].
+    pub const VALUES: &'static [PhysicalButton] = &[
+        PhysicalButton::Start,
+        PhysicalButton::LeftPinky,
+        PhysicalButton::LeftRing,
+        PhysicalButton::LeftMiddle,
+        PhysicalButton::LeftIndex,
+        PhysicalButton::LeftMiddle2,
+        PhysicalButton::LeftThumbLeft,
+        PhysicalButton::LeftThumbRight,
+        PhysicalButton::RightIndex,
+        PhysicalButton::RightMiddle,
+        PhysicalButton::RightRing,
+        PhysicalButton::RightPinky,
+        PhysicalButton::RightIndex2,
+        PhysicalButton::RightMiddle2,
+        PhysicalButton::RightRing2,
+        PhysicalButton::RightPinky2,
+        PhysicalButton::RightThumbLeft,
+        PhysicalButton::RightThumbRight,
+        PhysicalButton::RightThumbUp,
+        PhysicalButton::RightThumbDown,
+        PhysicalButton::RightThumbMiddle,
+        PhysicalButton::None,
+    ];
+}
+
 #[derive(Archive, Deserialize, Serialize, Debug, PartialEq, Default, Clone, Copy)]
 #[rkyv(derive(Debug))]
 pub enum LogicalButton {
@@ -138,9 +1344,146 @@ pub enum LogicalButton {
     DpadRight,
     ModX,
     ModY,
+    Start,
     A,
     B,
     X,
     Y,
     Z,
 }
+
+impl LogicalButton {
+    /// The kebab-case names of every logical button, as used in config.kdl.
+    pub const NAMES: &'static [&'static str] = &[
+        "l-analog",
+        "r-analog",
+        "l-digital",
+        "r-digital",
+        "stick-up",
+        "stick-down",
+        "stick-left",
+        "stick-right",
+        "cstick-up",
+        "cstick-down",
+        "cstick-left",
+        "cstick-right",
+        "dpad-up",
+        "dpad-down",
+        "dpad-left",
+        "dpad-right",
+        "mod-x",
+        "mod-y",
+        "start",
+        "a",
+        "b",
+        "x",
+        "y",
+        "z",
+    ];
+
+    /// The button named by each entry of [`LogicalButton::NAMES`].
+    pub const VALUES: &'static [LogicalButton] = &[
+        LogicalButton::LAnalog,
+        LogicalButton::RAnalog,
+        LogicalButton::LDigital,
+        LogicalButton::RDigital,
+        LogicalButton::StickUp,
+        LogicalButton::StickDown,
+        LogicalButton::StickLeft,
+        LogicalButton::StickRight,
+        LogicalButton::CstickUp,
+        LogicalButton::CstickDown,
+        LogicalButton::CstickLeft,
+        LogicalButton::CstickRight,
+        LogicalButton::DpadUp,
+        LogicalButton::DpadDown,
+        LogicalButton::DpadLeft,
+        LogicalButton::DpadRight,
+        LogicalButton::ModX,
+        LogicalButton::ModY,
+        LogicalButton::Start,
+        LogicalButton::A,
+        LogicalButton::B,
+        LogicalButton::X,
+        LogicalButton::Y,
+        LogicalButton::Z,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::MaybeUninit;
+    use rkyv::{
+        api::low::to_bytes_in_with_alloc,
+        rancor::Failure,
+        ser::{allocator::SubAllocator, writer::Buffer},
+        util::Align,
+    };
+
+    fn full<T, const N: usize>(item: impl FnMut() -> T) -> ArrayVec<T, N> {
+        core::iter::repeat_with(item).take(N).collect()
+    }
+
+    fn full_binding() -> Binding {
+        Binding {
+            buttons: full(|| PhysicalButton::Start),
+        }
+    }
+
+    fn max_capacity_profile() -> Profile {
+        let mut profile = Profile {
+            activation_combination: full(|| PhysicalButton::Start),
+            chords: full(|| Chord {
+                buttons: full(|| PhysicalButton::Start),
+                logical: LogicalButton::A,
+                consume: true,
+            }),
+            layers: full(|| Layer {
+                activation: LayerActivation::Hold(PhysicalButton::Start),
+                bindings: full(|| LayerBinding {
+                    logical: LogicalButton::A,
+                    binding: full_binding(),
+                }),
+            }),
+            tap_holds: full(TapHold::default),
+            latches: full(Latch::default),
+            overlays: full(|| Overlay {
+                conditions: full(|| OverlayCondition::Horizontal),
+                stick: Some(Coordinate::default()),
+                cstick: Some(Coordinate::default()),
+            }),
+            crouch_walk_prevention: Some(Coordinate::default()),
+            ..Default::default()
+        };
+        for binding in profile.buttons.fields_mut() {
+            *binding = full_binding();
+        }
+        profile
+    }
+
+    /// Stored the same way as the firmware, so a config that the flash tool accepts can always be stored.
+    #[test]
+    fn max_capacity_config_fits() {
+        let config = Config {
+            version: CONFIG_VERSION,
+            profiles: full(max_capacity_profile),
+        };
+        let mut bytes = Align([0xFF; CONFIG_SIZE]);
+        let mut scratch = [MaybeUninit::uninit(); CONFIG_SCRATCH_SIZE];
+        let size = to_bytes_in_with_alloc::<_, _, Failure>(
+            &config,
+            Buffer::from(&mut bytes[CONFIG_HEADER_SIZE..]),
+            SubAllocator::new(&mut scratch),
+        )
+        .unwrap()
+        .len();
+
+        let archive = rkyv::api::low::access::<ArchivedConfig, Failure>(
+            &bytes[CONFIG_HEADER_SIZE..CONFIG_HEADER_SIZE + size],
+        )
+        .unwrap();
+        let stored: Config = rkyv::api::low::deserialize::<_, Failure>(archive).unwrap();
+        assert_eq!(stored, config);
+    }
+}
@@ -0,0 +1,1469 @@
+//! A minimal KDL parser for parsing config.kdl on the device.
+//!
+//! The flash tool uses the `kdl` crate which requires an allocator, so it cannot be used here.
+//! Instead this handles the subset of KDL used by config.kdl without allocating:
+//! nodes with identifier, string and integer arguments, children blocks, comments and slashdash comments.
+//!
+//! Only the first error is reported and there are no migrations from older config versions,
+//! for anything more involved the flash tool should be used instead.
+//!
+//! Kept separate from the firmware so that it can be built and tested on the host.
+
+#![cfg_attr(not(test), no_std)]
+
+use arrayvec::ArrayVec;
+use core::fmt;
+use rukaibox_config::{
+    BaseLogic, Binding, CONFIG_VERSION, Chord, Config, Coordinate, Coordinates, CstickMode, Latch,
+    LatchKind, Layer, LayerActivation, LayerBinding, LogicalButton, LogicalButtonToPhysicalButton,
+    Overlay, OverlayCondition, PhysicalButton, Profile, Ruleset, RulesetViolation, SocdType,
+    SocdTypes, TapHold, TriggerLevels,
+};
+
+const MAX_ARGUMENTS: usize = 8;
+const MAX_COORDINATE_OVERRIDES: usize = 64;
+
+#[derive(Debug)]
+pub struct Error<'a> {
+    /// Byte offset into the source where the error occurred
+    pub position: usize,
+    pub kind: ErrorKind<'a>,
+}
+
+#[derive(Debug)]
+pub enum ErrorKind<'a> {
+    UnexpectedCharacter(char),
+    UnexpectedEndOfFile,
+    UnterminatedString,
+    UnsupportedStringEscape,
+    UnterminatedComment,
+    UnsupportedProperty,
+    UnsupportedTypeAnnotation,
+    InvalidValue(&'a str),
+    TooManyArguments,
+    ExpectedSingleArgument(&'a str),
+    ExpectedInteger(&'a str),
+    IntegerOutOfRange {
+        name: &'a str,
+        max: u32,
+    },
+    ExpectedCoordinate(&'a str),
+    CoordinateOutOfRange(&'a str),
+    ExpectedChildren(&'a str),
+    UnexpectedChildren(&'a str),
+    ExpectedListItem(&'a str),
+    TooManyListItems {
+        max: usize,
+    },
+    TooManyOverlayConditions {
+        max: usize,
+    },
+    EmptyOverlay,
+    ChordButtons {
+        max: usize,
+    },
+    ExpectedButtons(&'a str),
+    ExpectedLayerActivation,
+    ExpectedTapHoldButton,
+    TooManyBindings {
+        name: &'a str,
+        max: usize,
+    },
+    DuplicateBinding(&'a str),
+    UnknownNode {
+        name: &'a str,
+        expected: &'static [&'static str],
+    },
+    DuplicateNode(&'a str),
+    MissingNode(&'static str),
+    UnknownValue {
+        value: &'a str,
+        expected: &'static [&'static str],
+    },
+    UnsupportedVersion(i64),
+    RulesetViolation {
+        ruleset: &'static str,
+        violation: RulesetViolation,
+    },
+}
+
+impl Error<'_> {
+    /// Returns the 1-indexed line and column of the error.
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.position.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = match before.rfind('\n') {
+            Some(newline) => before[newline + 1..].chars().count() + 1,
+            None => before.chars().count() + 1,
+        };
+        (line, column)
+    }
+}
+
+impl fmt::Display for ErrorKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character {c:?}"),
+            ErrorKind::UnexpectedEndOfFile => {
+                write!(f, "Unexpected end of file, is there a missing `}}`?")
+            }
+            ErrorKind::UnterminatedString => write!(f, "String is missing its closing `\"`"),
+            ErrorKind::UnsupportedStringEscape => write!(
+                f,
+                "Escapes in strings are not supported on the controller, use the flash tool instead"
+            ),
+            ErrorKind::UnterminatedComment => write!(f, "Comment is missing its closing `*/`"),
+            ErrorKind::UnsupportedProperty => write!(f, "Properties are not supported"),
+            ErrorKind::UnsupportedTypeAnnotation => write!(f, "Type annotations are not supported"),
+            ErrorKind::InvalidValue(value) => write!(f, "{value:?} is not a valid value"),
+            ErrorKind::TooManyArguments => {
+                write!(f, "Node has more than {MAX_ARGUMENTS} arguments")
+            }
+            ErrorKind::ExpectedSingleArgument(name) => {
+                write!(f, "Node `{name}` should contain exactly 1 value")
+            }
+            ErrorKind::ExpectedInteger(name) => {
+                write!(f, "Node `{name}` should contain an integer")
+            }
+            ErrorKind::IntegerOutOfRange { name, max } => {
+                write!(f, "Node `{name}` must be between 0 and {max}")
+            }
+            ErrorKind::ExpectedCoordinate(name) => {
+                write!(
+                    f,
+                    "Coordinate `{name}` should contain 2 integers, e.g. `{name} 72 61`"
+                )
+            }
+            ErrorKind::CoordinateOutOfRange(name) => write!(
+                f,
+                "Coordinate `{name}` values must be between 0 and {}",
+                Coordinate::MAX
+            ),
+            ErrorKind::ExpectedChildren(name) => {
+                write!(f, "Node `{name}` should be followed by a `{{ }}` block")
+            }
+            ErrorKind::UnexpectedChildren(name) => {
+                write!(f, "Node `{name}` should not have a `{{ }}` block")
+            }
+            ErrorKind::ExpectedListItem(name) => write!(
+                f,
+                "List items must start with a \"-\", consider replacing {name:?} with a \"-\""
+            ),
+            ErrorKind::TooManyListItems { max } => {
+                write!(f, "List contains more than the maximum of {max} items")
+            }
+            ErrorKind::TooManyOverlayConditions { max } => {
+                write!(f, "Overlay has more than the maximum of {max} conditions")
+            }
+            ErrorKind::EmptyOverlay => write!(
+                f,
+                "Overlay should contain a `stick <x> <y>` or `cstick <x> <y>` node"
+            ),
+            ErrorKind::ChordButtons { max } => {
+                write!(f, "Chord should list between 2 and {max} physical buttons")
+            }
+            ErrorKind::ExpectedButtons(name) => write!(
+                f,
+                "Node `{name}` should list at least 1 physical button, or `none`"
+            ),
+            ErrorKind::ExpectedLayerActivation => write!(
+                f,
+                "Node `activation` should contain `hold` or `toggle` followed by a physical button"
+            ),
+            ErrorKind::ExpectedTapHoldButton => write!(
+                f,
+                "Tap-hold should be followed by a single physical button other than `none`"
+            ),
+            ErrorKind::TooManyBindings { name, max } => write!(
+                f,
+                "Button `{name}` is bound to more than the maximum of {max} physical buttons"
+            ),
+            ErrorKind::DuplicateBinding(name) => write!(
+                f,
+                "Button `{name}` lists the same physical button more than once"
+            ),
+            ErrorKind::UnknownNode { name, expected } => {
+                write!(f, "Unknown node name `{name}`, consider one of ")?;
+                write_list(f, expected)
+            }
+            ErrorKind::DuplicateNode(name) => write!(f, "Node `{name}` is defined twice"),
+            ErrorKind::MissingNode(name) => write!(f, "Child `{name}` is missing from this node"),
+            ErrorKind::UnknownValue { value, expected } => {
+                write!(f, "Unknown value `{value}`, consider one of ")?;
+                write_list(f, expected)
+            }
+            ErrorKind::UnsupportedVersion(version) => write!(
+                f,
+                "config.kdl is version {version} but the controller requires version {CONFIG_VERSION}. \
+                 Upgrade the config by running the flash tool with --upgrade-config"
+            ),
+            ErrorKind::RulesetViolation { ruleset, violation } => {
+                write!(f, "{violation} in ruleset {ruleset}")
+            }
+        }
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, values: &[&str]) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i != 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{value}")?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum Value<'a> {
+    /// Both quoted strings and bare identifiers
+    String(&'a str),
+    Integer(i64),
+}
+
+struct Node<'a> {
+    pub name: &'a str,
+    /// Byte offset into the source of the start of the node
+    pub position: usize,
+    pub arguments: ArrayVec<Value<'a>, MAX_ARGUMENTS>,
+    /// When true the children must be consumed via [`Parser::next_node`] or [`Parser::skip_children`]
+    /// before continuing on to the next sibling.
+    pub has_children: bool,
+}
+
+impl<'a> Node<'a> {
+    fn error(&self, kind: ErrorKind<'a>) -> Error<'a> {
+        Error {
+            position: self.position,
+            kind,
+        }
+    }
+
+    fn single_argument(&self) -> Result<Value<'a>, Error<'a>> {
+        if self.has_children {
+            return Err(self.error(ErrorKind::UnexpectedChildren(self.name)));
+        }
+        match self.arguments.as_slice() {
+            [value] => Ok(*value),
+            _ => Err(self.error(ErrorKind::ExpectedSingleArgument(self.name))),
+        }
+    }
+
+    fn integer(&self) -> Result<i64, Error<'a>> {
+        match self.single_argument()? {
+            Value::Integer(value) => Ok(value),
+            _ => Err(self.error(ErrorKind::ExpectedInteger(self.name))),
+        }
+    }
+
+    fn unsigned(&self, max: u32) -> Result<u32, Error<'a>> {
+        let value = self.integer()?;
+        if !(0..=max as i64).contains(&value) {
+            return Err(self.error(ErrorKind::IntegerOutOfRange {
+                name: self.name,
+                max,
+            }));
+        }
+        Ok(value as u32)
+    }
+
+    fn coordinate(&self) -> Result<Coordinate, Error<'a>> {
+        if self.has_children {
+            return Err(self.error(ErrorKind::UnexpectedChildren(self.name)));
+        }
+        let [Value::Integer(x), Value::Integer(y)] = self.arguments.as_slice() else {
+            return Err(self.error(ErrorKind::ExpectedCoordinate(self.name)));
+        };
+        let range = 0..=Coordinate::MAX as i64;
+        if !range.contains(x) || !range.contains(y) {
+            return Err(self.error(ErrorKind::CoordinateOutOfRange(self.name)));
+        }
+        Ok(Coordinate::new(*x as u8, *y as u8))
+    }
+
+    fn enum_value<T: Copy>(&self, values: &EnumValues<T>) -> Result<T, Error<'a>> {
+        self.enum_argument(self.single_argument()?, values)
+    }
+
+    /// Looks up `argument` of this node in `values`.
+    fn enum_argument<T: Copy>(
+        &self,
+        argument: Value<'a>,
+        values: &EnumValues<T>,
+    ) -> Result<T, Error<'a>> {
+        let value = match argument {
+            Value::String(value) => value,
+            // Report the node name since there is no text to report for non string values
+            _ => self.name,
+        };
+        match values.names.iter().position(|name| *name == value) {
+            Some(index) => Ok(values.values[index]),
+            None => Err(self.error(ErrorKind::UnknownValue {
+                value,
+                expected: values.names,
+            })),
+        }
+    }
+
+    fn expect_children(&self) -> Result<(), Error<'a>> {
+        if self.has_children && self.arguments.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::ExpectedChildren(self.name)))
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Parser {
+            source,
+            position: if source.starts_with('\u{FEFF}') { 3 } else { 0 },
+            depth: 0,
+        }
+    }
+
+    /// Returns the next node of the current document, or None when the end of the document is reached.
+    pub fn next_node(&mut self) -> Result<Option<Node<'a>>, Error<'a>> {
+        loop {
+            self.skip_line_space()?;
+            match self.peek() {
+                None => {
+                    return if self.depth == 0 {
+                        Ok(None)
+                    } else {
+                        Err(self.error(ErrorKind::UnexpectedEndOfFile))
+                    };
+                }
+                Some('}') => {
+                    if self.depth == 0 {
+                        return Err(self.error(ErrorKind::UnexpectedCharacter('}')));
+                    }
+                    self.position += 1;
+                    self.depth -= 1;
+                    return Ok(None);
+                }
+                Some(_) if self.rest().starts_with("/-") => {
+                    self.position += 2;
+                    self.skip_node_space()?;
+                    let node = self.parse_node()?;
+                    if node.has_children {
+                        self.skip_children()?;
+                    }
+                }
+                Some(_) => return self.parse_node().map(Some),
+            }
+        }
+    }
+
+    /// Skip over all remaining children of the current node.
+    pub fn skip_children(&mut self) -> Result<(), Error<'a>> {
+        while let Some(child) = self.next_node()? {
+            if child.has_children {
+                self.skip_children()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_node(&mut self) -> Result<Node<'a>, Error<'a>> {
+        let position = self.position;
+        let name = match self.parse_value()? {
+            Value::String(name) => name,
+            Value::Integer(_) => {
+                let c = self.source[position..].chars().next().unwrap();
+                return Err(self.error_at(position, ErrorKind::UnexpectedCharacter(c)));
+            }
+        };
+        let mut node = Node {
+            name,
+            position,
+            arguments: ArrayVec::new(),
+            has_children: false,
+        };
+
+        loop {
+            let had_space = self.skip_node_space()?;
+            let rest = self.rest();
+            match self.peek() {
+                None => return Ok(node),
+                Some('\n' | '\r' | ';') => {
+                    self.position += 1;
+                    return Ok(node);
+                }
+                Some('}') => return Ok(node),
+                Some('{') => {
+                    self.position += 1;
+                    self.depth += 1;
+                    node.has_children = true;
+                    return Ok(node);
+                }
+                Some(_) if rest.starts_with("//") => {
+                    self.skip_line_comment();
+                    return Ok(node);
+                }
+                Some(_) if rest.starts_with("/-") => {
+                    self.position += 2;
+                    self.skip_node_space()?;
+                    if self.peek() == Some('{') {
+                        self.position += 1;
+                        self.depth += 1;
+                        self.skip_children()?;
+                    } else {
+                        self.parse_value()?;
+                    }
+                }
+                Some(c) => {
+                    if !had_space {
+                        return Err(self.error(ErrorKind::UnexpectedCharacter(c)));
+                    }
+                    let value = self.parse_value()?;
+                    if self.peek() == Some('=') {
+                        return Err(self.error(ErrorKind::UnsupportedProperty));
+                    }
+                    node.arguments
+                        .try_push(value)
+                        .map_err(|_| self.error(ErrorKind::TooManyArguments))?;
+                }
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value<'a>, Error<'a>> {
+        let start = self.position;
+        match self.peek() {
+            Some('"') => {
+                self.position += 1;
+                let rest = self.rest();
+                for (i, c) in rest.char_indices() {
+                    match c {
+                        '"' => {
+                            self.position += i + 1;
+                            return Ok(Value::String(&rest[..i]));
+                        }
+                        '\\' => {
+                            return Err(self
+                                .error_at(self.position + i, ErrorKind::UnsupportedStringEscape));
+                        }
+                        _ => {}
+                    }
+                }
+                Err(self.error_at(start, ErrorKind::UnterminatedString))
+            }
+            Some('(') => Err(self.error(ErrorKind::UnsupportedTypeAnnotation)),
+            Some(_) => {
+                let len = self
+                    .rest()
+                    .find(|c: char| !is_identifier_char(c))
+                    .unwrap_or(self.rest().len());
+                if len == 0 {
+                    let c = self.peek().unwrap();
+                    return Err(self.error(ErrorKind::UnexpectedCharacter(c)));
+                }
+                let text = &self.rest()[..len];
+                self.position += len;
+                parse_bare_value(text).ok_or(Error {
+                    position: start,
+                    kind: ErrorKind::InvalidValue(text),
+                })
+            }
+            None => Err(self.error(ErrorKind::UnexpectedEndOfFile)),
+        }
+    }
+
+    /// Skips whitespace, newlines, comments and node terminators.
+    fn skip_line_space(&mut self) -> Result<(), Error<'a>> {
+        loop {
+            let rest = self.rest();
+            match self.peek() {
+                Some(c) if c.is_whitespace() || c == ';' => self.position += c.len_utf8(),
+                Some(_) if rest.starts_with("//") => self.skip_line_comment(),
+                Some(_) if rest.starts_with("/*") => self.skip_block_comment()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips whitespace and comments that do not end the node.
+    /// Returns true if anything was skipped.
+    fn skip_node_space(&mut self) -> Result<bool, Error<'a>> {
+        let start = self.position;
+        loop {
+            let rest = self.rest();
+            match self.peek() {
+                Some(c) if c.is_whitespace() && c != '\n' && c != '\r' => {
+                    self.position += c.len_utf8()
+                }
+                Some(_) if rest.starts_with("/*") => self.skip_block_comment()?,
+                // Line continuation
+                Some('\\') => {
+                    self.position += 1;
+                    while let Some(c) = self.peek() {
+                        if c.is_whitespace() && c != '\n' {
+                            self.position += c.len_utf8();
+                        } else {
+                            break;
+                        }
+                    }
+                    if self.rest().starts_with("//") {
+                        self.skip_line_comment();
+                    } else if self.peek() == Some('\n') {
+                        self.position += 1;
+                    } else {
+                        return Err(self.error(ErrorKind::UnexpectedCharacter('\\')));
+                    }
+                }
+                _ => return Ok(self.position != start),
+            }
+        }
+    }
+
+    /// Skips up to and including the next newline
+    fn skip_line_comment(&mut self) {
+        match self.rest().find('\n') {
+            Some(newline) => self.position += newline + 1,
+            None => self.position = self.source.len(),
+        }
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), Error<'a>> {
+        let start = self.position;
+        let mut depth = 0;
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("/*") {
+                depth += 1;
+                self.position += 2;
+            } else if rest.starts_with("*/") {
+                depth -= 1;
+                self.position += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if let Some(c) = self.peek() {
+                self.position += c.len_utf8();
+            } else {
+                return Err(self.error_at(start, ErrorKind::UnterminatedComment));
+            }
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, kind: ErrorKind<'a>) -> Error<'a> {
+        self.error_at(self.position, kind)
+    }
+
+    fn error_at(&self, position: usize, kind: ErrorKind<'a>) -> Error<'a> {
+        Error { position, kind }
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    !(c.is_whitespace()
+        || matches!(
+            c,
+            '\\' | '/' | '(' | ')' | '{' | '}' | ';' | '[' | ']' | '=' | '"'
+        ))
+}
+
+fn parse_bare_value(text: &str) -> Option<Value<'_>> {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        let mut value: i64 = 0;
+        for c in digits.chars().filter(|c| *c != '_') {
+            let digit = c.to_digit(10)?;
+            value = value.checked_mul(10)?.checked_add(digit as i64)?;
+        }
+        Some(Value::Integer(if text.starts_with('-') {
+            -value
+        } else {
+            value
+        }))
+    } else if text.starts_with('#') {
+        None
+    } else {
+        Some(Value::String(text))
+    }
+}
+
+/// Tracks which of a fixed set of child nodes have been seen.
+struct SeenNodes {
+    names: &'static [&'static str],
+    seen: u32,
+}
+
+impl SeenNodes {
+    fn new(names: &'static [&'static str]) -> Self {
+        SeenNodes { names, seen: 0 }
+    }
+
+    /// Returns the index of the node within `names`
+    fn visit<'a>(&mut self, node: &Node<'a>) -> Result<usize, Error<'a>> {
+        let Some(index) = self.names.iter().position(|name| *name == node.name) else {
+            return Err(node.error(ErrorKind::UnknownNode {
+                name: node.name,
+                expected: self.names,
+            }));
+        };
+        if self.seen & (1 << index) != 0 {
+            return Err(node.error(ErrorKind::DuplicateNode(node.name)));
+        }
+        self.seen |= 1 << index;
+        Ok(index)
+    }
+
+    fn finish<'a>(&self, position: usize) -> Result<(), Error<'a>> {
+        for (i, name) in self.names.iter().enumerate() {
+            if self.seen & (1 << i) == 0 {
+                return Err(Error {
+                    position,
+                    kind: ErrorKind::MissingNode(name),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse config.kdl into a [`Config`], matching the behaviour of the flash tool's config parsing.
+pub fn parse_config(source: &str) -> Result<Config, Error<'_>> {
+    let mut parser = Parser::new(source);
+    let mut config = Config::default();
+    let mut seen = SeenNodes::new(&["version", "profiles"]);
+    while let Some(node) = parser.next_node()? {
+        match seen.visit(&node)? {
+            0 => {
+                let version = node.integer()?;
+                if version != CONFIG_VERSION as i64 {
+                    return Err(node.error(ErrorKind::UnsupportedVersion(version)));
+                }
+                config.version = CONFIG_VERSION;
+            }
+            _ => {
+                node.expect_children()?;
+                while let Some(item) = parser.next_node()? {
+                    let profile = parse_profile(&mut parser, &item)?;
+                    let max = config.profiles.capacity();
+                    config
+                        .profiles
+                        .try_push(profile)
+                        .map_err(|_| item.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+        }
+    }
+    seen.finish(source.len())?;
+    Ok(config)
+}
+
+fn parse_profile<'a>(parser: &mut Parser<'a>, item: &Node<'a>) -> Result<Profile, Error<'a>> {
+    expect_list_item(item)?;
+    let mut profile = Profile::default();
+    let mut seen = SeenNodes::new(&[
+        "activation-combination",
+        "logic",
+        "socd",
+        "buttons",
+        "coordinates",
+        "overlays",
+        "ledgedash-socd-override-ms",
+        "triggers",
+        "cstick-mode",
+        "crouch-walk-prevention",
+        "travel-time",
+        "ruleset",
+        "chords",
+        "layers",
+        "tap-holds",
+        "latches",
+        "activation-hold-ms",
+    ]);
+    // The coordinate names depend on the logic, which may come later in the profile.
+    // Stores the name and position of each override for reporting errors.
+    let mut coordinates: ArrayVec<(&'a str, usize, Coordinate), MAX_COORDINATE_OVERRIDES> =
+        ArrayVec::new();
+    // Trigger levels also default to the logic's values, indexed by `TriggerLevels::NAMES`.
+    let mut trigger_levels = [None; TriggerLevels::NAMES.len()];
+    // Violations are reported at the `ruleset` node, since the whole profile is needed to check it.
+    let mut ruleset_position = item.position;
+    while let Some(node) = parser.next_node()? {
+        match seen.visit(&node)? {
+            0 => {
+                node.expect_children()?;
+                while let Some(item) = parser.next_node()? {
+                    expect_list_item(&item)?;
+                    let button = item.enum_value(&PHYSICAL_BUTTONS)?;
+                    let max = profile.activation_combination.capacity();
+                    profile
+                        .activation_combination
+                        .try_push(button)
+                        .map_err(|_| item.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+            1 => profile.logic = node.enum_value(&BASE_LOGICS)?,
+            2 => {
+                node.expect_children()?;
+                let mut seen = SeenNodes::new(SocdTypes::NAMES);
+                while let Some(child) = parser.next_node()? {
+                    let index = seen.visit(&child)?;
+                    let socd = child.enum_value(&SOCD_TYPES)?;
+                    if let Some(value) = profile.socd.get_mut(SocdTypes::NAMES[index]) {
+                        *value = socd;
+                    }
+                }
+                seen.finish(node.position)?;
+            }
+            3 => {
+                node.expect_children()?;
+                profile.buttons = parse_buttons(parser, &node)?;
+            }
+            4 => {
+                node.expect_children()?;
+                while let Some(child) = parser.next_node()? {
+                    let coordinate = child.coordinate()?;
+                    if coordinates.iter().any(|(name, _, _)| *name == child.name) {
+                        return Err(child.error(ErrorKind::DuplicateNode(child.name)));
+                    }
+                    let max = coordinates.capacity();
+                    coordinates
+                        .try_push((child.name, child.position, coordinate))
+                        .map_err(|_| child.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+            5 => {
+                node.expect_children()?;
+                while let Some(item) = parser.next_node()? {
+                    let overlay = parse_overlay(parser, &item)?;
+                    let max = profile.overlays.capacity();
+                    profile
+                        .overlays
+                        .try_push(overlay)
+                        .map_err(|_| item.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+            6 => profile.ledgedash_socd_override_ms = node.unsigned(u32::MAX)?,
+            7 => {
+                node.expect_children()?;
+                let mut seen = SeenNodes::new(TriggerLevels::NAMES);
+                while let Some(child) = parser.next_node()? {
+                    let index = seen.visit(&child)?;
+                    trigger_levels[index] = Some(child.unsigned(u8::MAX as u32)? as u8);
+                }
+            }
+            8 => profile.cstick_mode = node.enum_value(&CSTICK_MODES)?,
+            9 => {
+                node.expect_children()?;
+                let mut seen = SeenNodes::new(&["stick"]);
+                while let Some(child) = parser.next_node()? {
+                    seen.visit(&child)?;
+                    profile.crouch_walk_prevention = Some(child.coordinate()?);
+                }
+            }
+            10 => {
+                node.expect_children()?;
+                let mut seen = SeenNodes::new(&["stick-micros", "cstick-micros"]);
+                while let Some(child) = parser.next_node()? {
+                    let index = seen.visit(&child)?;
+                    let micros = child.unsigned(u32::MAX)?;
+                    match index {
+                        0 => profile.travel_time.stick_micros = micros,
+                        _ => profile.travel_time.cstick_micros = micros,
+                    }
+                }
+                seen.finish(node.position)?;
+            }
+            11 => {
+                profile.ruleset = node.enum_value(&RULESETS)?;
+                ruleset_position = node.position;
+            }
+            12 => {
+                node.expect_children()?;
+                while let Some(item) = parser.next_node()? {
+                    let chord = parse_chord(parser, &item)?;
+                    let max = profile.chords.capacity();
+                    profile
+                        .chords
+                        .try_push(chord)
+                        .map_err(|_| item.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+            13 => {
+                node.expect_children()?;
+                // Names are only used to tell layers apart in config.kdl, but must still be unique.
+                let mut names: ArrayVec<&str, 4> = ArrayVec::new();
+                while let Some(item) = parser.next_node()? {
+                    let (name, layer) = parse_layer(parser, &item)?;
+                    if names.contains(&name) {
+                        return Err(item.error(ErrorKind::DuplicateNode(name)));
+                    }
+                    let max = profile.layers.capacity();
+                    profile
+                        .layers
+                        .try_push(layer)
+                        .map_err(|_| item.error(ErrorKind::TooManyListItems { max }))?;
+                    names.push(name);
+                }
+            }
+            14 => {
+                node.expect_children()?;
+                while let Some(item) = parser.next_node()? {
+                    let (name, tap_hold) = parse_tap_hold(parser, &item)?;
+                    if profile
+                        .tap_holds
+                        .iter()
+                        .any(|existing| existing.button == tap_hold.button)
+                    {
+                        return Err(item.error(ErrorKind::DuplicateNode(name)));
+                    }
+                    let max = profile.tap_holds.capacity();
+                    profile
+                        .tap_holds
+                        .try_push(tap_hold)
+                        .map_err(|_| item.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+            15 => {
+                node.expect_children()?;
+                let mut seen = SeenNodes::new(LogicalButton::NAMES);
+                while let Some(child) = parser.next_node()? {
+                    let index = seen.visit(&child)?;
+                    let latch = Latch {
+                        logical: LogicalButton::VALUES[index],
+                        kind: child.enum_value(&LATCH_KINDS)?,
+                    };
+                    let max = profile.latches.capacity();
+                    profile
+                        .latches
+                        .try_push(latch)
+                        .map_err(|_| child.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+            _ => profile.activation_hold_ms = node.unsigned(u32::MAX)?,
+        }
+    }
+    seen.finish(item.position)?;
+
+    profile.triggers = TriggerLevels::new(profile.logic);
+    for (name, level) in TriggerLevels::NAMES.iter().zip(trigger_levels) {
+        if let (Some(value), Some(level)) = (profile.triggers.get_mut(name), level) {
+            *value = level;
+        }
+    }
+
+    profile.coordinates = Coordinates::new(profile.logic);
+    for (name, position, coordinate) in coordinates {
+        let Some(value) = profile.coordinates.get_mut(name) else {
+            return Err(Error {
+                position,
+                kind: ErrorKind::UnknownNode {
+                    name,
+                    expected: profile.logic.coordinate_names(),
+                },
+            });
+        };
+        *value = coordinate;
+    }
+
+    if let Some(violation) = profile.ruleset_violation() {
+        return Err(Error {
+            position: ruleset_position,
+            kind: ErrorKind::RulesetViolation {
+                ruleset: profile.ruleset.name(),
+                violation,
+            },
+        });
+    }
+    Ok(profile)
+}
+
+/// Parses an overlay of the form `- <condition>... { stick <x> <y>; cstick <x> <y> }`
+fn parse_overlay<'a>(parser: &mut Parser<'a>, item: &Node<'a>) -> Result<Overlay, Error<'a>> {
+    expect_list_item(item)?;
+    if !item.has_children {
+        return Err(item.error(ErrorKind::ExpectedChildren(item.name)));
+    }
+    let mut overlay = Overlay::default();
+    for argument in &item.arguments {
+        let name = match argument {
+            Value::String(name) => *name,
+            // Report the node name since there is no text to report for non string values
+            Value::Integer(_) => item.name,
+        };
+        let Some(index) = OVERLAY_CONDITIONS.names.iter().position(|x| *x == name) else {
+            return Err(item.error(ErrorKind::UnknownValue {
+                value: name,
+                expected: OVERLAY_CONDITIONS.names,
+            }));
+        };
+        let max = overlay.conditions.capacity();
+        overlay
+            .conditions
+            .try_push(OVERLAY_CONDITIONS.values[index])
+            .map_err(|_| item.error(ErrorKind::TooManyOverlayConditions { max }))?;
+    }
+
+    let mut seen = SeenNodes::new(&["stick", "cstick"]);
+    while let Some(node) = parser.next_node()? {
+        let index = seen.visit(&node)?;
+        let coordinate = Some(node.coordinate()?);
+        match index {
+            0 => overlay.stick = coordinate,
+            _ => overlay.cstick = coordinate,
+        }
+    }
+    if overlay.stick.is_none() && overlay.cstick.is_none() {
+        return Err(item.error(ErrorKind::EmptyOverlay));
+    }
+    Ok(overlay)
+}
+
+/// Parses a chord of the form `- <physical>... { button <logical>; members <forward|consume> }`
+fn parse_chord<'a>(parser: &mut Parser<'a>, item: &Node<'a>) -> Result<Chord, Error<'a>> {
+    expect_list_item(item)?;
+    if !item.has_children {
+        return Err(item.error(ErrorKind::ExpectedChildren(item.name)));
+    }
+    let mut chord = Chord::default();
+    let max = chord.buttons.capacity();
+    for argument in &item.arguments {
+        let button = item.enum_argument(*argument, &PHYSICAL_BUTTONS)?;
+        if button == PhysicalButton::None {
+            continue;
+        }
+        chord
+            .buttons
+            .try_push(button)
+            .map_err(|_| item.error(ErrorKind::ChordButtons { max }))?;
+    }
+    if chord.buttons.len() < 2 {
+        return Err(item.error(ErrorKind::ChordButtons { max }));
+    }
+
+    let mut seen = SeenNodes::new(&["button", "members"]);
+    let mut logical = None;
+    while let Some(node) = parser.next_node()? {
+        match seen.visit(&node)? {
+            0 => logical = Some(node.enum_value(&LOGICAL_BUTTONS)?),
+            _ => chord.consume = node.enum_value(&CHORD_MEMBERS)?,
+        }
+    }
+    chord.logical = logical.ok_or_else(|| item.error(ErrorKind::MissingNode("button")))?;
+    Ok(chord)
+}
+
+/// Parses a layer of the form `- <name> { activation <hold|toggle> <physical>; buttons { <logical> <physical>... } }`,
+/// returning its name along with the layer.
+fn parse_layer<'a>(
+    parser: &mut Parser<'a>,
+    item: &Node<'a>,
+) -> Result<(&'a str, Layer), Error<'a>> {
+    expect_list_item(item)?;
+    let [Value::String(name)] = item.arguments.as_slice() else {
+        return Err(item.error(ErrorKind::ExpectedSingleArgument(item.name)));
+    };
+    if !item.has_children {
+        return Err(item.error(ErrorKind::ExpectedChildren(item.name)));
+    }
+
+    let mut activation = None;
+    let mut bindings = ArrayVec::new();
+    let mut seen = SeenNodes::new(&["activation", "buttons"]);
+    while let Some(node) = parser.next_node()? {
+        match seen.visit(&node)? {
+            0 => {
+                if node.has_children {
+                    return Err(node.error(ErrorKind::UnexpectedChildren(node.name)));
+                }
+                let [kind, button] = node.arguments.as_slice() else {
+                    return Err(node.error(ErrorKind::ExpectedLayerActivation));
+                };
+                let button = match node.enum_argument(*button, &PHYSICAL_BUTTONS)? {
+                    PhysicalButton::None => {
+                        return Err(node.error(ErrorKind::ExpectedLayerActivation));
+                    }
+                    button => button,
+                };
+                activation = Some(node.enum_argument(*kind, &LAYER_ACTIVATIONS)?(button));
+            }
+            _ => {
+                node.expect_children()?;
+                // Only the logical buttons the layer overrides are listed.
+                let mut seen = SeenNodes::new(LogicalButton::NAMES);
+                while let Some(child) = parser.next_node()? {
+                    let index = seen.visit(&child)?;
+                    let max = bindings.capacity();
+                    bindings
+                        .try_push(LayerBinding {
+                            logical: LogicalButton::VALUES[index],
+                            binding: parse_binding(&child)?,
+                        })
+                        .map_err(|_| child.error(ErrorKind::TooManyListItems { max }))?;
+                }
+            }
+        }
+    }
+    seen.finish(item.position)?;
+    let activation = activation.ok_or_else(|| item.error(ErrorKind::MissingNode("activation")))?;
+    Ok((
+        name,
+        Layer {
+            activation,
+            bindings,
+        },
+    ))
+}
+
+/// Parses a tap-hold of the form
+/// `- <physical> { tap <logical>; hold <logical>; tapping-term-ms <ms>; on-other-key-press <wait|hold> }`,
+/// returning the name of its physical button along with the tap-hold.
+fn parse_tap_hold<'a>(
+    parser: &mut Parser<'a>,
+    item: &Node<'a>,
+) -> Result<(&'a str, TapHold), Error<'a>> {
+    expect_list_item(item)?;
+    let [argument @ Value::String(name)] = item.arguments.as_slice() else {
+        return Err(item.error(ErrorKind::ExpectedTapHoldButton));
+    };
+    if !item.has_children {
+        return Err(item.error(ErrorKind::ExpectedChildren(item.name)));
+    }
+    let mut tap_hold = TapHold {
+        button: match item.enum_argument(*argument, &PHYSICAL_BUTTONS)? {
+            PhysicalButton::None => return Err(item.error(ErrorKind::ExpectedTapHoldButton)),
+            button => button,
+        },
+        tap_ms: TapHold::DEFAULT_TAP_MS,
+        ..Default::default()
+    };
+
+    let mut seen = SeenNodes::new(&[
+        "tap",
+        "hold",
+        "tapping-term-ms",
+        "on-other-key-press",
+        "tap-ms",
+    ]);
+    let mut tap = None;
+    let mut hold = None;
+    let mut tapping_term_ms = None;
+    while let Some(node) = parser.next_node()? {
+        match seen.visit(&node)? {
+            0 => tap = Some(node.enum_value(&LOGICAL_BUTTONS)?),
+            1 => hold = Some(node.enum_value(&LOGICAL_BUTTONS)?),
+            2 => tapping_term_ms = Some(node.unsigned(u32::MAX)?),
+            3 => tap_hold.hold_on_other_key_press = node.enum_value(&TAP_HOLD_OTHER_KEY_PRESS)?,
+            _ => tap_hold.tap_ms = node.unsigned(u32::MAX)?,
+        }
+    }
+    tap_hold.tap = tap.ok_or_else(|| item.error(ErrorKind::MissingNode("tap")))?;
+    tap_hold.hold = hold.ok_or_else(|| item.error(ErrorKind::MissingNode("hold")))?;
+    tap_hold.tapping_term_ms =
+        tapping_term_ms.ok_or_else(|| item.error(ErrorKind::MissingNode("tapping-term-ms")))?;
+    Ok((*name, tap_hold))
+}
+
+fn parse_buttons<'a>(
+    parser: &mut Parser<'a>,
+    parent: &Node<'a>,
+) -> Result<LogicalButtonToPhysicalButton, Error<'a>> {
+    let mut buttons = LogicalButtonToPhysicalButton::default();
+    let mut seen = SeenNodes::new(&BUTTON_NAMES);
+    while let Some(node) = parser.next_node()? {
+        let index = seen.visit(&node)?;
+        *logical_button_field(&mut buttons, index) = parse_binding(&node)?;
+    }
+    seen.finish(parent.position)?;
+    Ok(buttons)
+}
+
+/// Parses a binding of the form `<logical> <physical>...`, where `none` binds no physical buttons.
+fn parse_binding<'a>(node: &Node<'a>) -> Result<Binding, Error<'a>> {
+    if node.has_children {
+        return Err(node.error(ErrorKind::UnexpectedChildren(node.name)));
+    }
+    if node.arguments.is_empty() {
+        return Err(node.error(ErrorKind::ExpectedButtons(node.name)));
+    }
+    let mut binding = Binding::default();
+    for argument in &node.arguments {
+        let button = node.enum_argument(*argument, &PHYSICAL_BUTTONS)?;
+        if button == PhysicalButton::None {
+            continue;
+        }
+        if binding.contains(button) {
+            return Err(node.error(ErrorKind::DuplicateBinding(node.name)));
+        }
+        let max = binding.buttons.capacity();
+        binding.buttons.try_push(button).map_err(|_| {
+            node.error(ErrorKind::TooManyBindings {
+                name: node.name,
+                max,
+            })
+        })?;
+    }
+    Ok(binding)
+}
+
+fn expect_list_item<'a>(node: &Node<'a>) -> Result<(), Error<'a>> {
+    if node.name != "-" {
+        return Err(node.error(ErrorKind::ExpectedListItem(node.name)));
+    }
+    Ok(())
+}
+
+/// The kebab-case names of an enum's variants, matching the names used by the flash tool.
+struct EnumValues<T: 'static> {
+    names: &'static [&'static str],
+    values: &'static [T],
+}
+
+const BASE_LOGICS: EnumValues<BaseLogic> = EnumValues {
+    names: &["project-plus", "rivals2", "ultimate", "melee", "rivals1"],
+    values: &[
+        BaseLogic::ProjectPlus,
+        BaseLogic::Rivals2,
+        BaseLogic::Ultimate,
+        BaseLogic::Melee,
+        BaseLogic::Rivals1,
+    ],
+};
+
+const OVERLAY_CONDITIONS: EnumValues<OverlayCondition> = EnumValues {
+    names: OverlayCondition::NAMES,
+    values: OverlayCondition::VALUES,
+};
+
+const SOCD_TYPES: EnumValues<SocdType> = EnumValues {
+    names: &[
+        "second-input-priority",
+        "second-input-priority-no-reactivation",
+        "first-input-priority",
+        "neutral",
+        "up-or-left-priority",
+        "down-or-right-priority",
+    ],
+    values: &[
+        SocdType::SecondInputPriority,
+        SocdType::SecondInputPriorityNoReactivation,
+        SocdType::FirstInputPriority,
+        SocdType::Neutral,
+        SocdType::UpOrLeftPriority,
+        SocdType::DownOrRightPriority,
+    ],
+};
+
+const RULESETS: EnumValues<Ruleset> = EnumValues {
+    names: Ruleset::NAMES,
+    values: Ruleset::VALUES,
+};
+
+const CSTICK_MODES: EnumValues<CstickMode> = EnumValues {
+    names: &["smash", "tilt-unless-mod-x", "tilt-unless-mod-y"],
+    values: &[
+        CstickMode::Smash,
+        CstickMode::TiltUnlessModX,
+        CstickMode::TiltUnlessModY,
+    ],
+};
+
+const PHYSICAL_BUTTONS: EnumValues<PhysicalButton> = EnumValues {
+    names: PhysicalButton::NAMES,
+    values: PhysicalButton::VALUES,
+};
+
+const LOGICAL_BUTTONS: EnumValues<LogicalButton> = EnumValues {
+    names: LogicalButton::NAMES,
+    values: LogicalButton::VALUES,
+};
+
+/// Whether the members of a chord are consumed.
+const CHORD_MEMBERS: EnumValues<bool> = EnumValues {
+    names: &["forward", "consume"],
+    values: &[false, true],
+};
+
+const LAYER_ACTIVATIONS: EnumValues<fn(PhysicalButton) -> LayerActivation> = EnumValues {
+    names: LayerActivation::NAMES,
+    values: &[LayerActivation::Hold, LayerActivation::Toggle],
+};
+
+/// Whether a tap-hold decides on hold when another button is pressed before its tapping term.
+const TAP_HOLD_OTHER_KEY_PRESS: EnumValues<bool> = EnumValues {
+    names: &["wait", "hold"],
+    values: &[false, true],
+};
+
+const LATCH_KINDS: EnumValues<LatchKind> = EnumValues {
+    names: LatchKind::NAMES,
+    values: LatchKind::VALUES,
+};
+
+const BUTTON_NAMES: [&str; 24] = [
+    "mod-x",
+    "mod-y",
+    "start",
+    "a",
+    "b",
+    "x",
+    "y",
+    "z",
+    "dpad-up",
+    "dpad-down",
+    "dpad-left",
+    "dpad-right",
+    "l-digital",
+    "r-digital",
+    "l-analog",
+    "r-analog",
+    "stick-left",
+    "stick-right",
+    "stick-up",
+    "stick-down",
+    "cstick-left",
+    "cstick-right",
+    "cstick-up",
+    "cstick-down",
+];
+
+/// The field of `buttons` named by `BUTTON_NAMES[index]`
+fn logical_button_field(buttons: &mut LogicalButtonToPhysicalButton, index: usize) -> &mut Binding {
+    match index {
+        0 => &mut buttons.mod_x,
+        1 => &mut buttons.mod_y,
+        2 => &mut buttons.start,
+        3 => &mut buttons.a,
+        4 => &mut buttons.b,
+        5 => &mut buttons.x,
+        6 => &mut buttons.y,
+        7 => &mut buttons.z,
+        8 => &mut buttons.dpad_up,
+        9 => &mut buttons.dpad_down,
+        10 => &mut buttons.dpad_left,
+        11 => &mut buttons.dpad_right,
+        12 => &mut buttons.l_digital,
+        13 => &mut buttons.r_digital,
+        14 => &mut buttons.l_analog,
+        15 => &mut buttons.r_analog,
+        16 => &mut buttons.stick_left,
+        17 => &mut buttons.stick_right,
+        18 => &mut buttons.stick_up,
+        19 => &mut buttons.stick_down,
+        20 => &mut buttons.cstick_left,
+        21 => &mut buttons.cstick_right,
+        22 => &mut buttons.cstick_up,
+        _ => &mut buttons.cstick_down,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Parser, Value, parse_config};
+    use rukaibox_config::{BaseLogic, CONFIG_VERSION, TapHold};
+
+    const CONFIG_KDL: &str = include_str!("../../config.kdl");
+
+    /// Every node in `source` as its name and arguments, with `{` and `}` marking where children start and end.
+    fn nodes(source: &str) -> Vec<String> {
+        fn visit(parser: &mut Parser, nodes: &mut Vec<String>) {
+            while let Some(node) = parser.next_node().unwrap() {
+                let mut text = node.name.to_owned();
+                for argument in &node.arguments {
+                    match argument {
+                        Value::String(value) => text.push_str(&format!(" {value:?}")),
+                        Value::Integer(value) => text.push_str(&format!(" {value}")),
+                    }
+                }
+                nodes.push(text);
+                if node.has_children {
+                    nodes.push("{".to_owned());
+                    visit(parser, nodes);
+                    nodes.push("}".to_owned());
+                }
+            }
+        }
+        let mut nodes = vec![];
+        visit(&mut Parser::new(source), &mut nodes);
+        nodes
+    }
+
+    /// The text the error points at up to the end of its line, and the error message.
+    fn error(source: &str) -> (&str, String) {
+        let Err(error) = parse_config(source) else {
+            panic!("expected an error");
+        };
+        let rest = &source[error.position..];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        (line, error.kind.to_string())
+    }
+
+    #[test]
+    fn config_kdl() {
+        let config = parse_config(CONFIG_KDL).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        let logic: Vec<_> = config
+            .profiles
+            .iter()
+            .map(|profile| profile.logic)
+            .collect();
+        assert_eq!(
+            logic,
+            [
+                BaseLogic::ProjectPlus,
+                BaseLogic::Rivals2,
+                BaseLogic::Ultimate
+            ]
+        );
+        assert_eq!(config.profiles[0].ledgedash_socd_override_ms, 100);
+    }
+
+    #[test]
+    fn tap_ms() {
+        let tap_holds = |tap_ms: &str| {
+            let source = CONFIG_KDL.replacen(
+                "tap-holds {\n        }",
+                &format!(
+                    "tap-holds {{\n - left-thumb-left {{\n tap dpad-up; hold mod-x; tapping-term-ms 200; {tap_ms}\n }}\n }}"
+                ),
+                1,
+            );
+            parse_config(&source).unwrap().profiles[0].tap_holds[0].tap_ms
+        };
+        assert_eq!(tap_holds(""), TapHold::DEFAULT_TAP_MS);
+        assert_eq!(tap_holds("tap-ms 50"), 50);
+    }
+
+    #[test]
+    fn syntax() {
+        assert_eq!(
+            nodes(
+                "\u{FEFF}a 1 -2 +3 1_000 \"b c\" d; e /* f */ g\n\
+                 // h\n\
+                 i \\\n  j /- k l /- {\n  m\n}\n\
+                 /* n /* o */ p */\n\
+                 q { r; s { }\n}"
+            ),
+            [
+                "a 1 -2 3 1000 \"b c\" \"d\"",
+                "e \"g\"",
+                "i \"j\" \"l\"",
+                "q",
+                "{",
+                "r",
+                "s",
+                "{",
+                "}",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        for (source, expected) in [
+            ("a \"b", ("\"b", "String is missing its closing `\"`")),
+            (
+                "a \"b\\nc\"",
+                (
+                    "\\nc\"",
+                    "Escapes in strings are not supported on the controller, use the flash tool instead",
+                ),
+            ),
+            ("a /* b", ("/* b", "Comment is missing its closing `*/`")),
+            ("a b=1", ("=1", "Properties are not supported")),
+            ("a (b)1", ("(b)1", "Type annotations are not supported")),
+            ("a #true", ("#true", "\"#true\" is not a valid value")),
+            (
+                "profiles {\n",
+                ("", "Unexpected end of file, is there a missing `}`?"),
+            ),
+            ("a\"b\"", ("\"b\"", "Unexpected character '\"'")),
+        ] {
+            let (at, message) = error(source);
+            assert_eq!((at, message.as_str()), expected, "{source:?}");
+        }
+    }
+
+    #[test]
+    fn config_errors() {
+        let source = CONFIG_KDL.replacen(&format!("version {CONFIG_VERSION}"), "version 1", 1);
+        assert_eq!(
+            error(&source),
+            (
+                "version 1",
+                format!(
+                    "config.kdl is version 1 but the controller requires version {CONFIG_VERSION}. \
+                     Upgrade the config by running the flash tool with --upgrade-config"
+                )
+            )
+        );
+
+        for ((from, to), expected) in [
+            (
+                (
+                    "ledgedash-socd-override-ms 100",
+                    "ledgedash-socd-override-ms -1",
+                ),
+                (
+                    "ledgedash-socd-override-ms -1",
+                    "Node `ledgedash-socd-override-ms` must be between 0 and 4294967295",
+                ),
+            ),
+            (
+                ("logic project-plus", "logic melee-2024"),
+                (
+                    "logic melee-2024",
+                    "Unknown value `melee-2024`, consider one of project-plus, rivals2, ultimate, melee, rivals1",
+                ),
+            ),
+            (
+                (
+                    "logic project-plus",
+                    "logic project-plus\n        logic melee",
+                ),
+                ("logic melee", "Node `logic` is defined twice"),
+            ),
+            (
+                (
+                    "coordinates {\n        }",
+                    "coordinates {\n            max 1 200\n        }",
+                ),
+                (
+                    "max 1 200",
+                    "Coordinate `max` values must be between 0 and 127",
+                ),
+            ),
+            (
+                ("r-digital right-index", "r-digital right-index right-index"),
+                (
+                    "r-digital right-index right-index",
+                    "Button `r-digital` lists the same physical button more than once",
+                ),
+            ),
+            (
+                ("ruleset unrestricted", "ruleset melee-2024"),
+                (
+                    "ruleset melee-2024",
+                    "The profile logic is not allowed in ruleset melee-2024",
+                ),
+            ),
+        ] {
+            assert!(CONFIG_KDL.contains(from), "{from:?}");
+            let source = CONFIG_KDL.replacen(from, to, 1);
+            let (at, message) = error(&source);
+            assert_eq!((at, message.as_str()), expected, "{to:?}");
+        }
+    }
+}
@@ -1,23 +1,154 @@
-use rkyv::{rancor::Failure, util::Align};
-use rukaibox_config::{ArchivedConfig, CONFIG_OFFSET, CONFIG_SIZE, Config, RP2040_FLASH_OFFSET};
+use crate::flash;
+use core::mem::MaybeUninit;
+use rkyv::{
+    api::low::to_bytes_in_with_alloc,
+    rancor::Failure,
+    ser::{allocator::SubAllocator, writer::Buffer},
+    util::Align,
+};
+use rukaibox_config::{
+    ArchivedConfig, CONFIG_HEADER_SIZE, CONFIG_OFFSET, CONFIG_SCRATCH_SIZE, CONFIG_SIZE, Config,
+    REMAPPED_CONFIG_OFFSET, RP2040_FLASH_OFFSET, SCHEMA_FINGERPRINT,
+};
+#[cfg(feature = "usb-drive")]
+use rukaibox_config::{CONFIG_KDL_OFFSET, CONFIG_KDL_SIZE};
 
-// TODO: store in heap instead, apparently only 2kb of stack o.0
+// The stack is only 2 KiB, so the stored config is read and written through these instead.
+static mut BYTES: Align<[u8; CONFIG_SIZE]> = Align([0; CONFIG_SIZE]);
+static mut SCRATCH: [MaybeUninit<u8>; CONFIG_SCRATCH_SIZE] =
+    [MaybeUninit::uninit(); CONFIG_SCRATCH_SIZE];
 
-pub fn load() -> Result<Config, Failure> {
-    let bytes = load_config_bytes_from_flash();
+pub enum LoadError {
+    /// The config could not be deserialized, it may be missing or corrupted.
+    Invalid,
+    /// The config was created for a firmware with a different config schema.
+    SchemaMismatch,
+    /// A profile breaks its [`rukaibox_config::Ruleset`].
+    RulesetViolation,
+}
+
+/// Load the remapped config if there is one, otherwise the flashed config.
+///
+/// Only a missing or corrupted remapped config falls back to the flashed config.
+/// A remapped config that breaks a ruleset or has a different schema is reported, so remaps can't bypass either check.
+pub fn load() -> Result<Config, LoadError> {
+    match load_at(REMAPPED_CONFIG_OFFSET) {
+        Err(LoadError::Invalid) => load_at(CONFIG_OFFSET),
+        result => result,
+    }
+}
+
+/// Load the flashed config, ignoring any remapped config.
+pub fn load_flashed() -> Result<Config, LoadError> {
+    load_at(CONFIG_OFFSET)
+}
+
+fn load_at(offset: usize) -> Result<Config, LoadError> {
+    let bytes = load_config_bytes_from_flash(offset);
+    // Erased flash reads as 0xFF, so nothing has been stored here.
+    if bytes[..CONFIG_HEADER_SIZE].iter().all(|byte| *byte == 0xFF) {
+        return Err(LoadError::Invalid);
+    }
     let size = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
-    let archive = rkyv::api::low::access::<ArchivedConfig, Failure>(&(&*bytes)[4..4 + size])?;
-    rkyv::api::low::deserialize::<_, Failure>(archive)
+    let fingerprint = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    if fingerprint != SCHEMA_FINGERPRINT {
+        return Err(LoadError::SchemaMismatch);
+    }
+
+    if size > CONFIG_SIZE - CONFIG_HEADER_SIZE {
+        return Err(LoadError::Invalid);
+    }
+
+    let archive = rkyv::api::low::access::<ArchivedConfig, Failure>(
+        &(&*bytes)[CONFIG_HEADER_SIZE..CONFIG_HEADER_SIZE + size],
+    )
+    .map_err(|_| LoadError::Invalid)?;
+    let config: Config =
+        rkyv::api::low::deserialize::<_, Failure>(archive).map_err(|_| LoadError::Invalid)?;
+
+    // The flash tool and USB drive mode already refuse such configs, but the rules must hold however the config got here.
+    if config
+        .profiles
+        .iter()
+        .any(|profile| profile.ruleset_violation().is_some())
+    {
+        return Err(LoadError::RulesetViolation);
+    }
+    Ok(config)
+}
+
+/// Write the config to flash in the same format as the flash tool, replacing the flashed config and any remaps.
+#[cfg(feature = "usb-drive")]
+pub fn store(config: &Config) -> Result<(), Failure> {
+    store_at(config, CONFIG_OFFSET)?;
+    clear_remapped();
+    Ok(())
 }
 
-fn load_config_bytes_from_flash() -> Align<[u8; CONFIG_SIZE]> {
-    let mut data = Align([0; CONFIG_SIZE]);
+/// Write a config containing bindings changed on the controller, it is used instead of the flashed config until cleared.
+pub fn store_remapped(config: &Config) -> Result<(), Failure> {
+    store_at(config, REMAPPED_CONFIG_OFFSET)
+}
+
+/// Remove the remapped config, so that the flashed config is used again.
+pub fn clear_remapped() {
+    flash::erase(REMAPPED_CONFIG_OFFSET, CONFIG_SIZE);
+}
+
+fn store_at(config: &Config, offset: usize) -> Result<(), Failure> {
+    // Safety: The statics are only referenced within a single call to `store_at` or `load_at`,
+    // which are never called from an interrupt, so these are the only references to them.
+    let bytes = unsafe { &mut *(&raw mut BYTES) };
+    let scratch = unsafe { &mut *(&raw mut SCRATCH) };
+    bytes.fill(0xFF);
+    let size = to_bytes_in_with_alloc::<_, _, Failure>(
+        config,
+        Buffer::from(&mut bytes[CONFIG_HEADER_SIZE..]),
+        SubAllocator::new(scratch),
+    )?
+    .len();
+    bytes[0..4].copy_from_slice(&(size as u32).to_be_bytes());
+    bytes[4..8].copy_from_slice(&SCHEMA_FINGERPRINT.to_be_bytes());
+    flash::write(offset, &bytes[..]);
+    Ok(())
+}
+
+/// Reads the stored config.kdl into `buffer` and returns its text.
+/// Returns None if no config.kdl was flashed alongside the config.
+#[cfg(feature = "usb-drive")]
+pub fn load_kdl(buffer: &mut [u8; CONFIG_KDL_SIZE]) -> Option<&str> {
+    read_flash(CONFIG_KDL_OFFSET, buffer);
+    let size = u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
+    // Erased flash reads as 0xFF so a missing config.kdl fails this check.
+    if size > CONFIG_KDL_SIZE - 4 {
+        return None;
+    }
+    core::str::from_utf8(&buffer[4..4 + size]).ok()
+}
+
+/// Write `buffer[4..4 + size]` to flash as the stored config.kdl.
+/// The rest of the buffer is overwritten.
+#[cfg(feature = "usb-drive")]
+pub fn store_kdl(buffer: &mut [u8; CONFIG_KDL_SIZE], size: usize) {
+    buffer[0..4].copy_from_slice(&(size as u32).to_be_bytes());
+    let end = (4 + size).next_multiple_of(flash::PAGE_SIZE);
+    buffer[4 + size..end].fill(0xFF);
+    flash::write(CONFIG_KDL_OFFSET, &buffer[..end]);
+}
+
+fn load_config_bytes_from_flash(offset: usize) -> &'static Align<[u8; CONFIG_SIZE]> {
+    // Safety: see `store_at`, the returned reference is dropped before `load_at` returns.
+    let data = unsafe { &mut *(&raw mut BYTES) };
+    read_flash(offset, &mut data[..]);
+    data
+}
+
+fn read_flash(offset: usize, data: &mut [u8]) {
     // Safety: This byte range is known to be valid flash memory on this device
     unsafe {
-        for i in 0..CONFIG_SIZE {
-            let address = (RP2040_FLASH_OFFSET + CONFIG_OFFSET + i) as *mut u8;
-            data[i] = core::ptr::read_volatile(address);
+        for (i, byte) in data.iter_mut().enumerate() {
+            let address = (RP2040_FLASH_OFFSET + offset + i) as *mut u8;
+            *byte = core::ptr::read_volatile(address);
         }
     }
-    data
 }
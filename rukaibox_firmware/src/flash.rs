@@ -0,0 +1,99 @@
+//! Writing to the flash that the firmware itself is running from.
+//!
+//! The bootrom flash functions disable XIP (execute in place) so nothing may run from flash while they are in use.
+//! To handle this, the function pointers are looked up ahead of time and the flash operations are run from RAM with interrupts disabled.
+//! Afterwards boot2 is rerun from a copy in RAM to restore the fast XIP mode that it configures.
+
+use rp2040_hal::rom_data;
+use rukaibox_config::RP2040_FLASH_OFFSET;
+
+pub const SECTOR_SIZE: usize = 4096;
+pub const PAGE_SIZE: usize = 256;
+
+const BOOT2_SIZE: usize = 256;
+
+struct FlashFunctions {
+    connect_internal_flash: extern "C" fn(),
+    flash_exit_xip: extern "C" fn(),
+    flash_range_erase: extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: extern "C" fn(),
+    boot2: extern "C" fn(),
+}
+
+/// Erase the sectors starting at `offset` bytes into flash and then program `data` into them.
+///
+/// `offset` must be a multiple of [`SECTOR_SIZE`] and the length of `data` must be a multiple of [`PAGE_SIZE`].
+/// `data` must be located in RAM since flash cannot be read while it is being written.
+pub fn write(offset: usize, data: &[u8]) {
+    assert!(offset.is_multiple_of(SECTOR_SIZE));
+    assert!(data.len().is_multiple_of(PAGE_SIZE));
+    erase_and_program(offset, data.len().next_multiple_of(SECTOR_SIZE), data);
+}
+
+/// Erase `len` bytes starting at `offset` bytes into flash.
+///
+/// Both `offset` and `len` must be a multiple of [`SECTOR_SIZE`].
+pub fn erase(offset: usize, len: usize) {
+    assert!(offset.is_multiple_of(SECTOR_SIZE));
+    assert!(len.is_multiple_of(SECTOR_SIZE));
+    erase_and_program(offset, len, &[]);
+}
+
+fn erase_and_program(offset: usize, erase_len: usize, data: &[u8]) {
+    let mut boot2 = [0u32; BOOT2_SIZE / 4];
+    // Safety: boot2 is located at the very start of flash
+    unsafe {
+        core::ptr::copy_nonoverlapping(
+            RP2040_FLASH_OFFSET as *const u8,
+            boot2.as_mut_ptr() as *mut u8,
+            BOOT2_SIZE,
+        );
+    }
+
+    let functions = FlashFunctions {
+        connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+        flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+        flash_range_erase: rom_data::flash_range_erase::ptr(),
+        flash_range_program: rom_data::flash_range_program::ptr(),
+        flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+        // Safety: boot2 is thumb code, so the address must have the lowest bit set.
+        boot2: unsafe {
+            core::mem::transmute::<usize, extern "C" fn()>(boot2.as_ptr() as usize + 1)
+        },
+    };
+
+    cortex_m::interrupt::free(|_| {
+        // Safety: interrupts are disabled and the function pointers all point to ROM or RAM.
+        unsafe {
+            write_from_ram(
+                offset as u32,
+                erase_len,
+                data.as_ptr(),
+                data.len(),
+                &functions,
+            )
+        }
+    });
+}
+
+/// Must not access flash in any way, including calling non-inlined functions, since XIP is disabled during this function.
+#[inline(never)]
+#[unsafe(link_section = ".data.ram_func")]
+unsafe fn write_from_ram(
+    offset: u32,
+    erase_len: usize,
+    data: *const u8,
+    data_len: usize,
+    functions: &FlashFunctions,
+) {
+    (functions.connect_internal_flash)();
+    (functions.flash_exit_xip)();
+    // Use 64 KiB block erases where possible, the ROM falls back to sector erases otherwise.
+    (functions.flash_range_erase)(offset, erase_len, 1 << 16, 0xd8);
+    if data_len > 0 {
+        (functions.flash_range_program)(offset, data, data_len);
+    }
+    (functions.flash_flush_cache)();
+    (functions.boot2)();
+}
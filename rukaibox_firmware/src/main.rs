@@ -2,11 +2,14 @@
 #![no_main]
 
 mod config;
+mod flash;
 mod input;
-mod profile;
-mod socd;
+mod remap;
+#[cfg(feature = "usb-drive")]
+mod usb_drive;
 
 use bsp::entry;
+use config::LoadError;
 use cortex_m::delay::Delay;
 use embedded_hal::digital::{InputPin, OutputPin};
 use input::ButtonInput;
@@ -20,14 +23,22 @@ use bsp::hal::{
     sio::Sio,
     watchdog::Watchdog,
 };
-use profile::MapProfile;
+use remap::{RemapEvent, RemapMode};
 use rp_pico as bsp;
+#[cfg(feature = "usb-drive")]
+use rp2040_hal::usb::UsbBus;
 use rp2040_hal::{
     Timer,
     gpio::{FunctionSio, Pin, PullDown, SioOutput, bank0::Gpio25},
     rom_data::reset_to_usb_boot,
 };
-use rukaibox_config::Config;
+use rukaibox_config::{Config, FirmwareInfo};
+use rukaibox_profile::{GamecubeInput, input::TimedInput, profile::MapProfile, time::Instant};
+
+/// Read by the flash tool to check that a config is compatible with this firmware before flashing only the config.
+#[unsafe(link_section = ".firmware_info")]
+#[used]
+static FIRMWARE_INFO: [u8; FirmwareInfo::SIZE] = FirmwareInfo::CURRENT.to_bytes();
 
 #[entry]
 fn main() -> ! {
@@ -107,21 +118,39 @@ fn main() -> ! {
         start,
     };
 
-    let Ok(config) = config::load() else {
-        // Failed to parse config, set 5s blinky for diagnostics
-        loop {
-            led_pin.set_high().unwrap();
-            delay.delay_ms(5000);
-            if input.start.is_low().unwrap_or(true) {
-                reset_to_usb_boot(0, 0);
-            }
+    // Holding the top right pinky button while plugging in enters USB drive mode for editing the config.
+    #[cfg(feature = "usb-drive")]
+    if input.right_pinky_2.is_low().unwrap_or(false) {
+        let usb_bus = UsbBus::new(
+            pac.USBCTRL_REGS,
+            pac.USBCTRL_DPRAM,
+            clocks.usb_clock,
+            true,
+            &mut pac.RESETS,
+        );
+        usb_drive::run(led_pin, usb_bus, &timer);
+    }
 
-            led_pin.set_low().unwrap();
-            delay.delay_ms(5000);
-            if input.start.is_low().unwrap_or(true) {
-                reset_to_usb_boot(0, 0);
-            }
+    let config = match config::load() {
+        Ok(config) => config,
+        // Failed to parse config, set 5s blinky for diagnostics
+        Err(LoadError::Invalid) => {
+            blink_error_forever(&mut led_pin, &mut delay, &mut input, &[5000, 5000])
         }
+        // Config was flashed for a different firmware version, set 3 quick blinks followed by a pause for diagnostics
+        Err(LoadError::SchemaMismatch) => blink_error_forever(
+            &mut led_pin,
+            &mut delay,
+            &mut input,
+            &[200, 200, 200, 200, 200, 2000],
+        ),
+        // A profile breaks its tournament ruleset, set 2 long blinks followed by a pause for diagnostics
+        Err(LoadError::RulesetViolation) => blink_error_forever(
+            &mut led_pin,
+            &mut delay,
+            &mut input,
+            &[1000, 500, 1000, 2000],
+        ),
     };
 
     let profile = MapProfile::new(config.profiles.first().unwrap());
@@ -136,7 +165,7 @@ fn main() -> ! {
                 &mut delay,
                 profile,
                 input,
-                &config,
+                config,
             );
         }
         Err(_pio) => {
@@ -145,6 +174,29 @@ fn main() -> ! {
     }
 }
 
+/// Blinks the LED with the given pattern of alternating on and off durations in ms.
+/// Allows flashing via the start button so the user can recover by flashing a new firmware and config.
+fn blink_error_forever(
+    led_pin: &mut Pin<Gpio25, FunctionSio<SioOutput>, PullDown>,
+    delay: &mut Delay,
+    input: &mut ButtonInput,
+    pattern: &[u32],
+) -> ! {
+    loop {
+        for (i, duration) in pattern.iter().enumerate() {
+            if i % 2 == 0 {
+                led_pin.set_high().unwrap();
+            } else {
+                led_pin.set_low().unwrap();
+            }
+            delay.delay_ms(*duration);
+            if input.start.is_low().unwrap_or(true) {
+                reset_to_usb_boot(0, 0);
+            }
+        }
+    }
+}
+
 fn run_gamecube_loop(
     mut led_pin: Pin<Gpio25, FunctionSio<SioOutput>, PullDown>,
     mut gamecube_controller: GamecubeController,
@@ -152,22 +204,83 @@ fn run_gamecube_loop(
     delay: &mut Delay,
     mut profile: MapProfile,
     mut input: ButtonInput,
-    config: &Config,
+    mut config: Config,
 ) -> ! {
     let mut counter = 0u32;
+    let mut profile_index = 0;
+    let mut remap = RemapMode::default();
+    let mut timed_input = TimedInput::default();
     loop {
         counter += 1;
-        if counter % 10 < 5 {
-            led_pin.set_high().unwrap();
-        } else {
-            led_pin.set_low().unwrap();
-        }
+        // Stay lit while a latch is keeping its button pressed, instead of the usual flicker.
+        let led_on = remap
+            .led(timer.get_counter().ticks())
+            .unwrap_or(profile.latched() || counter % 10 < 5);
+        led_pin.set_state(led_on.into()).unwrap();
 
         gamecube_controller.wait_for_poll_start(timer, delay);
-        let input_results = input.get_pin_state();
-        profile.change_profile(&input_results, config);
-        let report = profile.map_to_gamecube(&input_results);
-        gamecube_controller.respond_to_poll(timer, delay, report);
+        let now = Instant::from_micros(timer.get_counter().ticks());
+        timed_input.update(input.get_pin_state(), now);
+        let event = remap.update(&timed_input.buttons, now.micros());
+        let report = match event {
+            RemapEvent::Inactive => {
+                if let Some(index) = profile.change_profile(&timed_input, &config) {
+                    profile_index = index;
+                }
+                profile.map_to_gamecube(&timed_input)
+            }
+            RemapEvent::Remapped(logical, physical) => {
+                let current = &mut config.profiles[profile_index];
+                current.buttons.assign(logical, physical);
+                profile = MapProfile::new(current);
+                GamecubeInput::NEUTRAL
+            }
+            RemapEvent::Active | RemapEvent::Save | RemapEvent::Revert => GamecubeInput::NEUTRAL,
+        };
+        gamecube_controller.respond_to_poll(timer, delay, to_joybus(report));
+
+        // Writing to flash stalls the controller for a few polls, so only do it after responding.
+        match event {
+            RemapEvent::Save => {
+                if config::store_remapped(&config).is_err() {
+                    remap.failed(timer.get_counter().ticks());
+                }
+            }
+            RemapEvent::Revert => match config::load_flashed() {
+                // Only discard the remaps once there is a usable config to replace them with.
+                Ok(flashed) if !flashed.profiles.is_empty() => {
+                    config::clear_remapped();
+                    config = flashed;
+                    profile_index = profile_index.min(config.profiles.len() - 1);
+                    profile = MapProfile::new(&config.profiles[profile_index]);
+                }
+                _ => remap.failed(timer.get_counter().ticks()),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn to_joybus(input: GamecubeInput) -> joybus_pio::GamecubeInput {
+    joybus_pio::GamecubeInput {
+        start: input.start,
+        a: input.a,
+        b: input.b,
+        x: input.x,
+        y: input.y,
+        z: input.z,
+        dpad_up: input.dpad_up,
+        dpad_down: input.dpad_down,
+        dpad_left: input.dpad_left,
+        dpad_right: input.dpad_right,
+        l_digital: input.l_digital,
+        r_digital: input.r_digital,
+        stick_x: input.stick_x,
+        stick_y: input.stick_y,
+        cstick_x: input.cstick_x,
+        cstick_y: input.cstick_y,
+        l_analog: input.l_analog,
+        r_analog: input.r_analog,
     }
 }
 
@@ -0,0 +1,257 @@
+//! Remap mode, for changing the bindings of the current profile without a PC.
+//!
+//! * Hold start + left-middle2 + right-pinky2 for 2 seconds to enter remap mode.
+//!   While in remap mode the controller reports no buttons as pressed.
+//! * Tap the physical button to rebind, the LED stops blinking and stays on.
+//! * Tap the button that selects the logical button to assign to it, as laid out in [`select_logical_button`].
+//!   The LED blinks 3 times to confirm.
+//! * Hold start for 2 seconds to save the changes and leave remap mode.
+//! * Hold left-middle2 + right-pinky2 for 2 seconds to revert to the flashed config and leave remap mode.
+//!
+//! If saving or reverting fails, the LED flickers rapidly for 2 seconds and remap mode stays active so it can be retried.
+
+use rukaibox_config::{LogicalButton, PhysicalButton};
+use rukaibox_profile::input::{ButtonInputResults, PHYSICAL_BUTTONS};
+
+const HOLD_US: u64 = 2_000_000;
+const CONFIRM_BLINK_US: u64 = 600_000;
+const FAILED_BLINK_US: u64 = 2_000_000;
+
+const ENTER_COMBINATION: ButtonSet = ButtonSet::of(&[
+    PhysicalButton::Start,
+    PhysicalButton::LeftMiddle2,
+    PhysicalButton::RightPinky2,
+]);
+const SAVE_COMBINATION: ButtonSet = ButtonSet::of(&[PhysicalButton::Start]);
+const REVERT_COMBINATION: ButtonSet =
+    ButtonSet::of(&[PhysicalButton::LeftMiddle2, PhysicalButton::RightPinky2]);
+/// Held while tapping a button to select the logical buttons that are not in the base layout.
+const SHIFT: PhysicalButton = PhysicalButton::RightPinky2;
+
+/// A set of physical buttons, stored as a bitmask indexed by the `PhysicalButton` discriminant
+#[derive(Clone, Copy, PartialEq, Default)]
+struct ButtonSet(u32);
+
+impl ButtonSet {
+    const fn of(buttons: &[PhysicalButton]) -> Self {
+        let mut set = 0;
+        let mut i = 0;
+        while i < buttons.len() {
+            set |= 1 << buttons[i] as u32;
+            i += 1;
+        }
+        ButtonSet(set)
+    }
+
+    fn pressed(input: &ButtonInputResults) -> Self {
+        let mut set = ButtonSet::default();
+        for button in PHYSICAL_BUTTONS {
+            if input.get_button_value(button) {
+                set.0 |= 1 << button as u32;
+            }
+        }
+        set
+    }
+
+    fn contains(self, button: PhysicalButton) -> bool {
+        self.0 & (1 << button as u32) != 0
+    }
+
+    fn without(self, button: PhysicalButton) -> Self {
+        ButtonSet(self.0 & !(1 << button as u32))
+    }
+
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the button if the set contains exactly one button.
+    fn single(self) -> Option<PhysicalButton> {
+        if self.0.count_ones() == 1 {
+            PHYSICAL_BUTTONS
+                .into_iter()
+                .find(|button| self.contains(*button))
+        } else {
+            None
+        }
+    }
+}
+
+pub enum RemapEvent {
+    /// Remap mode is not active, the controller should be used as normal.
+    Inactive,
+    /// Remap mode is active, the controller should report no buttons as pressed.
+    Active,
+    /// The logical button of the current profile should be bound to the physical button.
+    Remapped(LogicalButton, PhysicalButton),
+    /// Remap mode was left and the remapped config should be saved.
+    Save,
+    /// Remap mode was left and the flashed config should be restored.
+    Revert,
+}
+
+#[derive(Default)]
+pub struct RemapMode {
+    active: bool,
+    /// Ignore all input until every button is released, then leave remap mode if `leaving` is set.
+    wait_for_release: bool,
+    leaving: bool,
+    /// The physical button waiting to be assigned a logical button
+    selected: Option<PhysicalButton>,
+    /// Every button pressed since all buttons were last released
+    tapped: ButtonSet,
+    /// The currently held buttons and when they started being held
+    held: ButtonSet,
+    held_since: u64,
+    confirm_blink_since: Option<u64>,
+    failed_blink_since: Option<u64>,
+}
+
+impl RemapMode {
+    /// `now` is the current time in microseconds.
+    pub fn update(&mut self, input: &ButtonInputResults, now: u64) -> RemapEvent {
+        let pressed = ButtonSet::pressed(input);
+        if pressed != self.held {
+            self.held = pressed;
+            self.held_since = now;
+        }
+        let held_long = !pressed.is_empty() && now - self.held_since >= HOLD_US;
+
+        if !self.active {
+            if held_long && pressed == ENTER_COMBINATION {
+                *self = RemapMode {
+                    active: true,
+                    wait_for_release: true,
+                    held: pressed,
+                    held_since: self.held_since,
+                    ..Default::default()
+                };
+                return RemapEvent::Active;
+            }
+            return RemapEvent::Inactive;
+        }
+
+        if self.wait_for_release {
+            if pressed.is_empty() {
+                self.wait_for_release = false;
+                self.active = !self.leaving;
+            }
+            return RemapEvent::Active;
+        }
+
+        if held_long && (pressed == SAVE_COMBINATION || pressed == REVERT_COMBINATION) {
+            self.wait_for_release = true;
+            self.leaving = true;
+            return if pressed == SAVE_COMBINATION {
+                RemapEvent::Save
+            } else {
+                RemapEvent::Revert
+            };
+        }
+
+        // A tap is complete once all of its buttons have been released.
+        self.tapped.0 |= pressed.0;
+        if !pressed.is_empty() {
+            return RemapEvent::Active;
+        }
+        let tapped = core::mem::take(&mut self.tapped);
+        if tapped.is_empty() {
+            return RemapEvent::Active;
+        }
+
+        match self.selected {
+            None => {
+                self.selected = tapped.single();
+                RemapEvent::Active
+            }
+            Some(physical) => match select_logical_button(tapped) {
+                Some(logical) => {
+                    self.selected = None;
+                    self.confirm_blink_since = Some(now);
+                    RemapEvent::Remapped(logical, physical)
+                }
+                None => RemapEvent::Active,
+            },
+        }
+    }
+
+    /// Call when the [`RemapEvent::Save`] or [`RemapEvent::Revert`] just returned by `update` could not be carried out.
+    /// Remap mode stays active instead of being left once all buttons are released.
+    pub fn failed(&mut self, now: u64) {
+        self.leaving = false;
+        self.failed_blink_since = Some(now);
+    }
+
+    /// Returns the LED state while remap mode is active.
+    pub fn led(&self, now: u64) -> Option<bool> {
+        if !self.active {
+            return None;
+        }
+        if let Some(since) = self.failed_blink_since
+            && now - since < FAILED_BLINK_US
+        {
+            return Some(((now - since) / 50_000) % 2 == 0);
+        }
+        if let Some(since) = self.confirm_blink_since
+            && now - since < CONFIRM_BLINK_US
+        {
+            return Some(((now - since) / 100_000) % 2 == 0);
+        }
+        if self.selected.is_some() {
+            Some(true)
+        } else {
+            Some((now / 250_000) % 2 == 0)
+        }
+    }
+}
+
+/// The logical button selected by a tap in remap mode.
+///
+/// Buttons in the default Project+ layout select the logical button they are bound to by default.
+/// The remaining logical buttons are selected by holding right-pinky2 while tapping:
+/// * right-thumb-up/down/left/right selects dpad-up/down/left/right
+/// * left-pinky selects l-analog
+fn select_logical_button(tapped: ButtonSet) -> Option<LogicalButton> {
+    let shifted = tapped.contains(SHIFT) && tapped.0.count_ones() > 1;
+    let button = if shifted {
+        tapped.without(SHIFT).single()?
+    } else {
+        tapped.single()?
+    };
+
+    let logical = if shifted {
+        match button {
+            PhysicalButton::RightThumbUp => LogicalButton::DpadUp,
+            PhysicalButton::RightThumbDown => LogicalButton::DpadDown,
+            PhysicalButton::RightThumbLeft => LogicalButton::DpadLeft,
+            PhysicalButton::RightThumbRight => LogicalButton::DpadRight,
+            PhysicalButton::LeftPinky => LogicalButton::LAnalog,
+            _ => return None,
+        }
+    } else {
+        match button {
+            PhysicalButton::Start => LogicalButton::Start,
+            PhysicalButton::LeftPinky => LogicalButton::LDigital,
+            PhysicalButton::LeftRing => LogicalButton::StickLeft,
+            PhysicalButton::LeftMiddle => LogicalButton::StickDown,
+            PhysicalButton::LeftIndex => LogicalButton::StickRight,
+            PhysicalButton::LeftThumbLeft => LogicalButton::ModX,
+            PhysicalButton::LeftThumbRight => LogicalButton::ModY,
+            PhysicalButton::RightIndex => LogicalButton::RDigital,
+            PhysicalButton::RightMiddle => LogicalButton::X,
+            PhysicalButton::RightRing => LogicalButton::B,
+            PhysicalButton::RightPinky => LogicalButton::StickUp,
+            PhysicalButton::RightIndex2 => LogicalButton::Z,
+            PhysicalButton::RightMiddle2 => LogicalButton::Y,
+            PhysicalButton::RightRing2 => LogicalButton::RAnalog,
+            PhysicalButton::RightPinky2 => LogicalButton::DpadUp,
+            PhysicalButton::RightThumbLeft => LogicalButton::CstickLeft,
+            PhysicalButton::RightThumbRight => LogicalButton::CstickRight,
+            PhysicalButton::RightThumbUp => LogicalButton::CstickUp,
+            PhysicalButton::RightThumbDown => LogicalButton::CstickDown,
+            PhysicalButton::RightThumbMiddle => LogicalButton::A,
+            PhysicalButton::LeftMiddle2 | PhysicalButton::None => return None,
+        }
+    };
+    Some(logical)
+}
@@ -0,0 +1,224 @@
+//! A tiny FAT12 filesystem kept in RAM and exposed to the host as the USB drive.
+//!
+//! Only the root directory is used, files are identified by their 8.3 name.
+//! The host is free to modify the filesystem however it likes, so everything read back from it is treated as untrusted.
+
+pub const SECTOR_SIZE: usize = 512;
+pub const SECTOR_COUNT: usize = 128;
+pub const DISK_SIZE: usize = SECTOR_SIZE * SECTOR_COUNT;
+
+const RESERVED_SECTORS: usize = 1;
+const FAT_SECTORS: usize = 1;
+const ROOT_ENTRIES: usize = 64;
+const ROOT_SECTORS: usize = ROOT_ENTRIES * DIR_ENTRY_SIZE / SECTOR_SIZE;
+
+const FAT_START: usize = RESERVED_SECTORS * SECTOR_SIZE;
+const ROOT_START: usize = FAT_START + FAT_SECTORS * SECTOR_SIZE;
+const DATA_START: usize = ROOT_START + ROOT_SECTORS * SECTOR_SIZE;
+
+/// One sector per cluster, the first data cluster is numbered 2.
+const CLUSTER_SIZE: usize = SECTOR_SIZE;
+const FIRST_CLUSTER: usize = 2;
+const CLUSTER_END: usize = FIRST_CLUSTER + (DISK_SIZE - DATA_START) / CLUSTER_SIZE;
+const END_OF_CHAIN: u16 = 0xFFF;
+
+const DIR_ENTRY_SIZE: usize = 32;
+const ATTR_READ_ONLY: u8 = 0x01;
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+const ATTR_LONG_NAME: u8 = 0x0F;
+const DELETED: u8 = 0xE5;
+
+/// 2025-01-01, there is no real time clock to get the actual date from.
+const DATE: u16 = ((2025 - 1980) << 9) | (1 << 5) | 1;
+
+pub type FileName = [u8; 11];
+
+pub struct DiskFull;
+
+pub enum ReadError {
+    NotFound,
+    TooLarge,
+    /// The cluster chain or file size is inconsistent.
+    Corrupted,
+}
+
+/// Overwrite `disk` with an empty filesystem.
+pub fn format(disk: &mut [u8; DISK_SIZE], volume_label: &FileName) {
+    disk.fill(0);
+
+    let boot = &mut disk[..SECTOR_SIZE];
+    boot[0..3].copy_from_slice(&[0xEB, 0x3C, 0x90]);
+    boot[3..11].copy_from_slice(b"MSDOS5.0");
+    boot[11..13].copy_from_slice(&(SECTOR_SIZE as u16).to_le_bytes());
+    boot[13] = (CLUSTER_SIZE / SECTOR_SIZE) as u8;
+    boot[14..16].copy_from_slice(&(RESERVED_SECTORS as u16).to_le_bytes());
+    boot[16] = 1; // FAT count
+    boot[17..19].copy_from_slice(&(ROOT_ENTRIES as u16).to_le_bytes());
+    boot[19..21].copy_from_slice(&(SECTOR_COUNT as u16).to_le_bytes());
+    boot[21] = 0xF8; // media descriptor: fixed disk
+    boot[22..24].copy_from_slice(&(FAT_SECTORS as u16).to_le_bytes());
+    boot[24..26].copy_from_slice(&1u16.to_le_bytes()); // sectors per track
+    boot[26..28].copy_from_slice(&1u16.to_le_bytes()); // heads
+    boot[36] = 0x80; // drive number
+    boot[38] = 0x29; // extended boot signature
+    boot[39..43].copy_from_slice(b"RKBX"); // volume serial number
+    boot[43..54].copy_from_slice(volume_label);
+    boot[54..62].copy_from_slice(b"FAT12   ");
+    boot[510..512].copy_from_slice(&[0x55, 0xAA]);
+
+    // The first two FAT entries are reserved, the first contains the media descriptor.
+    disk[FAT_START..FAT_START + 3].copy_from_slice(&[0xF8, 0xFF, 0xFF]);
+
+    let label = &mut disk[ROOT_START..ROOT_START + DIR_ENTRY_SIZE];
+    label[0..11].copy_from_slice(volume_label);
+    label[11] = ATTR_VOLUME_ID;
+    label[24..26].copy_from_slice(&DATE.to_le_bytes());
+}
+
+/// Create a file in the root directory, replacing any existing file with the same name.
+pub fn write_file(
+    disk: &mut [u8; DISK_SIZE],
+    name: &FileName,
+    contents: &[u8],
+    read_only: bool,
+) -> Result<(), DiskFull> {
+    remove_file(disk, name);
+
+    let entry = root_entries()
+        .find(|entry| matches!(disk[*entry], 0 | DELETED))
+        .ok_or(DiskFull)?;
+
+    let cluster_count = contents.len().div_ceil(CLUSTER_SIZE);
+    let free_clusters = (FIRST_CLUSTER..CLUSTER_END)
+        .filter(|cluster| fat_get(disk, *cluster) == 0)
+        .count();
+    if free_clusters < cluster_count {
+        return Err(DiskFull);
+    }
+
+    let mut first_cluster = 0;
+    let mut previous_cluster = None;
+    for chunk in contents.chunks(CLUSTER_SIZE) {
+        let cluster = (FIRST_CLUSTER..CLUSTER_END)
+            .find(|cluster| fat_get(disk, *cluster) == 0)
+            .unwrap();
+        fat_set(disk, cluster, END_OF_CHAIN);
+        match previous_cluster {
+            Some(previous) => fat_set(disk, previous, cluster as u16),
+            None => first_cluster = cluster,
+        }
+        previous_cluster = Some(cluster);
+
+        let start = cluster_offset(cluster);
+        disk[start..start + CLUSTER_SIZE].fill(0);
+        disk[start..start + chunk.len()].copy_from_slice(chunk);
+    }
+
+    let entry = &mut disk[entry..entry + DIR_ENTRY_SIZE];
+    entry.fill(0);
+    entry[0..11].copy_from_slice(name);
+    entry[11] = ATTR_ARCHIVE | if read_only { ATTR_READ_ONLY } else { 0 };
+    // Windows displays the name and extension in lowercase when these bits are set.
+    entry[12] = 0x08 | 0x10;
+    entry[16..18].copy_from_slice(&DATE.to_le_bytes());
+    entry[18..20].copy_from_slice(&DATE.to_le_bytes());
+    entry[24..26].copy_from_slice(&DATE.to_le_bytes());
+    entry[26..28].copy_from_slice(&(first_cluster as u16).to_le_bytes());
+    entry[28..32].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+    Ok(())
+}
+
+/// Remove a file from the root directory, does nothing if the file does not exist.
+pub fn remove_file(disk: &mut [u8; DISK_SIZE], name: &FileName) {
+    while let Some(entry) = find_file(disk, name) {
+        let mut cluster = first_cluster(disk, entry);
+        // Bounded by the cluster count in case the host created a loop
+        for _ in FIRST_CLUSTER..CLUSTER_END {
+            if !(FIRST_CLUSTER..CLUSTER_END).contains(&cluster) {
+                break;
+            }
+            let next = fat_get(disk, cluster) as usize;
+            fat_set(disk, cluster, 0);
+            cluster = next;
+        }
+        disk[entry] = DELETED;
+    }
+}
+
+/// Read a file from the root directory into `out`, returning the size of the file.
+pub fn read_file(
+    disk: &[u8; DISK_SIZE],
+    name: &FileName,
+    out: &mut [u8],
+) -> Result<usize, ReadError> {
+    let entry = find_file(disk, name).ok_or(ReadError::NotFound)?;
+    let size = u32::from_le_bytes(disk[entry + 28..entry + 32].try_into().unwrap()) as usize;
+    if size > out.len() {
+        return Err(ReadError::TooLarge);
+    }
+
+    let mut cluster = first_cluster(disk, entry);
+    for chunk in out[..size].chunks_mut(CLUSTER_SIZE) {
+        if !(FIRST_CLUSTER..CLUSTER_END).contains(&cluster) {
+            return Err(ReadError::Corrupted);
+        }
+        let start = cluster_offset(cluster);
+        chunk.copy_from_slice(&disk[start..start + chunk.len()]);
+        cluster = fat_get(disk, cluster) as usize;
+    }
+    Ok(size)
+}
+
+fn find_file(disk: &[u8; DISK_SIZE], name: &FileName) -> Option<usize> {
+    for entry in root_entries() {
+        match disk[entry] {
+            0 => return None,
+            DELETED => {}
+            _ => {
+                let attributes = disk[entry + 11];
+                let is_file = attributes & ATTR_LONG_NAME != ATTR_LONG_NAME
+                    && attributes & (ATTR_VOLUME_ID | ATTR_DIRECTORY) == 0;
+                if is_file && disk[entry..entry + 11].eq_ignore_ascii_case(name) {
+                    return Some(entry);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn root_entries() -> impl Iterator<Item = usize> {
+    (ROOT_START..DATA_START).step_by(DIR_ENTRY_SIZE)
+}
+
+fn first_cluster(disk: &[u8; DISK_SIZE], entry: usize) -> usize {
+    u16::from_le_bytes([disk[entry + 26], disk[entry + 27]]) as usize
+}
+
+fn cluster_offset(cluster: usize) -> usize {
+    DATA_START + (cluster - FIRST_CLUSTER) * CLUSTER_SIZE
+}
+
+/// FAT12 packs two 12 bit entries into every 3 bytes.
+fn fat_get(disk: &[u8; DISK_SIZE], cluster: usize) -> u16 {
+    let offset = FAT_START + cluster + cluster / 2;
+    let value = u16::from_le_bytes([disk[offset], disk[offset + 1]]);
+    if cluster % 2 == 0 {
+        value & 0xFFF
+    } else {
+        value >> 4
+    }
+}
+
+fn fat_set(disk: &mut [u8; DISK_SIZE], cluster: usize, value: u16) {
+    let offset = FAT_START + cluster + cluster / 2;
+    if cluster % 2 == 0 {
+        disk[offset] = value as u8;
+        disk[offset + 1] = (disk[offset + 1] & 0xF0) | ((value >> 8) as u8 & 0x0F);
+    } else {
+        disk[offset] = (disk[offset] & 0x0F) | ((value << 4) as u8);
+        disk[offset + 1] = (value >> 4) as u8;
+    }
+}
@@ -0,0 +1,212 @@
+//! USB drive mode, where the controller appears as a USB drive containing config.kdl.
+//!
+//! When config.kdl on the drive is saved, it is parsed and if valid, written to flash as the new config.
+//! The result is reported in status.txt and any errors are written to errors.txt.
+//! This allows editing the config without the flash tool or any drivers.
+
+mod fat;
+mod msc;
+
+use crate::config;
+use core::fmt::Write;
+use embedded_hal::digital::OutputPin;
+use fat::{DISK_SIZE, FileName};
+use msc::{MassStorage, Medium};
+use rp2040_hal::{
+    Timer,
+    gpio::{FunctionSio, Pin, PullDown, SioOutput, bank0::Gpio25},
+    usb::UsbBus,
+};
+use rukaibox_config::CONFIG_KDL_SIZE;
+use usb_device::{bus::UsbBusAllocator, device::StringDescriptors, prelude::*};
+
+const VOLUME_LABEL: &FileName = b"RUKAIBOX   ";
+const CONFIG_FILE: &FileName = b"CONFIG  KDL";
+const STATUS_FILE: &FileName = b"STATUS  TXT";
+const ERRORS_FILE: &FileName = b"ERRORS  TXT";
+
+/// The USB vendor and product ID of the drive.
+///
+/// This can't reuse the RP2040 bootloader's 0x2e8a/0x0003: the flash tool would try to use the drive as the bootloader,
+/// and on Windows the WinUSB driver installed for the bootloader via zadig would stop the drive from mounting.
+/// So this is the pid.codes test ID until a pid.codes ID is allocated for rukaibox, which only needs changing here.
+/// Being a test ID, it must not ship in release firmware, so USB drive mode is behind the off by default `usb-drive` feature.
+const USB_VID_PID: UsbVidPid = UsbVidPid(0x1209, 0x0001);
+
+/// The host may write a file across many separate transfers, so wait until it has been quiet for this long before reading config.kdl.
+const WRITE_SETTLE_US: u64 = 1_000_000;
+/// How long the drive is reported as removed after config.kdl is processed.
+const MEDIUM_REMOVED_US: u64 = 500_000;
+
+const NO_CONFIG_KDL: &str = "// No config.kdl was stored on this controller.
+// Replace this file with the config.kdl distributed with the flash tool.
+";
+
+static mut DISK: [u8; DISK_SIZE] = [0; DISK_SIZE];
+/// Holds config.kdl prefixed by its size, in the format it is stored in flash.
+static mut CONFIG_KDL: [u8; CONFIG_KDL_SIZE] = [0; CONFIG_KDL_SIZE];
+
+/// Run USB drive mode until the controller is unplugged.
+pub fn run(
+    mut led_pin: Pin<Gpio25, FunctionSio<SioOutput>, PullDown>,
+    usb_bus: UsbBus,
+    timer: &Timer,
+) -> ! {
+    // Safety: `run` never returns and is only called once, so these are the only references to the statics.
+    let disk = unsafe { &mut *(&raw mut DISK) };
+    let config_kdl = unsafe { &mut *(&raw mut CONFIG_KDL) };
+
+    fat::format(disk, VOLUME_LABEL);
+    let text = config::load_kdl(config_kdl).unwrap_or(NO_CONFIG_KDL);
+    fat::write_file(disk, CONFIG_FILE, text.as_bytes(), false).ok();
+    write_status(disk, "No changes have been made yet.");
+
+    let usb_bus = UsbBusAllocator::new(usb_bus);
+    let mut storage = MassStorage::new(&usb_bus, disk);
+    let mut usb_device = UsbDeviceBuilder::new(&usb_bus, USB_VID_PID)
+        .strings(&[StringDescriptors::default()
+            .manufacturer("rukai")
+            .product("Rukaibox Config Drive")
+            .serial_number("RUKAIBOX")])
+        .unwrap()
+        .build();
+
+    let mut last_write = None;
+    let mut medium_removed_at = None;
+    loop {
+        usb_device.poll(&mut [&mut storage]);
+        let now = timer.get_counter().ticks();
+
+        if storage.take_written() {
+            last_write = Some(now);
+        }
+        let ejected = storage.take_ejected();
+        if let Some(last_write_at) = last_write
+            && (ejected || now - last_write_at > WRITE_SETTLE_US)
+        {
+            last_write = None;
+            led_pin.set_low().unwrap();
+            apply_config(storage.disk(), config_kdl);
+
+            // Make the host reread the whole drive, so it sees the status and errors written above.
+            storage.set_medium(Medium::NotPresent);
+            medium_removed_at = Some(now);
+        }
+
+        if let Some(removed_at) = medium_removed_at
+            && now - removed_at > MEDIUM_REMOVED_US
+        {
+            medium_removed_at = None;
+            storage.set_medium(Medium::Changed);
+        }
+
+        // Solid LED while idle, off while processing, and short blinks while the host is writing.
+        let led_on = last_write.is_none() || (now / 100_000) % 2 == 0;
+        led_pin.set_state(led_on.into()).unwrap();
+    }
+}
+
+/// Parse config.kdl from the disk and store it if valid, reporting the result back to the disk.
+fn apply_config(disk: &mut [u8; DISK_SIZE], config_kdl: &mut [u8; CONFIG_KDL_SIZE]) {
+    let size = match fat::read_file(disk, CONFIG_FILE, &mut config_kdl[4..]) {
+        Ok(size) => size,
+        Err(fat::ReadError::NotFound) => {
+            return write_error(disk, "config.kdl was not found on the drive.");
+        }
+        Err(fat::ReadError::TooLarge) => {
+            return write_error(disk, "config.kdl is too large, it must be under 32 KiB.");
+        }
+        Err(fat::ReadError::Corrupted) => {
+            return write_error(
+                disk,
+                "config.kdl could not be read, the drive may be corrupted.",
+            );
+        }
+    };
+    let Ok(text) = core::str::from_utf8(&config_kdl[4..4 + size]) else {
+        return write_error(disk, "config.kdl is not valid UTF-8 text.");
+    };
+
+    match rukaibox_config_kdl::parse_config(text) {
+        Ok(parsed) => {
+            if config::store(&parsed).is_err() {
+                return write_error(disk, "The config is too large to store on the controller.");
+            }
+            config::store_kdl(config_kdl, size);
+            fat::remove_file(disk, ERRORS_FILE);
+            write_status(
+                disk,
+                "config.kdl was saved to the controller, unplug it to start using the new config.",
+            );
+        }
+        Err(err) => {
+            let (line, column) = err.line_column(text);
+            let mut message = TextBuffer::new();
+            write!(message, "config.kdl:{line}:{column}: {}", err.kind).ok();
+            write_error(disk, message.as_str());
+        }
+    }
+}
+
+fn write_error(disk: &mut [u8; DISK_SIZE], error: &str) {
+    let mut text = TextBuffer::new();
+    writeln!(text, "{error}").ok();
+    fat::write_file(disk, ERRORS_FILE, text.as_bytes(), true).ok();
+    write_status(
+        disk,
+        "config.kdl was NOT saved to the controller, see errors.txt for details.",
+    );
+}
+
+fn write_status(disk: &mut [u8; DISK_SIZE], result: &str) {
+    let mut text = TextBuffer::new();
+    write!(
+        text,
+        "Rukaibox USB drive mode
+
+Edit config.kdl on this drive and save it to update the controller's config.
+If there is a problem with the config, it will not be saved and errors.txt will describe the problem.
+Unplug the controller to leave USB drive mode.
+
+Last result: {result}
+"
+    )
+    .ok();
+    fat::write_file(disk, STATUS_FILE, text.as_bytes(), true).ok();
+}
+
+/// A fixed size buffer for formatting text files into, text that does not fit is dropped.
+struct TextBuffer {
+    bytes: [u8; 1024],
+    len: usize,
+}
+
+impl TextBuffer {
+    fn new() -> Self {
+        TextBuffer {
+            bytes: [0; 1024],
+            len: 0,
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    fn as_str(&self) -> &str {
+        // Only whole `str`s are written so this is always valid
+        core::str::from_utf8(self.as_bytes()).unwrap_or("")
+    }
+}
+
+impl Write for TextBuffer {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.bytes.len() {
+            return Err(core::fmt::Error);
+        }
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
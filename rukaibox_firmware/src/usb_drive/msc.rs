@@ -0,0 +1,402 @@
+//! A USB mass storage class implementing the bulk only transport with the SCSI command set.
+//!
+//! This is the minimal set of commands that Windows, macOS and Linux require to mount a drive.
+
+use super::fat::{DISK_SIZE, SECTOR_COUNT, SECTOR_SIZE};
+use usb_device::class_prelude::*;
+
+const CLASS_MASS_STORAGE: u8 = 0x08;
+const SUBCLASS_SCSI: u8 = 0x06;
+const PROTOCOL_BULK_ONLY: u8 = 0x50;
+
+const REQUEST_GET_MAX_LUN: u8 = 0xFE;
+const REQUEST_RESET: u8 = 0xFF;
+
+const PACKET_SIZE: usize = 64;
+
+const CBW_SIGNATURE: &[u8; 4] = b"USBC";
+const CBW_SIZE: usize = 31;
+const CSW_SIGNATURE: &[u8; 4] = b"USBS";
+const CSW_SIZE: usize = 13;
+
+const STATUS_PASSED: u8 = 0;
+const STATUS_FAILED: u8 = 1;
+
+mod scsi {
+    pub const TEST_UNIT_READY: u8 = 0x00;
+    pub const REQUEST_SENSE: u8 = 0x03;
+    pub const INQUIRY: u8 = 0x12;
+    pub const MODE_SENSE_6: u8 = 0x1A;
+    pub const START_STOP_UNIT: u8 = 0x1B;
+    pub const PREVENT_ALLOW_MEDIUM_REMOVAL: u8 = 0x1E;
+    pub const READ_FORMAT_CAPACITIES: u8 = 0x23;
+    pub const READ_CAPACITY_10: u8 = 0x25;
+    pub const READ_10: u8 = 0x28;
+    pub const WRITE_10: u8 = 0x2A;
+    pub const VERIFY_10: u8 = 0x2F;
+    pub const SYNCHRONIZE_CACHE_10: u8 = 0x35;
+    pub const MODE_SENSE_10: u8 = 0x5A;
+}
+
+/// SCSI sense key, additional sense code and additional sense code qualifier
+#[derive(Clone, Copy)]
+struct Sense(u8, u8, u8);
+
+impl Sense {
+    const NONE: Sense = Sense(0x00, 0x00, 0x00);
+    const MEDIUM_NOT_PRESENT: Sense = Sense(0x02, 0x3A, 0x00);
+    const MEDIUM_CHANGED: Sense = Sense(0x06, 0x28, 0x00);
+    const INVALID_COMMAND: Sense = Sense(0x05, 0x20, 0x00);
+    const INVALID_FIELD: Sense = Sense(0x05, 0x24, 0x00);
+    const LBA_OUT_OF_RANGE: Sense = Sense(0x05, 0x21, 0x00);
+}
+
+enum State {
+    /// Waiting for a command block wrapper
+    Idle,
+    /// Sending `response[offset..end]` or `disk[offset..end]` to the host
+    DataIn {
+        from_disk: bool,
+        offset: usize,
+        end: usize,
+    },
+    /// Receiving `disk[offset..end]` from the host, the data is thrown away when `discard` is set.
+    DataOut {
+        discard: bool,
+        offset: usize,
+        end: usize,
+    },
+    /// Sending the command status wrapper
+    Status,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Medium {
+    Present,
+    /// Reported to the host as the medium having been removed, so it forgets everything it cached about the disk.
+    NotPresent,
+    /// The host has not yet been told that the medium was replaced.
+    Changed,
+}
+
+pub struct MassStorage<'a, B: UsbBus> {
+    interface: InterfaceNumber,
+    ep_in: EndpointIn<'a, B>,
+    ep_out: EndpointOut<'a, B>,
+    disk: &'a mut [u8; DISK_SIZE],
+    state: State,
+    tag: [u8; 4],
+    /// The amount of data the host expected, minus the amount actually transferred
+    residue: u32,
+    status: u8,
+    sense: Sense,
+    response: [u8; 36],
+    medium: Medium,
+    written: bool,
+    ejected: bool,
+}
+
+impl<'a, B: UsbBus> MassStorage<'a, B> {
+    pub fn new(alloc: &'a UsbBusAllocator<B>, disk: &'a mut [u8; DISK_SIZE]) -> Self {
+        MassStorage {
+            interface: alloc.interface(),
+            ep_in: alloc.bulk(PACKET_SIZE as u16),
+            ep_out: alloc.bulk(PACKET_SIZE as u16),
+            disk,
+            state: State::Idle,
+            tag: [0; 4],
+            residue: 0,
+            status: STATUS_PASSED,
+            sense: Sense::NONE,
+            response: [0; 36],
+            medium: Medium::Present,
+            written: false,
+            ejected: false,
+        }
+    }
+
+    pub fn disk(&mut self) -> &mut [u8; DISK_SIZE] {
+        self.disk
+    }
+
+    pub fn set_medium(&mut self, medium: Medium) {
+        self.medium = medium;
+    }
+
+    /// Returns true if the host has written to the disk since the last call.
+    pub fn take_written(&mut self) -> bool {
+        core::mem::take(&mut self.written)
+    }
+
+    /// Returns true if the host has ejected the disk since the last call.
+    pub fn take_ejected(&mut self) -> bool {
+        core::mem::take(&mut self.ejected)
+    }
+
+    fn handle_command(&mut self, cbw: &[u8]) {
+        if cbw.len() != CBW_SIZE || &cbw[0..4] != CBW_SIGNATURE {
+            // Invalid command block wrappers must stall until the host resets the device.
+            self.ep_in.stall();
+            self.ep_out.stall();
+            return;
+        }
+        self.tag.copy_from_slice(&cbw[4..8]);
+        let transfer_len = u32::from_le_bytes(cbw[8..12].try_into().unwrap()) as usize;
+        let direction_in = cbw[12] & 0x80 != 0;
+        let command_len = (cbw[14] as usize).clamp(1, 16);
+        let mut command = [0; 16];
+        command[..command_len].copy_from_slice(&cbw[15..15 + command_len]);
+
+        let result = self.execute(&command, transfer_len);
+        let (data_len, from_disk, offset) = match result {
+            Ok(response) => {
+                self.sense = Sense::NONE;
+                self.status = STATUS_PASSED;
+                response
+            }
+            Err(sense) => {
+                self.sense = sense;
+                self.status = STATUS_FAILED;
+                (0, false, 0)
+            }
+        };
+
+        let data_len = data_len.min(transfer_len);
+        self.residue = (transfer_len - data_len) as u32;
+        self.state = if transfer_len == 0 {
+            State::Status
+        } else if direction_in {
+            State::DataIn {
+                from_disk,
+                offset,
+                end: offset + data_len,
+            }
+        } else {
+            // Any data the host sends that we do not want still needs to be received
+            let discard = !from_disk || self.status == STATUS_FAILED;
+            self.residue = if discard { transfer_len as u32 } else { 0 };
+            State::DataOut {
+                discard,
+                offset,
+                end: offset + transfer_len,
+            }
+        };
+    }
+
+    /// Returns the length of the data phase, whether it uses the disk and its offset into either the disk or the response buffer.
+    fn execute(
+        &mut self,
+        command: &[u8; 16],
+        transfer_len: usize,
+    ) -> Result<(usize, bool, usize), Sense> {
+        let response = &mut self.response;
+        response.fill(0);
+        match command[0] {
+            scsi::TEST_UNIT_READY => {
+                check_medium(&mut self.medium)?;
+                Ok((0, false, 0))
+            }
+            scsi::REQUEST_SENSE => {
+                response[0] = 0x70; // current errors, fixed format
+                response[2] = self.sense.0;
+                response[7] = 10; // additional length
+                response[12] = self.sense.1;
+                response[13] = self.sense.2;
+                Ok((18, false, 0))
+            }
+            scsi::INQUIRY => {
+                response[0] = 0x00; // direct access block device
+                response[1] = 0x80; // removable
+                response[2] = 0x04; // SPC-2
+                response[3] = 0x02; // response data format
+                response[4] = 31; // additional length
+                response[8..16].copy_from_slice(b"rukai   ");
+                response[16..32].copy_from_slice(b"Rukaibox Config ");
+                response[32..36].copy_from_slice(b"0001");
+                Ok((36, false, 0))
+            }
+            scsi::MODE_SENSE_6 => {
+                response[0] = 3; // mode data length, nothing is write protected
+                Ok((4, false, 0))
+            }
+            scsi::MODE_SENSE_10 => {
+                response[1] = 6; // mode data length, nothing is write protected
+                Ok((8, false, 0))
+            }
+            scsi::START_STOP_UNIT => {
+                let load_eject = command[4] & 0x02 != 0;
+                let start = command[4] & 0x01 != 0;
+                if load_eject && !start {
+                    self.ejected = true;
+                }
+                Ok((0, false, 0))
+            }
+            scsi::PREVENT_ALLOW_MEDIUM_REMOVAL | scsi::VERIFY_10 | scsi::SYNCHRONIZE_CACHE_10 => {
+                Ok((0, false, 0))
+            }
+            scsi::READ_FORMAT_CAPACITIES => {
+                response[3] = 8; // capacity list length
+                response[4..8].copy_from_slice(&(SECTOR_COUNT as u32).to_be_bytes());
+                response[8] = 0x02; // formatted media
+                response[9..12].copy_from_slice(&(SECTOR_SIZE as u32).to_be_bytes()[1..]);
+                Ok((12, false, 0))
+            }
+            scsi::READ_CAPACITY_10 => {
+                check_medium(&mut self.medium)?;
+                response[0..4].copy_from_slice(&(SECTOR_COUNT as u32 - 1).to_be_bytes());
+                response[4..8].copy_from_slice(&(SECTOR_SIZE as u32).to_be_bytes());
+                Ok((8, false, 0))
+            }
+            scsi::READ_10 | scsi::WRITE_10 => {
+                check_medium(&mut self.medium)?;
+                let lba = u32::from_be_bytes(command[2..6].try_into().unwrap()) as usize;
+                let blocks = u16::from_be_bytes([command[7], command[8]]) as usize;
+                if lba + blocks > SECTOR_COUNT {
+                    return Err(Sense::LBA_OUT_OF_RANGE);
+                }
+                let len = blocks * SECTOR_SIZE;
+                if len != transfer_len {
+                    return Err(Sense::INVALID_FIELD);
+                }
+                Ok((len, true, lba * SECTOR_SIZE))
+            }
+            _ => Err(Sense::INVALID_COMMAND),
+        }
+    }
+
+    fn csw(&self) -> [u8; CSW_SIZE] {
+        let mut csw = [0; CSW_SIZE];
+        csw[0..4].copy_from_slice(CSW_SIGNATURE);
+        csw[4..8].copy_from_slice(&self.tag);
+        csw[8..12].copy_from_slice(&self.residue.to_le_bytes());
+        csw[12] = self.status;
+        csw
+    }
+}
+
+fn check_medium(medium: &mut Medium) -> Result<(), Sense> {
+    match medium {
+        Medium::Present => Ok(()),
+        Medium::NotPresent => Err(Sense::MEDIUM_NOT_PRESENT),
+        Medium::Changed => {
+            *medium = Medium::Present;
+            Err(Sense::MEDIUM_CHANGED)
+        }
+    }
+}
+
+impl<B: UsbBus> UsbClass<B> for MassStorage<'_, B> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        writer.interface(
+            self.interface,
+            CLASS_MASS_STORAGE,
+            SUBCLASS_SCSI,
+            PROTOCOL_BULK_ONLY,
+        )?;
+        writer.endpoint(&self.ep_in)?;
+        writer.endpoint(&self.ep_out)
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Idle;
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<B>) {
+        let request = xfer.request();
+        if request.request_type == control::RequestType::Class
+            && request.recipient == control::Recipient::Interface
+            && request.index == u8::from(self.interface) as u16
+            && request.request == REQUEST_GET_MAX_LUN
+        {
+            // Only a single logical unit
+            xfer.accept_with(&[0]).ok();
+        }
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<B>) {
+        let request = xfer.request();
+        if request.request_type == control::RequestType::Class
+            && request.recipient == control::Recipient::Interface
+            && request.index == u8::from(self.interface) as u16
+            && request.request == REQUEST_RESET
+        {
+            self.state = State::Idle;
+            xfer.accept().ok();
+        }
+    }
+
+    fn poll(&mut self) {
+        loop {
+            match self.state {
+                State::Idle => {
+                    let mut cbw = [0; PACKET_SIZE];
+                    match self.ep_out.read(&mut cbw) {
+                        Ok(len) => self.handle_command(&cbw[..len]),
+                        Err(_) => return,
+                    }
+                }
+                State::DataIn {
+                    from_disk,
+                    offset,
+                    end,
+                } => {
+                    let len = (end - offset).min(PACKET_SIZE);
+                    let source = if from_disk {
+                        &self.disk[offset..offset + len]
+                    } else {
+                        &self.response[offset..offset + len]
+                    };
+                    match self.ep_in.write(source) {
+                        Ok(written) => {
+                            let offset = offset + written;
+                            self.state = if offset == end {
+                                State::Status
+                            } else {
+                                State::DataIn {
+                                    from_disk,
+                                    offset,
+                                    end,
+                                }
+                            };
+                        }
+                        Err(_) => return,
+                    }
+                }
+                State::DataOut {
+                    discard,
+                    offset,
+                    end,
+                } => {
+                    let mut packet = [0; PACKET_SIZE];
+                    match self.ep_out.read(&mut packet) {
+                        Ok(len) => {
+                            let len = len.min(end - offset);
+                            if !discard {
+                                self.disk[offset..offset + len].copy_from_slice(&packet[..len]);
+                                self.written = true;
+                            }
+                            let offset = offset + len;
+                            self.state = if offset == end || len < PACKET_SIZE {
+                                State::Status
+                            } else {
+                                State::DataOut {
+                                    discard,
+                                    offset,
+                                    end,
+                                }
+                            };
+                        }
+                        Err(_) => return,
+                    }
+                }
+                State::Status => match self.ep_in.write(&self.csw()) {
+                    Ok(_) => self.state = State::Idle,
+                    Err(_) => return,
+                },
+            }
+        }
+    }
+}
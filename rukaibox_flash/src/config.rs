@@ -1,37 +1,446 @@
 use arrayvec::ArrayVec;
-use kdl::{KdlDocument, KdlNode};
-use kdl_config::{KdlConfig, KdlConfigFinalize, Parsed, error::ParseError};
+use kdl::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+use kdl_config::{
+    KdlConfig, KdlConfigFinalize, Parsed,
+    error::{ParseDiagnostic, ParseError},
+    parse_helpers::error_diagnostic,
+};
 use kdl_config_derive::{KdlConfig, KdlConfigFinalize};
 use miette::{IntoDiagnostic, NamedSource, miette};
 use rkyv::rancor::Error;
-use rukaibox_config::Config;
-use std::path::PathBuf;
+use rukaibox_config::{
+    Binding, CONFIG_HEADER_SIZE, CONFIG_KDL_SIZE, CONFIG_VERSION, Chord, Config, Coordinate,
+    Coordinates, Latch, LatchKind, Layer, LayerActivation, LayerBinding, LogicalButton,
+    MAX_PROFILES, Overlay, OverlayCondition, PhysicalButton, Ruleset, RulesetViolation,
+    SCHEMA_FINGERPRINT, SocdTypes, TapHold, TriggerLevels,
+};
+use std::path::{Path, PathBuf};
 
 pub fn encode_config(config: &Config) -> miette::Result<Vec<u8>> {
     let bytes = rkyv::to_bytes::<Error>(config).map_err(|e| miette!(e))?;
     let mut result = vec![];
     result.extend((bytes.len() as u32).to_be_bytes());
+    result.extend(SCHEMA_FINGERPRINT.to_be_bytes());
     result.extend(bytes.iter());
     Ok(result)
 }
 
-pub fn load() -> miette::Result<Config> {
-    let input = load_source(None)?;
+/// The inverse of [`encode_config`], returns None if the bytes are not a config for this flash tool's schema.
+pub fn decode_config(bytes: &[u8]) -> Option<Config> {
+    let header = bytes.get(..CONFIG_HEADER_SIZE)?;
+    let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let fingerprint = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    if fingerprint != SCHEMA_FINGERPRINT {
+        return None;
+    }
+    let archive = bytes.get(CONFIG_HEADER_SIZE..CONFIG_HEADER_SIZE.checked_add(size)?)?;
+    // rkyv requires the archive to be aligned, which a slice into the read bytes is not.
+    let mut aligned = rkyv::util::AlignedVec::<16>::new();
+    aligned.extend_from_slice(archive);
+    rkyv::from_bytes::<Config, Error>(&aligned).ok()
+}
+
+pub fn encode_config_kdl(source: &str) -> miette::Result<Vec<u8>> {
+    let mut result = vec![];
+    result.extend((source.len() as u32).to_be_bytes());
+    result.extend(source.as_bytes());
+    if result.len() > CONFIG_KDL_SIZE {
+        return Err(miette!(
+            "Config file is too large to store on the controller, is {:?} bytes but must be at most {:?} bytes.",
+            source.len(),
+            CONFIG_KDL_SIZE - 4
+        ));
+    }
+    Ok(result)
+}
+
+/// Migrations are applied in order, entry `i` upgrades a config from version `i` to version `i + 1`.
+///
+/// When the config format changes, append a migration here that rewrites the old format into the new one.
+/// This keeps config files written for older versions of the flash tool working.
+const MIGRATIONS: &[fn(&mut KdlDocument)] = &[
+    add_coordinates,
+    add_overlays,
+    add_ledgedash_socd_override,
+    add_triggers,
+    add_cstick_mode,
+    add_crouch_walk_prevention,
+    add_travel_time,
+    add_ruleset,
+    split_socd,
+    merge_stick_up2,
+    add_chords,
+    add_layers,
+    add_tap_holds,
+    add_latches,
+    add_activation_hold_ms,
+    add_tap_ms,
+];
+
+/// Version 1 added the `coordinates` node to profiles, older configs did not override any coordinates.
+fn add_coordinates(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "coordinates");
+}
+
+/// Version 2 added the `overlays` node to profiles, older configs did not have any overlays.
+fn add_overlays(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "overlays");
+}
+
+/// Version 3 added `ledgedash-socd-override-ms` to profiles, older configs did not have the override.
+fn add_ledgedash_socd_override(kdl: &mut KdlDocument) {
+    add_profile_value_node(kdl, "ledgedash-socd-override-ms", KdlValue::Integer(0));
+}
+
+/// Version 4 added the `triggers` node to profiles, older configs used the default trigger levels.
+fn add_triggers(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "triggers");
+}
+
+/// Version 5 added `cstick-mode` to profiles, older configs always used full C-stick magnitude.
+fn add_cstick_mode(kdl: &mut KdlDocument) {
+    add_profile_value_node(kdl, "cstick-mode", KdlValue::String("smash".to_owned()));
+}
+
+/// Version 6 added the `crouch-walk-prevention` node to profiles, older configs did not prevent crouch walking.
+fn add_crouch_walk_prevention(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "crouch-walk-prevention");
+}
+
+/// Version 7 added the `travel-time` node to profiles, older configs moved the sticks instantly.
+fn add_travel_time(kdl: &mut KdlDocument) {
+    for profile in profile_nodes(kdl) {
+        let mut children = KdlDocument::new();
+        for name in ["stick-micros", "cstick-micros"] {
+            let mut node = KdlNode::new(name);
+            node.push(KdlEntry::new(KdlValue::Integer(0)));
+            children.nodes_mut().push(node);
+        }
+        let mut node = KdlNode::new("travel-time");
+        node.set_children(children);
+        profile.ensure_children().nodes_mut().push(node);
+    }
+}
+
+/// Version 8 added `ruleset` to profiles, older configs were not restricted by a ruleset.
+fn add_ruleset(kdl: &mut KdlDocument) {
+    add_profile_value_node(kdl, "ruleset", KdlValue::String("unrestricted".to_owned()));
+}
+
+/// Version 9 split `socd` into a type for each axis, older configs used one type for every stick axis.
+/// The dpad was not resolved, it now uses the same type as the sticks.
+fn split_socd(kdl: &mut KdlDocument) {
+    for profile in profile_nodes(kdl) {
+        let Some(socd) = profile
+            .children_mut()
+            .as_mut()
+            .and_then(|children| children.get_mut("socd"))
+        else {
+            continue;
+        };
+        let Some(value) = socd.entries().first().map(|entry| entry.value().clone()) else {
+            continue;
+        };
+        let mut axes = KdlDocument::new();
+        for name in SocdTypes::NAMES {
+            let mut node = KdlNode::new(*name);
+            node.push(KdlEntry::new(value.clone()));
+            axes.nodes_mut().push(node);
+        }
+        socd.entries_mut().clear();
+        socd.set_children(axes);
+    }
+}
+
+/// Version 10 allowed binding several physical buttons to a logical button,
+/// older configs used `stick-up2` as a second binding for `stick-up`.
+fn merge_stick_up2(kdl: &mut KdlDocument) {
+    for profile in profile_nodes(kdl) {
+        let Some(buttons) = profile
+            .children_mut()
+            .as_mut()
+            .and_then(|children| children.get_mut("buttons"))
+            .and_then(|buttons| buttons.children_mut().as_mut())
+        else {
+            continue;
+        };
+        let Some(index) = buttons
+            .nodes()
+            .iter()
+            .position(|node| node.name().value() == "stick-up2")
+        else {
+            continue;
+        };
+        let stick_up2 = buttons.nodes_mut().remove(index);
+        let Some(stick_up) = buttons.get_mut("stick-up") else {
+            continue;
+        };
+        let is_none = |entry: &KdlEntry| entry.value().as_string() == Some("none");
+        for entry in stick_up2.entries().iter().filter(|entry| !is_none(entry)) {
+            stick_up.entries_mut().retain(|entry| !is_none(entry));
+            // Listing the same button twice in a binding is an error in the current format.
+            if !stick_up
+                .entries()
+                .iter()
+                .any(|bound| bound.value() == entry.value())
+            {
+                stick_up.push(KdlEntry::new(entry.value().clone()));
+            }
+        }
+    }
+}
+
+/// Version 11 added the `chords` node to profiles, older configs did not have any chords.
+fn add_chords(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "chords");
+}
+
+/// Version 12 added the `layers` node to profiles, older configs did not have any layers.
+fn add_layers(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "layers");
+}
+
+/// Version 13 added the `tap-holds` node to profiles, older configs did not have any tap-holds.
+fn add_tap_holds(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "tap-holds");
+}
+
+/// Version 14 added the `latches` node to profiles, older configs did not have any latches.
+fn add_latches(kdl: &mut KdlDocument) {
+    add_empty_profile_node(kdl, "latches");
+}
+
+/// Version 15 added `activation-hold-ms` to profiles, older configs switched profile as soon as the combination was held.
+fn add_activation_hold_ms(kdl: &mut KdlDocument) {
+    add_profile_value_node(kdl, "activation-hold-ms", KdlValue::Integer(0));
+}
+
+/// Version 16 added the optional `tap-ms` node to tap-holds, older configs use the default so nothing changes.
+fn add_tap_ms(_kdl: &mut KdlDocument) {}
+
+fn add_profile_value_node(kdl: &mut KdlDocument, name: &str, value: KdlValue) {
+    for profile in profile_nodes(kdl) {
+        let mut node = KdlNode::new(name);
+        node.push(KdlEntry::new(value.clone()));
+        profile.ensure_children().nodes_mut().push(node);
+    }
+}
+
+fn add_empty_profile_node(kdl: &mut KdlDocument, name: &str) {
+    for profile in profile_nodes(kdl) {
+        let mut node = KdlNode::new(name);
+        node.set_children(KdlDocument::new());
+        profile.ensure_children().nodes_mut().push(node);
+    }
+}
+
+fn profile_nodes(kdl: &mut KdlDocument) -> impl Iterator<Item = &mut KdlNode> {
+    kdl.get_mut("profiles")
+        .and_then(|profiles| profiles.children_mut().as_mut())
+        .into_iter()
+        .flat_map(|profiles| profiles.nodes_mut())
+}
+
+/// The config version produced by this version of the flash tool.
+pub const CURRENT_VERSION: u32 = CONFIG_VERSION;
+
+const _: () = assert!(
+    MIGRATIONS.len() as u32 == CONFIG_VERSION,
+    "rukaibox_config::CONFIG_VERSION must be incremented when a migration is added"
+);
+
+/// Load the config file, returning the config and the KDL source it was loaded from.
+/// If the config file is from an older version it is migrated to the current version.
+/// When `write_upgrade` is set the migrated config is also written back to the config file.
+pub fn load(write_upgrade: bool) -> miette::Result<(Config, String)> {
+    let path = config_path(None);
+    let input = load_source(&path)?;
     // TODO: upstream a way to tell KDL parser what the filename is.
-    let kdl: KdlDocument = input.inner().parse()?;
-    let (profile, error): (Parsed<ConfigKdl>, ParseError) = kdl_config::parse(input, kdl);
+    let mut kdl: KdlDocument = input.inner().parse()?;
+
+    let input = match migrate(&mut kdl)? {
+        Some(old_version) => {
+            // Spans in the migrated document no longer match the original text,
+            // so reparse the migrated text to keep diagnostics pointing at the right place.
+            let text = kdl.to_string();
+            if write_upgrade {
+                std::fs::write(&path, &text)
+                    .into_diagnostic()
+                    .map_err(|e| e.context(format!("Failed to write config file at {path:?}")))?;
+                println!(
+                    "Upgraded config file at {path:?} from version {old_version} to version {CURRENT_VERSION}"
+                );
+            } else {
+                println!(
+                    "Config file at {path:?} is version {old_version}, it was upgraded to version {CURRENT_VERSION} before flashing.\n\
+                     Run with --upgrade-config to write the upgraded config back to the file."
+                );
+            }
+            kdl = text.parse()?;
+            NamedSource::new(input.name(), text)
+        }
+        None => input,
+    };
 
-    // TODO: extra diagnostics here.
+    let source = input.inner().clone();
+    let config = parse(input, kdl)?;
+    report_coordinate_overrides(&config);
+    Ok((config.finalize(), source))
+}
+
+/// Parse a document that is already at [`CURRENT_VERSION`], reporting every error found in it.
+fn parse(input: NamedSource<String>, kdl: KdlDocument) -> miette::Result<ConfigKdl> {
+    let (config, mut error): (Parsed<ConfigKdl>, ParseError) = kdl_config::parse(input, kdl);
+
+    check_coordinates(&config.value, &mut error);
+    // Rulesets are checked against the finalized profiles, which are only meaningful once everything else is valid.
+    if error.diagnostics.is_empty() {
+        check_rulesets(&config.value, &mut error);
+    }
 
     if !error.diagnostics.is_empty() {
         return Err(error.into());
     }
+    Ok(config.value)
+}
+
+/// Coordinate names depend on the profile's logic, so they can only be checked once the whole profile is parsed.
+fn check_coordinates(config: &ConfigKdl, error: &mut ParseError) {
+    for profile in &config.profiles.value {
+        let logic = profile.value.logic.value.finalize();
+        let names = logic.coordinate_names();
+        let overrides = &profile.value.coordinates.value.overrides;
+        for (i, coordinate) in overrides.iter().enumerate() {
+            let name = &coordinate.value.name;
+            if !names.contains(&name.as_str()) {
+                error.diagnostics.push(error_diagnostic(
+                    error.input.clone(),
+                    coordinate.name_span,
+                    format!("Unknown coordinate {name} for logic {logic:?}"),
+                    format!("Consider one of {names:?}"),
+                ));
+            } else if overrides[..i].iter().any(|x| x.value.name == *name) {
+                error.diagnostics.push(error_diagnostic(
+                    error.input.clone(),
+                    coordinate.name_span,
+                    format!("Coordinate {name} is overridden more than once"),
+                    "Consider removing one of the overrides".to_owned(),
+                ));
+            }
+        }
+    }
+}
 
-    Ok(profile.value.finalize())
+/// Report every setting that breaks its profile's ruleset, pointing at the node responsible for it.
+fn check_rulesets(config: &ConfigKdl, error: &mut ParseError) {
+    for (i, profile) in config.profiles.value.iter().enumerate() {
+        let kdl = &profile.value;
+        let finalized = kdl.finalize();
+        let ruleset = finalized.ruleset.name();
+        finalized.check_ruleset(|violation| {
+            let span = match violation {
+                RulesetViolation::Logic => kdl.logic.full_span,
+                RulesetViolation::Socd { axis } => match axis {
+                    "stick-vertical" => kdl.socd.value.stick_vertical.full_span,
+                    "cstick-horizontal" => kdl.socd.value.cstick_horizontal.full_span,
+                    "cstick-vertical" => kdl.socd.value.cstick_vertical.full_span,
+                    "dpad" => kdl.socd.value.dpad.full_span,
+                    _ => kdl.socd.value.stick_horizontal.full_span,
+                },
+                // Coordinates that are not overridden are the logic's defaults.
+                RulesetViolation::Coordinate { name, .. } => kdl
+                    .coordinates
+                    .value
+                    .overrides
+                    .iter()
+                    .find(|coordinate| coordinate.value.name == name)
+                    .map_or(kdl.logic.full_span, |coordinate| coordinate.full_span),
+                RulesetViolation::OverlayCondition { index, .. }
+                | RulesetViolation::OverlayCoordinate { index, .. } => {
+                    kdl.overlays.value.overlays[index].full_span
+                }
+                RulesetViolation::CrouchWalkPrevention { .. } => {
+                    kdl.crouch_walk_prevention.full_span
+                }
+                RulesetViolation::Chord { index } => kdl.chords.value.chords[index].full_span,
+                RulesetViolation::Layer { index } => kdl.layers.value.layers[index].full_span,
+                RulesetViolation::TapHold { index } => {
+                    kdl.tap_holds.value.tap_holds[index].full_span
+                }
+                RulesetViolation::Latch { index } => kdl.latches.value.latches[index].full_span,
+            };
+            error.diagnostics.push(ParseDiagnostic {
+                input: error.input.clone(),
+                span,
+                message: Some(format!("{violation} in ruleset {ruleset}")),
+                label: Some(format!("not allowed by ruleset {ruleset}")),
+                help: Some(format!(
+                    "Profile {} uses `ruleset {ruleset}`, change this setting to comply with it",
+                    i + 1
+                )),
+                severity: miette::Severity::Error,
+            });
+        });
+    }
+}
+
+/// Print every coordinate replaced by an override, so that it is clear which stick values have changed.
+fn report_coordinate_overrides(config: &ConfigKdl) {
+    for (i, profile) in config.profiles.value.iter().enumerate() {
+        let logic = profile.value.logic.value.finalize();
+        let mut defaults = Coordinates::new(logic);
+        for coordinate in &profile.value.coordinates.value.overrides {
+            let name = &coordinate.value.name;
+            let Some(default) = defaults.get_mut(name) else {
+                continue;
+            };
+            let new = coordinate.value.coordinate;
+            println!(
+                "Profile {} ({logic:?}) coordinate {name} replaced ({}, {}) with ({}, {})",
+                i + 1,
+                default.x,
+                default.y,
+                new.x,
+                new.y
+            );
+        }
+    }
 }
 
-fn load_source(path: Option<PathBuf>) -> miette::Result<NamedSource<String>> {
-    let path = if let Some(path) = path {
+/// Apply all migrations needed to bring the document up to [`CURRENT_VERSION`].
+/// Returns the original version if any migrations were applied.
+fn migrate(kdl: &mut KdlDocument) -> miette::Result<Option<u32>> {
+    let version = match kdl.get_arg("version") {
+        Some(KdlValue::Integer(version)) => u32::try_from(*version)
+            .map_err(|_| miette!("Config version {version} is not a valid version"))?,
+        Some(value) => {
+            return Err(miette!("Config version must be an integer but was {value}"));
+        }
+        None => return Err(miette!("Config is missing the `version` node")),
+    };
+
+    if version > CURRENT_VERSION {
+        return Err(miette!(
+            "Config is version {version} but this flash tool only supports up to version {CURRENT_VERSION}. Download a newer flash tool."
+        ));
+    }
+    if version == CURRENT_VERSION {
+        return Ok(None);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(kdl);
+    }
+
+    let version_node = kdl.get_mut("version").unwrap();
+    version_node.entries_mut().clear();
+    version_node.push(KdlEntry::new(KdlValue::Integer(CURRENT_VERSION as i128)));
+
+    Ok(Some(version))
+}
+
+fn config_path(path: Option<PathBuf>) -> PathBuf {
+    if let Some(path) = path {
         path
     } else if let Ok(cargo_manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
         PathBuf::from(cargo_manifest_dir)
@@ -44,9 +453,12 @@ fn load_source(path: Option<PathBuf>) -> miette::Result<NamedSource<String>> {
             .parent()
             .unwrap()
             .join("config.kdl")
-    };
+    }
+}
+
+fn load_source(path: &Path) -> miette::Result<NamedSource<String>> {
     let filename = path.file_name().unwrap().to_str().unwrap();
-    let text = std::fs::read_to_string(&path)
+    let text = std::fs::read_to_string(path)
         .into_diagnostic()
         .map_err(|e| e.context(format!("Failed to load config file at {path:?}")))?;
     Ok(NamedSource::new(filename, text))
@@ -56,55 +468,1342 @@ fn load_source(path: Option<PathBuf>) -> miette::Result<NamedSource<String>> {
 #[kdl_config_finalize_into = "rukaibox_config::Config"]
 pub struct ConfigKdl {
     pub version: Parsed<u32>,
-    pub profiles: Parsed<ArrayVec<Parsed<ProfileKdl>, 3>>,
+    pub profiles: Parsed<ArrayVec<Parsed<ProfileKdl>, MAX_PROFILES>>,
 }
 
 // TODO: add derive side validation that Parsed is used everywhere.
-#[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
-#[kdl_config_finalize_into = "rukaibox_config::Profile"]
+#[derive(KdlConfig, Default, Debug)]
 pub struct ProfileKdl {
     pub activation_combination: Parsed<ArrayVec<Parsed<PhysicalButtonKdl>, 10>>,
+    pub activation_hold_ms: Parsed<u32>,
     pub logic: Parsed<BaseLogicKdl>,
-    pub socd: Parsed<SocdTypeKdl>,
+    pub socd: Parsed<SocdTypesKdl>,
     pub buttons: Parsed<LogicalButtonToPhysicalButtonKdl>,
+    pub coordinates: Parsed<CoordinatesKdl>,
+    pub overlays: Parsed<OverlaysKdl>,
+    pub ledgedash_socd_override_ms: Parsed<u32>,
+    pub triggers: Parsed<TriggersKdl>,
+    pub cstick_mode: Parsed<CstickModeKdl>,
+    pub crouch_walk_prevention: Parsed<CrouchWalkPreventionKdl>,
+    pub travel_time: Parsed<TravelTimeKdl>,
+    pub ruleset: Parsed<RulesetKdl>,
+    pub chords: Parsed<ChordsKdl>,
+    pub layers: Parsed<LayersKdl>,
+    pub tap_holds: Parsed<TapHoldsKdl>,
+    pub latches: Parsed<LatchesKdl>,
     // pub left_hand: Parsed<LeftHandMapKdl>,
     // pub right_hand: Parsed<RightHandMapKdl>,
 }
 
+/// The coordinates are stored in full for the profile's logic, with the overrides applied over the defaults.
+impl KdlConfigFinalize for ProfileKdl {
+    type FinalizeType = rukaibox_config::Profile;
+    fn finalize(&self) -> Self::FinalizeType {
+        let logic = self.logic.value.finalize();
+        let mut coordinates = Coordinates::new(logic);
+        for coordinate in &self.coordinates.value.overrides {
+            if let Some(value) = coordinates.get_mut(&coordinate.value.name) {
+                *value = coordinate.value.coordinate;
+            }
+        }
+
+        let mut triggers = TriggerLevels::new(logic);
+        for level in &self.triggers.value.overrides {
+            if let Some(value) = triggers.get_mut(&level.value.name) {
+                *value = level.value.value;
+            }
+        }
+
+        rukaibox_config::Profile {
+            activation_combination: self.activation_combination.value.finalize(),
+            activation_hold_ms: self.activation_hold_ms.value,
+            logic,
+            socd: self.socd.value.finalize(),
+            buttons: self.buttons.value.finalize(),
+            coordinates,
+            overlays: self
+                .overlays
+                .value
+                .overlays
+                .iter()
+                .map(|overlay| overlay.value.clone())
+                .collect(),
+            ledgedash_socd_override_ms: self.ledgedash_socd_override_ms.value,
+            triggers,
+            cstick_mode: self.cstick_mode.value.finalize(),
+            crouch_walk_prevention: self.crouch_walk_prevention.value.stick,
+            travel_time: self.travel_time.value.finalize(),
+            ruleset: self.ruleset.value.0,
+            chords: self
+                .chords
+                .value
+                .chords
+                .iter()
+                .map(|chord| chord.value.clone())
+                .collect(),
+            layers: self
+                .layers
+                .value
+                .layers
+                .iter()
+                .filter_map(|layer| layer.value.layer.clone())
+                .collect(),
+            tap_holds: self
+                .tap_holds
+                .value
+                .tap_holds
+                .iter()
+                .map(|tap_hold| tap_hold.value.clone())
+                .collect(),
+            latches: self
+                .latches
+                .value
+                .latches
+                .iter()
+                .map(|latch| latch.value.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Overrides for the named coordinates of the profile's logic, e.g. `mod-x-diagonal-cstick-right 72 61`.
+///
+/// The names are checked against the profile's logic by [`check_coordinates`].
+#[derive(Default, Debug)]
+pub struct CoordinatesKdl {
+    pub overrides: Vec<Parsed<CoordinateOverrideKdl>>,
+}
+
+#[derive(Default, Debug)]
+pub struct CoordinateOverrideKdl {
+    pub name: String,
+    pub coordinate: Coordinate,
+}
+
+impl KdlConfig for CoordinatesKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut overrides = vec![];
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let coordinate = parse_coordinate(&input, child, diagnostics);
+            overrides.push(Parsed {
+                value: CoordinateOverrideKdl {
+                    name: child.name().value().to_owned(),
+                    coordinate: coordinate.unwrap_or_default(),
+                },
+                full_span: child.span(),
+                name_span: child.name().span(),
+                valid: coordinate.is_some(),
+            });
+        }
+
+        Parsed {
+            value: CoordinatesKdl { overrides },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: true,
+        }
+    }
+}
+
+/// Overrides for the analog trigger levels of the profile's logic, e.g. `analog-mod-x 80`.
+#[derive(Default, Debug)]
+pub struct TriggersKdl {
+    pub overrides: Vec<Parsed<TriggerOverrideKdl>>,
+}
+
+#[derive(Default, Debug)]
+pub struct TriggerOverrideKdl {
+    pub name: String,
+    pub value: u8,
+}
+
+impl KdlConfig for TriggersKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut overrides: Vec<Parsed<TriggerOverrideKdl>> = vec![];
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let name = child.name().value();
+            if !TriggerLevels::NAMES.contains(&name) {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!("Unknown trigger level {name}"),
+                    format!("Consider one of {:?}", TriggerLevels::NAMES),
+                ));
+                continue;
+            }
+            if overrides.iter().any(|x| x.value.name == name) {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!("Trigger level {name} is overridden more than once"),
+                    "Consider removing one of the overrides".to_owned(),
+                ));
+                continue;
+            }
+            let value = match &child.entries()[..] {
+                [entry] if entry.name().is_none() => match entry.value() {
+                    KdlValue::Integer(value) => u8::try_from(*value).ok(),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if value.is_none() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    "Trigger level should be a single integer between 0 and 255".to_owned(),
+                    format!("Consider replacing it with `{name} <value>`"),
+                ));
+            }
+            overrides.push(Parsed {
+                value: TriggerOverrideKdl {
+                    name: name.to_owned(),
+                    value: value.unwrap_or_default(),
+                },
+                full_span: child.span(),
+                name_span: child.name().span(),
+                valid: value.is_some(),
+            });
+        }
+
+        Parsed {
+            value: TriggersKdl { overrides },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: true,
+        }
+    }
+}
+
+#[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
+#[kdl_config_finalize_into = "rukaibox_config::TravelTime"]
+pub struct TravelTimeKdl {
+    pub stick_micros: Parsed<u32>,
+    pub cstick_micros: Parsed<u32>,
+}
+
+/// A ruleset name such as `melee-2024`, parsed by hand since the names are not the kebab-case variant names.
+#[derive(Default, Debug)]
+pub struct RulesetKdl(pub Ruleset);
+
+impl KdlConfig for RulesetKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let ruleset = match node.entries() {
+            [entry] if entry.name().is_none() => match entry.value() {
+                KdlValue::String(name) => Ruleset::NAMES
+                    .iter()
+                    .position(|x| *x == name.as_str())
+                    .map(|i| Ruleset::VALUES[i]),
+                _ => None,
+            },
+            _ => None,
+        };
+        if ruleset.is_none() {
+            diagnostics.push(error_diagnostic(
+                input,
+                node.span(),
+                "Ruleset should be a single ruleset name".to_owned(),
+                format!("Consider replacing it with one of {:?}", Ruleset::NAMES),
+            ));
+        }
+
+        Parsed {
+            value: RulesetKdl(ruleset.unwrap_or_default()),
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: ruleset.is_some(),
+        }
+    }
+}
+
+/// Either empty to disable crouch walk prevention, or contains the crouch safe coordinate, e.g. `stick 40 70`.
+#[derive(Default, Debug)]
+pub struct CrouchWalkPreventionKdl {
+    pub stick: Option<Coordinate>,
+}
+
+impl KdlConfig for CrouchWalkPreventionKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut stick = None;
+        let mut valid = true;
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let name = child.name().value();
+            let message = if name != "stick" {
+                Some((
+                    format!("Unknown crouch-walk-prevention node {name}"),
+                    "Consider replacing it with `stick <x> <y>`",
+                ))
+            } else if stick.is_some() {
+                Some((
+                    "Node stick is defined twice".to_owned(),
+                    "Consider removing one of them",
+                ))
+            } else {
+                None
+            };
+            if let Some((message, help)) = message {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.name().span(),
+                    message,
+                    help.to_owned(),
+                ));
+                valid = false;
+                continue;
+            }
+            stick = parse_coordinate(&input, child, diagnostics);
+            valid &= stick.is_some();
+        }
+
+        Parsed {
+            value: CrouchWalkPreventionKdl { stick },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid,
+        }
+    }
+}
+
+/// Parses a node of the form `<name> <x> <y>`, returning None if it is invalid.
+fn parse_coordinate(
+    input: &NamedSource<String>,
+    node: &KdlNode,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Option<Coordinate> {
+    let values: Vec<_> = node
+        .entries()
+        .iter()
+        .map(|entry| match entry.value() {
+            KdlValue::Integer(value) if entry.name().is_none() => Some(*value),
+            _ => None,
+        })
+        .collect();
+    match values.as_slice() {
+        [Some(x), Some(y)] => {
+            let range = 0..=Coordinate::MAX as i128;
+            if range.contains(x) && range.contains(y) {
+                Some(Coordinate::new(*x as u8, *y as u8))
+            } else {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    node.span(),
+                    format!(
+                        "Coordinate values must be between 0 and {} but were {x} {y}",
+                        Coordinate::MAX
+                    ),
+                    "Coordinates are offsets from the center of the stick, the direction the stick is held in determines the sign"
+                        .to_owned(),
+                ));
+                None
+            }
+        }
+        _ => {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                node.span(),
+                "Coordinate should contain exactly 2 integers".to_owned(),
+                format!(
+                    "Consider replacing it with `{} <x> <y>`",
+                    node.name().value()
+                ),
+            ));
+            None
+        }
+    }
+}
+
+/// Overlays replace the coordinates chosen by the profile's logic while their conditions are met, e.g.
+/// ```kdl
+/// - mod-x diagonal b cstick-up {
+///     stick 59 43
+/// }
+/// ```
+#[derive(Default, Debug)]
+pub struct OverlaysKdl {
+    pub overlays: ArrayVec<Parsed<Overlay>, 8>,
+}
+
+impl KdlConfig for OverlaysKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut overlays = ArrayVec::new();
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let overlay = parse_overlay(&input, child, diagnostics);
+            if overlays.is_full() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!(
+                        "A profile can have at most {} overlays",
+                        overlays.capacity()
+                    ),
+                    "Consider removing this overlay".to_owned(),
+                ));
+                break;
+            }
+            overlays.push(overlay);
+        }
+
+        Parsed {
+            value: OverlaysKdl { overlays },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: true,
+        }
+    }
+}
+
+fn parse_overlay(
+    input: &NamedSource<String>,
+    node: &KdlNode,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Parsed<Overlay> {
+    let mut overlay = Overlay::default();
+    let mut valid = true;
+
+    if node.name().value() != "-" {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.name().span(),
+            "Overlays are list items and must be named \"-\"".to_owned(),
+            format!("Consider replacing {:?} with \"-\"", node.name().value()),
+        ));
+        valid = false;
+    }
+
+    for entry in node.entries() {
+        let condition = match entry.value() {
+            KdlValue::String(name) if entry.name().is_none() => OverlayCondition::NAMES
+                .iter()
+                .position(|x| *x == name.as_str())
+                .map(|i| OverlayCondition::VALUES[i]),
+            _ => None,
+        };
+        let Some(condition) = condition else {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                entry.span(),
+                format!("Unknown overlay condition {}", entry.value()),
+                format!("Consider one of {:?}", OverlayCondition::NAMES),
+            ));
+            valid = false;
+            continue;
+        };
+        if overlay.conditions.try_push(condition).is_err() {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                entry.span(),
+                format!(
+                    "An overlay can have at most {} conditions",
+                    overlay.conditions.capacity()
+                ),
+                "Consider removing this condition".to_owned(),
+            ));
+            valid = false;
+        }
+    }
+
+    let children = node.children().iter().flat_map(|children| children.nodes());
+    for child in children {
+        let target = match child.name().value() {
+            "stick" => &mut overlay.stick,
+            "cstick" => &mut overlay.cstick,
+            name => {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.name().span(),
+                    format!("Unknown overlay node {name}"),
+                    "Consider one of [\"stick\", \"cstick\"]".to_owned(),
+                ));
+                valid = false;
+                continue;
+            }
+        };
+        if target.is_some() {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                child.name().span(),
+                format!("Overlay node {} is defined twice", child.name().value()),
+                "Consider removing one of them".to_owned(),
+            ));
+            valid = false;
+        }
+        match parse_coordinate(input, child, diagnostics) {
+            Some(coordinate) => *target = Some(coordinate),
+            None => valid = false,
+        }
+    }
+
+    if overlay.stick.is_none() && overlay.cstick.is_none() {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.span(),
+            "Overlay does not replace any coordinates".to_owned(),
+            "Consider adding a `stick <x> <y>` or `cstick <x> <y>` child".to_owned(),
+        ));
+        valid = false;
+    }
+
+    Parsed {
+        value: overlay,
+        full_span: node.span(),
+        name_span: node.name().span(),
+        valid,
+    }
+}
+
+/// Chords press a logical button while all of their physical buttons are held together, e.g.
+/// ```kdl
+/// - left-thumb-left right-ring {
+///     button z
+///     members consume
+/// }
+/// ```
+#[derive(Default, Debug)]
+pub struct ChordsKdl {
+    pub chords: ArrayVec<Parsed<Chord>, 8>,
+}
+
+impl KdlConfig for ChordsKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut chords = ArrayVec::new();
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let chord = parse_chord(&input, child, diagnostics);
+            if chords.is_full() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!("A profile can have at most {} chords", chords.capacity()),
+                    "Consider removing this chord".to_owned(),
+                ));
+                break;
+            }
+            chords.push(chord);
+        }
+
+        Parsed {
+            value: ChordsKdl { chords },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: true,
+        }
+    }
+}
+
+/// Whether the members of a chord are consumed, named by the `members` node.
+const CHORD_MEMBERS: [&str; 2] = ["forward", "consume"];
+
+fn parse_chord(
+    input: &NamedSource<String>,
+    node: &KdlNode,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Parsed<Chord> {
+    let mut chord = Chord::default();
+    let mut valid = true;
+
+    if node.name().value() != "-" {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.name().span(),
+            "Chords are list items and must be named \"-\"".to_owned(),
+            format!("Consider replacing {:?} with \"-\"", node.name().value()),
+        ));
+        valid = false;
+    }
+
+    for entry in node.entries() {
+        let button = entry_value(entry, PhysicalButton::NAMES, PhysicalButton::VALUES);
+        let Some(button) = button else {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                entry.span(),
+                format!("Unknown physical button {}", entry.value()),
+                format!("Consider one of {:?}", PhysicalButton::NAMES),
+            ));
+            valid = false;
+            continue;
+        };
+        if button == PhysicalButton::None {
+            continue;
+        }
+        if chord.buttons.try_push(button).is_err() {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                entry.span(),
+                format!(
+                    "A chord can have at most {} buttons",
+                    chord.buttons.capacity()
+                ),
+                "Consider removing this button".to_owned(),
+            ));
+            valid = false;
+        }
+    }
+    if valid && chord.buttons.len() < 2 {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.span(),
+            "Chord should list at least 2 physical buttons".to_owned(),
+            "Consider binding a single button under `buttons` instead".to_owned(),
+        ));
+        valid = false;
+    }
+
+    let mut logical = None;
+    let mut consume = None;
+    let children = node.children().iter().flat_map(|children| children.nodes());
+    for child in children {
+        let single_entry = match child.entries() {
+            [entry] => Some(entry),
+            _ => None,
+        };
+        let (seen, parsed, names) = match child.name().value() {
+            "button" => {
+                let value = single_entry.and_then(|entry| {
+                    entry_value(entry, LogicalButton::NAMES, LogicalButton::VALUES)
+                });
+                let seen = logical.is_some();
+                logical = logical.or(value);
+                (seen, value.is_some(), LogicalButton::NAMES)
+            }
+            "members" => {
+                let value = single_entry
+                    .and_then(|entry| entry_value(entry, &CHORD_MEMBERS, &[false, true]));
+                let seen = consume.is_some();
+                consume = consume.or(value);
+                (seen, value.is_some(), CHORD_MEMBERS.as_slice())
+            }
+            name => {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.name().span(),
+                    format!("Unknown chord node {name}"),
+                    "Consider one of [\"button\", \"members\"]".to_owned(),
+                ));
+                valid = false;
+                continue;
+            }
+        };
+        if seen {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                child.name().span(),
+                format!("Chord node {} is defined twice", child.name().value()),
+                "Consider removing one of them".to_owned(),
+            ));
+            valid = false;
+        } else if !parsed {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                child.span(),
+                format!(
+                    "Chord node {} should be a single name",
+                    child.name().value()
+                ),
+                format!("Consider one of {names:?}"),
+            ));
+            valid = false;
+        }
+    }
+
+    match logical {
+        Some(logical) => chord.logical = logical,
+        None if valid => {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                node.span(),
+                "Chord does not press a logical button".to_owned(),
+                "Consider adding a `button <logical>` child, e.g. `button z`".to_owned(),
+            ));
+            valid = false;
+        }
+        None => {}
+    }
+    chord.consume = consume.unwrap_or_default();
+
+    Parsed {
+        value: chord,
+        full_span: node.span(),
+        name_span: node.name().span(),
+        valid,
+    }
+}
+
+/// Layers override some of the profile's button bindings while active, listed from highest to lowest priority, e.g.
+/// ```kdl
+/// - menu {
+///     activation hold right-pinky2
+///     buttons {
+///         dpad-up right-thumb-up
+///     }
+/// }
+/// ```
+#[derive(Default, Debug)]
+pub struct LayersKdl {
+    pub layers: ArrayVec<Parsed<LayerKdl>, 4>,
+}
+
+/// A layer along with its name, the layer is None when its activation is invalid.
+#[derive(Default, Debug)]
+pub struct LayerKdl {
+    pub name: String,
+    pub layer: Option<Layer>,
+}
+
+impl KdlConfig for LayersKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut layers: ArrayVec<Parsed<LayerKdl>, 4> = ArrayVec::new();
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let layer = parse_layer(&input, child, diagnostics);
+            if layers
+                .iter()
+                .any(|existing| existing.value.name == layer.value.name)
+            {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!("Layer {} is defined twice", layer.value.name),
+                    "Consider renaming one of them".to_owned(),
+                ));
+            }
+            if layers.is_full() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!("A profile can have at most {} layers", layers.capacity()),
+                    "Consider removing this layer".to_owned(),
+                ));
+                break;
+            }
+            layers.push(layer);
+        }
+
+        Parsed {
+            value: LayersKdl { layers },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: true,
+        }
+    }
+}
+
+fn parse_layer(
+    input: &NamedSource<String>,
+    node: &KdlNode,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Parsed<LayerKdl> {
+    let mut valid = true;
+
+    if node.name().value() != "-" {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.name().span(),
+            "Layers are list items and must be named \"-\"".to_owned(),
+            format!("Consider replacing {:?} with \"-\"", node.name().value()),
+        ));
+        valid = false;
+    }
+    let name = match node.entries() {
+        [entry] if entry.name().is_none() => entry.value().as_string().map(str::to_owned),
+        _ => None,
+    };
+    let name = name.unwrap_or_else(|| {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.span(),
+            "Layer should be followed by a single name".to_owned(),
+            "Consider naming it after what it is used for, e.g. `- menu { }`".to_owned(),
+        ));
+        valid = false;
+        String::new()
+    });
+
+    let mut activation = None;
+    let mut bindings: ArrayVec<LayerBinding, 12> = ArrayVec::new();
+    let children = node.children().iter().flat_map(|children| children.nodes());
+    for child in children {
+        match child.name().value() {
+            "activation" => {
+                let parsed = match child.entries() {
+                    [kind, button] => entry_value(
+                        kind,
+                        LayerActivation::NAMES,
+                        &[LayerActivation::Hold, LayerActivation::Toggle],
+                    )
+                    .zip(entry_value(
+                        button,
+                        PhysicalButton::NAMES,
+                        PhysicalButton::VALUES,
+                    ))
+                    .filter(|(_, button)| *button != PhysicalButton::None)
+                    .map(|(kind, button)| kind(button)),
+                    _ => None,
+                };
+                if activation.is_some() {
+                    diagnostics.push(error_diagnostic(
+                        input.clone(),
+                        child.name().span(),
+                        "Layer node activation is defined twice".to_owned(),
+                        "Consider removing one of them".to_owned(),
+                    ));
+                    valid = false;
+                } else if parsed.is_none() {
+                    diagnostics.push(error_diagnostic(
+                        input.clone(),
+                        child.span(),
+                        "Layer activation should be `hold` or `toggle` followed by a physical button"
+                            .to_owned(),
+                        "Consider e.g. `activation hold right-pinky2`".to_owned(),
+                    ));
+                    valid = false;
+                }
+                activation = activation.or(parsed);
+            }
+            "buttons" => {
+                let buttons = child
+                    .children()
+                    .iter()
+                    .flat_map(|children| children.nodes());
+                for button in buttons {
+                    let name = button.name().value();
+                    let Some(logical) = LogicalButton::NAMES
+                        .iter()
+                        .position(|x| *x == name)
+                        .map(|i| LogicalButton::VALUES[i])
+                    else {
+                        diagnostics.push(error_diagnostic(
+                            input.clone(),
+                            button.name().span(),
+                            format!("Unknown logical button {name}"),
+                            format!("Consider one of {:?}", LogicalButton::NAMES),
+                        ));
+                        valid = false;
+                        continue;
+                    };
+                    if bindings.iter().any(|existing| existing.logical == logical) {
+                        diagnostics.push(error_diagnostic(
+                            input.clone(),
+                            button.name().span(),
+                            format!("Layer button {name} is defined twice"),
+                            "Consider removing one of them".to_owned(),
+                        ));
+                        valid = false;
+                    }
+                    let binding = BindingKdl::parse_as_node(input.clone(), button, diagnostics);
+                    valid &= binding.valid;
+                    let layer_binding = LayerBinding {
+                        logical,
+                        binding: binding.value.0,
+                    };
+                    if bindings.try_push(layer_binding).is_err() {
+                        diagnostics.push(error_diagnostic(
+                            input.clone(),
+                            button.span(),
+                            format!(
+                                "A layer can override at most {} buttons",
+                                bindings.capacity()
+                            ),
+                            "Consider removing this button".to_owned(),
+                        ));
+                        valid = false;
+                    }
+                }
+            }
+            name => {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.name().span(),
+                    format!("Unknown layer node {name}"),
+                    "Consider one of [\"activation\", \"buttons\"]".to_owned(),
+                ));
+                valid = false;
+            }
+        }
+    }
+
+    if activation.is_none() && valid {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.span(),
+            "Layer is never activated".to_owned(),
+            "Consider adding an `activation hold <button>` or `activation toggle <button>` child"
+                .to_owned(),
+        ));
+        valid = false;
+    }
+
+    Parsed {
+        value: LayerKdl {
+            name,
+            layer: activation.map(|activation| Layer {
+                activation,
+                bindings,
+            }),
+        },
+        full_span: node.span(),
+        name_span: node.name().span(),
+        valid,
+    }
+}
+
+/// Tap-holds activate one logical button when a physical button is tapped and another when it is held, e.g.
+/// ```kdl
+/// - left-thumb-left {
+///     tap dpad-up
+///     hold mod-x
+///     tapping-term-ms 200
+///     on-other-key-press hold
+/// }
+/// ```
+#[derive(Default, Debug)]
+pub struct TapHoldsKdl {
+    pub tap_holds: ArrayVec<Parsed<TapHold>, 4>,
+}
+
+impl KdlConfig for TapHoldsKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut tap_holds: ArrayVec<Parsed<TapHold>, 4> = ArrayVec::new();
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let tap_hold = parse_tap_hold(&input, child, diagnostics);
+            if tap_hold.valid
+                && tap_holds
+                    .iter()
+                    .any(|existing| existing.value.button == tap_hold.value.button)
+            {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!(
+                        "Physical button {} has more than one tap-hold",
+                        child.entries()[0].value()
+                    ),
+                    "Consider removing one of them".to_owned(),
+                ));
+            }
+            if tap_holds.is_full() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!(
+                        "A profile can have at most {} tap-holds",
+                        tap_holds.capacity()
+                    ),
+                    "Consider removing this tap-hold".to_owned(),
+                ));
+                break;
+            }
+            tap_holds.push(tap_hold);
+        }
+
+        Parsed {
+            value: TapHoldsKdl { tap_holds },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: true,
+        }
+    }
+}
+
+/// What a tap-hold does when another button is pressed before it is decided, named by the `on-other-key-press` node.
+const TAP_HOLD_OTHER_KEY_PRESS: [&str; 2] = ["wait", "hold"];
+
+fn parse_tap_hold(
+    input: &NamedSource<String>,
+    node: &KdlNode,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Parsed<TapHold> {
+    let mut tap_hold = TapHold::default();
+    let mut valid = true;
+
+    if node.name().value() != "-" {
+        diagnostics.push(error_diagnostic(
+            input.clone(),
+            node.name().span(),
+            "Tap-holds are list items and must be named \"-\"".to_owned(),
+            format!("Consider replacing {:?} with \"-\"", node.name().value()),
+        ));
+        valid = false;
+    }
+    let button = match node.entries() {
+        [entry] => entry_value(entry, PhysicalButton::NAMES, PhysicalButton::VALUES),
+        _ => None,
+    };
+    match button {
+        Some(button) if button != PhysicalButton::None => tap_hold.button = button,
+        _ => {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                node.span(),
+                "Tap-hold should be followed by a single physical button".to_owned(),
+                format!("Consider one of {:?}", PhysicalButton::NAMES),
+            ));
+            valid = false;
+        }
+    }
+
+    let mut tap = None;
+    let mut hold = None;
+    let mut tapping_term_ms = None;
+    let mut hold_on_other_key_press = None;
+    let mut tap_ms = None;
+    let children = node.children().iter().flat_map(|children| children.nodes());
+    for child in children {
+        let single_entry = match child.entries() {
+            [entry] => Some(entry),
+            _ => None,
+        };
+        let logical = || {
+            single_entry
+                .and_then(|entry| entry_value(entry, LogicalButton::NAMES, LogicalButton::VALUES))
+        };
+        let logical_help = || format!("Consider one of {:?}", LogicalButton::NAMES);
+        let millis = single_entry.and_then(|entry| match entry.value() {
+            KdlValue::Integer(value) if entry.name().is_none() => u32::try_from(*value).ok(),
+            _ => None,
+        });
+        let (seen, parsed, help) = match child.name().value() {
+            "tap" => {
+                let seen = tap.is_some();
+                tap = tap.or(logical());
+                (seen, logical().is_some(), logical_help())
+            }
+            "hold" => {
+                let seen = hold.is_some();
+                hold = hold.or(logical());
+                (seen, logical().is_some(), logical_help())
+            }
+            "tapping-term-ms" => {
+                let seen = tapping_term_ms.is_some();
+                tapping_term_ms = tapping_term_ms.or(millis);
+                (
+                    seen,
+                    millis.is_some(),
+                    "Consider a whole number of milliseconds, e.g. `tapping-term-ms 200`"
+                        .to_owned(),
+                )
+            }
+            "tap-ms" => {
+                let seen = tap_ms.is_some();
+                tap_ms = tap_ms.or(millis);
+                (
+                    seen,
+                    millis.is_some(),
+                    "Consider a whole number of milliseconds, e.g. `tap-ms 34`".to_owned(),
+                )
+            }
+            "on-other-key-press" => {
+                let value = single_entry.and_then(|entry| {
+                    entry_value(entry, &TAP_HOLD_OTHER_KEY_PRESS, &[false, true])
+                });
+                let seen = hold_on_other_key_press.is_some();
+                hold_on_other_key_press = hold_on_other_key_press.or(value);
+                (
+                    seen,
+                    value.is_some(),
+                    format!("Consider one of {TAP_HOLD_OTHER_KEY_PRESS:?}"),
+                )
+            }
+            name => {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.name().span(),
+                    format!("Unknown tap-hold node {name}"),
+                    "Consider one of [\"tap\", \"hold\", \"tapping-term-ms\", \"on-other-key-press\", \"tap-ms\"]"
+                        .to_owned(),
+                ));
+                valid = false;
+                continue;
+            }
+        };
+        if seen {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                child.name().span(),
+                format!("Tap-hold node {} is defined twice", child.name().value()),
+                "Consider removing one of them".to_owned(),
+            ));
+            valid = false;
+        } else if !parsed {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                child.span(),
+                format!(
+                    "Tap-hold node {} has an invalid value",
+                    child.name().value()
+                ),
+                help,
+            ));
+            valid = false;
+        }
+    }
+
+    for (name, missing) in [
+        ("tap", tap.is_none()),
+        ("hold", hold.is_none()),
+        ("tapping-term-ms", tapping_term_ms.is_none()),
+    ] {
+        if missing && valid {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                node.span(),
+                format!("Tap-hold is missing node {name}"),
+                format!("Consider adding a `{name}` child"),
+            ));
+            valid = false;
+        }
+    }
+    tap_hold.tap = tap.unwrap_or_default();
+    tap_hold.hold = hold.unwrap_or_default();
+    tap_hold.tapping_term_ms = tapping_term_ms.unwrap_or_default();
+    tap_hold.hold_on_other_key_press = hold_on_other_key_press.unwrap_or_default();
+    tap_hold.tap_ms = tap_ms.unwrap_or(TapHold::DEFAULT_TAP_MS);
+
+    Parsed {
+        value: tap_hold,
+        full_span: node.span(),
+        name_span: node.name().span(),
+        valid,
+    }
+}
+
+/// Latches keep a logical button pressed without it being held, named by the logical button, e.g. `mod-y lock`.
+#[derive(Default, Debug)]
+pub struct LatchesKdl {
+    pub latches: ArrayVec<Parsed<Latch>, 4>,
+}
+
+impl KdlConfig for LatchesKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut latches: ArrayVec<Parsed<Latch>, 4> = ArrayVec::new();
+        for child in node.children().iter().flat_map(|children| children.nodes()) {
+            let name = child.name().value();
+            let Some(logical) = LogicalButton::NAMES
+                .iter()
+                .position(|x| *x == name)
+                .map(|i| LogicalButton::VALUES[i])
+            else {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.name().span(),
+                    format!("Unknown logical button {name}"),
+                    format!("Consider one of {:?}", LogicalButton::NAMES),
+                ));
+                continue;
+            };
+            let mut valid = true;
+            if latches
+                .iter()
+                .any(|existing| existing.value.logical == logical)
+            {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.name().span(),
+                    format!("Latch {name} is defined twice"),
+                    "Consider removing one of them".to_owned(),
+                ));
+                valid = false;
+            }
+            let kind = match child.entries() {
+                [entry] => entry_value(entry, LatchKind::NAMES, LatchKind::VALUES),
+                _ => None,
+            };
+            if kind.is_none() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!("Latch {name} should be followed by a single latch kind"),
+                    format!("Consider one of {:?}", LatchKind::NAMES),
+                ));
+                valid = false;
+            }
+            if latches.is_full() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    child.span(),
+                    format!("A profile can have at most {} latches", latches.capacity()),
+                    "Consider removing this latch".to_owned(),
+                ));
+                break;
+            }
+            latches.push(Parsed {
+                value: Latch {
+                    logical,
+                    kind: kind.unwrap_or_default(),
+                },
+                full_span: child.span(),
+                name_span: child.name().span(),
+                valid,
+            });
+        }
+
+        Parsed {
+            value: LatchesKdl { latches },
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid: true,
+        }
+    }
+}
+
+/// Looks up a string entry in `names`, returning the matching entry of `values`.
+fn entry_value<T: Copy>(entry: &KdlEntry, names: &[&str], values: &[T]) -> Option<T> {
+    match entry.value() {
+        KdlValue::String(name) if entry.name().is_none() => names
+            .iter()
+            .position(|x| *x == name.as_str())
+            .map(|i| values[i]),
+        _ => None,
+    }
+}
+
 #[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
 #[kdl_config_finalize_into = "rukaibox_config::LogicalButtonToPhysicalButton"]
 pub struct LogicalButtonToPhysicalButtonKdl {
-    pub mod_x: Parsed<PhysicalButtonKdl>,
-    pub mod_y: Parsed<PhysicalButtonKdl>,
-
-    pub start: Parsed<PhysicalButtonKdl>,
-    pub a: Parsed<PhysicalButtonKdl>,
-    pub b: Parsed<PhysicalButtonKdl>,
-    pub x: Parsed<PhysicalButtonKdl>,
-    pub y: Parsed<PhysicalButtonKdl>,
-    pub z: Parsed<PhysicalButtonKdl>,
-
-    pub dpad_up: Parsed<PhysicalButtonKdl>,
-    pub dpad_down: Parsed<PhysicalButtonKdl>,
-    pub dpad_left: Parsed<PhysicalButtonKdl>,
-    pub dpad_right: Parsed<PhysicalButtonKdl>,
-
-    pub l_digital: Parsed<PhysicalButtonKdl>,
-    pub r_digital: Parsed<PhysicalButtonKdl>,
-    pub l_analog: Parsed<PhysicalButtonKdl>,
-    pub r_analog: Parsed<PhysicalButtonKdl>,
-
-    pub stick_left: Parsed<PhysicalButtonKdl>,
-    pub stick_right: Parsed<PhysicalButtonKdl>,
-    pub stick_up: Parsed<PhysicalButtonKdl>,
-    /// Quick hack to work around lack of OR
-    pub stick_up2: Parsed<PhysicalButtonKdl>,
-    pub stick_down: Parsed<PhysicalButtonKdl>,
-
-    pub cstick_left: Parsed<PhysicalButtonKdl>,
-    pub cstick_right: Parsed<PhysicalButtonKdl>,
-    pub cstick_up: Parsed<PhysicalButtonKdl>,
-    pub cstick_down: Parsed<PhysicalButtonKdl>,
+    pub mod_x: Parsed<BindingKdl>,
+    pub mod_y: Parsed<BindingKdl>,
+
+    pub start: Parsed<BindingKdl>,
+    pub a: Parsed<BindingKdl>,
+    pub b: Parsed<BindingKdl>,
+    pub x: Parsed<BindingKdl>,
+    pub y: Parsed<BindingKdl>,
+    pub z: Parsed<BindingKdl>,
+
+    pub dpad_up: Parsed<BindingKdl>,
+    pub dpad_down: Parsed<BindingKdl>,
+    pub dpad_left: Parsed<BindingKdl>,
+    pub dpad_right: Parsed<BindingKdl>,
+
+    pub l_digital: Parsed<BindingKdl>,
+    pub r_digital: Parsed<BindingKdl>,
+    pub l_analog: Parsed<BindingKdl>,
+    pub r_analog: Parsed<BindingKdl>,
+
+    pub stick_left: Parsed<BindingKdl>,
+    pub stick_right: Parsed<BindingKdl>,
+    pub stick_up: Parsed<BindingKdl>,
+    pub stick_down: Parsed<BindingKdl>,
+
+    pub cstick_left: Parsed<BindingKdl>,
+    pub cstick_right: Parsed<BindingKdl>,
+    pub cstick_up: Parsed<BindingKdl>,
+    pub cstick_down: Parsed<BindingKdl>,
+}
+
+/// The physical buttons bound to a logical button, e.g. `a right-thumb-middle right-ring`, or `none` to leave it unbound.
+#[derive(Default, Debug)]
+pub struct BindingKdl(pub Binding);
+
+impl KdlConfig for BindingKdl {
+    fn parse_as_node(
+        input: NamedSource<String>,
+        node: &KdlNode,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> Parsed<Self> {
+        let mut binding = Binding::default();
+        let mut valid = true;
+
+        if node.entries().is_empty() {
+            diagnostics.push(error_diagnostic(
+                input.clone(),
+                node.span(),
+                "Button should list at least one physical button".to_owned(),
+                format!(
+                    "Consider `{} none` to leave it unbound",
+                    node.name().value()
+                ),
+            ));
+            valid = false;
+        }
+
+        for entry in node.entries() {
+            let button = entry_value(entry, PhysicalButton::NAMES, PhysicalButton::VALUES);
+            let Some(button) = button else {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    entry.span(),
+                    format!("Unknown physical button {}", entry.value()),
+                    format!("Consider one of {:?}", PhysicalButton::NAMES),
+                ));
+                valid = false;
+                continue;
+            };
+            if button == PhysicalButton::None {
+                continue;
+            }
+            if binding.contains(button) {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    entry.span(),
+                    format!("Physical button {} is listed more than once", entry.value()),
+                    "Consider removing this button".to_owned(),
+                ));
+                valid = false;
+                continue;
+            }
+            if binding.buttons.try_push(button).is_err() {
+                diagnostics.push(error_diagnostic(
+                    input.clone(),
+                    entry.span(),
+                    format!(
+                        "A button can be bound to at most {} physical buttons",
+                        binding.buttons.capacity()
+                    ),
+                    "Consider removing this button".to_owned(),
+                ));
+                valid = false;
+            }
+        }
+
+        Parsed {
+            value: BindingKdl(binding),
+            full_span: node.span(),
+            name_span: node.name().span(),
+            valid,
+        }
+    }
+}
+
+impl KdlConfigFinalize for BindingKdl {
+    type FinalizeType = Binding;
+    fn finalize(&self) -> Self::FinalizeType {
+        self.0.clone()
+    }
 }
 
 // #[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
@@ -141,12 +1840,35 @@ pub struct LogicalButtonToPhysicalButtonKdl {
 //     pub thumb_middle: Parsed<LogicalButtonKdl>,
 // }
 
+#[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
+#[kdl_config_finalize_into = "rukaibox_config::SocdTypes"]
+pub struct SocdTypesKdl {
+    pub stick_horizontal: Parsed<SocdTypeKdl>,
+    pub stick_vertical: Parsed<SocdTypeKdl>,
+    pub cstick_horizontal: Parsed<SocdTypeKdl>,
+    pub cstick_vertical: Parsed<SocdTypeKdl>,
+    pub dpad: Parsed<SocdTypeKdl>,
+}
+
 #[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
 #[kdl_config_finalize_into = "rukaibox_config::SocdType"]
 pub enum SocdTypeKdl {
     #[default]
     SecondInputPriority,
+    SecondInputPriorityNoReactivation,
+    FirstInputPriority,
     Neutral,
+    UpOrLeftPriority,
+    DownOrRightPriority,
+}
+
+#[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
+#[kdl_config_finalize_into = "rukaibox_config::CstickMode"]
+pub enum CstickModeKdl {
+    #[default]
+    Smash,
+    TiltUnlessModX,
+    TiltUnlessModY,
 }
 
 #[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
@@ -156,6 +1878,8 @@ pub enum BaseLogicKdl {
     ProjectPlus,
     Rivals2,
     Ultimate,
+    Melee,
+    Rivals1,
 }
 
 #[derive(KdlConfig, KdlConfigFinalize, Default, Debug)]
@@ -215,9 +1939,430 @@ pub enum LogicalButtonKdl {
     DpadRight,
     ModX,
     ModY,
+    Start,
     A,
     B,
     X,
     Y,
     Z,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CURRENT_VERSION, LatchesKdl, MIGRATIONS, merge_stick_up2, migrate, parse, split_socd,
+    };
+    use kdl::{KdlDocument, KdlValue};
+    use kdl_config::{KdlConfig, KdlConfigFinalize, error::ParseError};
+    use miette::NamedSource;
+    use rukaibox_config::{PhysicalButton, Ruleset, SocdType, SocdTypes};
+
+    /// A Project+ profile as written for the first version of the config format.
+    const VERSION_0: &str = r#"
+version 0
+profiles {
+    - {
+        logic project-plus
+        activation-combination {
+            - start
+            - left-thumb-left
+            - left-pinky
+        }
+        socd neutral
+        buttons {
+            start start
+            l-digital left-pinky
+            r-digital right-index
+            l-analog none
+            r-analog right-ring2
+            stick-up right-pinky
+            stick-up2 left-middle2
+            stick-down left-middle
+            stick-left left-ring
+            stick-right left-index
+            cstick-left right-thumb-left
+            cstick-right right-thumb-right
+            cstick-up right-thumb-up
+            cstick-down right-thumb-down
+            mod-x left-thumb-left
+            mod-y left-thumb-right
+            a right-thumb-middle
+            b right-ring
+            x right-middle
+            y right-middle2
+            z right-index2
+            dpad-up right-pinky2
+            dpad-down none
+            dpad-left none
+            dpad-right none
+        }
+    }
+}
+"#;
+
+    const CONFIG_KDL: &str = include_str!("../../config.kdl");
+
+    fn document(text: &str) -> KdlDocument {
+        text.parse().unwrap()
+    }
+
+    /// The source text and message of every diagnostic reported when parsing `text`.
+    fn diagnostics(text: &str) -> Vec<(&str, String)> {
+        let Err(report) = parse(
+            NamedSource::new("config.kdl", text.to_owned()),
+            document(text),
+        ) else {
+            panic!("expected an error");
+        };
+        let error = report.downcast_ref::<ParseError>().unwrap();
+        error
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic.span;
+                let at = text[span.offset()..span.offset() + span.len()].trim();
+                (at, diagnostic.message.clone().unwrap_or_default())
+            })
+            .collect()
+    }
+
+    /// Formats `kdl` the same regardless of how it was built, so migrated documents can be compared to parsed ones.
+    fn formatted(mut kdl: KdlDocument) -> String {
+        kdl.autoformat();
+        kdl.to_string()
+    }
+
+    #[test]
+    fn migrate_current_version() {
+        let text = format!("version {CURRENT_VERSION}\nprofiles {{\n}}\n");
+        let mut kdl = document(&text);
+        assert_eq!(migrate(&mut kdl).unwrap(), None);
+        assert_eq!(kdl.to_string(), text);
+    }
+
+    #[test]
+    fn migrate_older_version() {
+        let mut kdl = document(&format!("version {}\nprofiles {{\n}}", CURRENT_VERSION - 1));
+        assert_eq!(migrate(&mut kdl).unwrap(), Some(CURRENT_VERSION - 1));
+        assert_eq!(
+            kdl.get_arg("version"),
+            Some(&KdlValue::Integer(CURRENT_VERSION as i128))
+        );
+    }
+
+    #[test]
+    fn migrate_invalid_version() {
+        for (text, message) in [
+            (
+                format!("version {}", CURRENT_VERSION + 1),
+                "only supports up to version",
+            ),
+            ("version \"1\"".to_owned(), "must be an integer"),
+            ("version 1.5".to_owned(), "must be an integer"),
+            ("version -1".to_owned(), "is not a valid version"),
+            ("profiles {\n}".to_owned(), "missing the `version` node"),
+        ] {
+            let error = migrate(&mut document(&text)).unwrap_err();
+            assert!(error.to_string().contains(message), "{text}: {error}");
+        }
+    }
+
+    #[test]
+    fn migrate_from_version_0() {
+        // Every step must leave a document that can be written out and read back in.
+        let mut kdl = document(VERSION_0);
+        for migration in MIGRATIONS {
+            migration(&mut kdl);
+            kdl = document(&kdl.to_string());
+        }
+
+        let mut kdl = document(VERSION_0);
+        assert_eq!(migrate(&mut kdl).unwrap(), Some(0));
+        let text = kdl.to_string();
+        let config = parse(
+            NamedSource::new("config.kdl", text.clone()),
+            document(&text),
+        )
+        .unwrap()
+        .finalize();
+        assert_eq!(config.version, CURRENT_VERSION);
+        let profile = &config.profiles[0];
+        assert_eq!(
+            profile.buttons.stick_up.buttons.as_slice(),
+            [PhysicalButton::RightPinky, PhysicalButton::LeftMiddle2]
+        );
+        assert_eq!(profile.socd, SocdTypes::new(SocdType::Neutral));
+        assert_eq!(profile.ruleset, Ruleset::Unrestricted);
+        assert_eq!(profile.ledgedash_socd_override_ms, 0);
+        assert_eq!(profile.activation_hold_ms, 0);
+        assert!(profile.overlays.is_empty() && profile.chords.is_empty());
+        assert!(profile.layers.is_empty() && profile.tap_holds.is_empty());
+        assert!(profile.latches.is_empty());
+    }
+
+    #[test]
+    fn split_socd_per_axis() {
+        let mut kdl = document(
+            r#"
+            version 8
+            profiles {
+                - {
+                    socd neutral
+                    ruleset unrestricted
+                }
+                - {
+                    ruleset unrestricted
+                }
+            }
+            "#,
+        );
+        split_socd(&mut kdl);
+        // A profile without `socd` is left alone, so it gets the default for every axis.
+        let expected = document(
+            r#"
+            version 8
+            profiles {
+                - {
+                    socd {
+                        stick-horizontal neutral
+                        stick-vertical neutral
+                        cstick-horizontal neutral
+                        cstick-vertical neutral
+                        dpad neutral
+                    }
+                    ruleset unrestricted
+                }
+                - {
+                    ruleset unrestricted
+                }
+            }
+            "#,
+        );
+        assert_eq!(formatted(kdl), formatted(expected));
+    }
+
+    #[test]
+    fn merge_stick_up2_into_stick_up() {
+        let profile = |stick_up: &str, stick_up2: &str| {
+            format!(
+                r#"
+                version 9
+                profiles {{
+                    - {{
+                        buttons {{
+                            a right-thumb-middle
+                            stick-up {stick_up}
+                            stick-up2 {stick_up2}
+                        }}
+                    }}
+                }}
+                "#
+            )
+        };
+        let merged = |stick_up: &str| {
+            format!(
+                r#"
+                version 9
+                profiles {{
+                    - {{
+                        buttons {{
+                            a right-thumb-middle
+                            stick-up {stick_up}
+                        }}
+                    }}
+                }}
+                "#
+            )
+        };
+        for (stick_up, stick_up2, expected) in [
+            ("right-pinky", "left-middle2", "right-pinky left-middle2"),
+            ("right-pinky", "none", "right-pinky"),
+            ("none", "left-middle2", "left-middle2"),
+            ("none", "none", "none"),
+            ("right-pinky", "right-pinky", "right-pinky"),
+        ] {
+            let mut kdl = document(&profile(stick_up, stick_up2));
+            merge_stick_up2(&mut kdl);
+            assert_eq!(
+                formatted(kdl),
+                formatted(document(&merged(expected))),
+                "stick-up {stick_up}, stick-up2 {stick_up2}"
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_binding() {
+        let text = CONFIG_KDL.replacen(
+            "r-digital right-index",
+            "r-digital right-index right-index",
+            1,
+        );
+        assert_eq!(
+            diagnostics(&text),
+            [(
+                "right-index",
+                "Physical button right-index is listed more than once".to_owned()
+            )]
+        );
+    }
+
+    /// config.kdl with each `(from, to)` replacement applied to the first occurrence of `from`.
+    fn config_kdl_with(replacements: &[(&str, &str)]) -> String {
+        let mut text = CONFIG_KDL.to_owned();
+        for (from, to) in replacements {
+            assert!(text.contains(from), "{from:?}");
+            text = text.replacen(from, to, 1);
+        }
+        text
+    }
+
+    #[test]
+    fn duplicate_latch() {
+        let latches = "latches {\n    mod-y lock\n    mod-y toggle\n}";
+        let text = config_kdl_with(&[(
+            "        latches {\n        }",
+            "        latches {\n            mod-y lock\n            mod-y toggle\n        }",
+        )]);
+        assert_eq!(
+            diagnostics(&text),
+            [("mod-y", "Latch mod-y is defined twice".to_owned())]
+        );
+
+        let mut diagnostics = vec![];
+        let parsed = LatchesKdl::parse_as_node(
+            NamedSource::new("config.kdl", latches.to_owned()),
+            &document(latches).nodes()[0],
+            &mut diagnostics,
+        );
+        assert_eq!(diagnostics.len(), 1);
+        let validity: Vec<bool> = parsed.value.latches.iter().map(|x| x.valid).collect();
+        assert_eq!(validity, [true, false]);
+    }
+
+    #[test]
+    fn device_parser_matches() {
+        // The controller parses config.kdl itself in USB drive mode, it must agree with the flash tool.
+        // A migrated config covers the formatting the flash tool writes out when upgrading.
+        let mut migrated = document(VERSION_0);
+        migrate(&mut migrated).unwrap();
+        let layers = r#"        layers {
+            - menu {
+                activation hold right-pinky2
+                buttons {
+                    dpad-up right-thumb-up
+                    dpad-down right-thumb-down left-middle2
+                }
+            }
+            - shield {
+                activation toggle left-middle2
+                buttons {
+                    l-analog none
+                }
+            }
+        }"#;
+        let chords = r#"        chords {
+            - left-thumb-left right-ring {
+                button z
+                members consume
+            }
+            - left-pinky right-index right-ring2 {
+                button start
+                members forward
+            }
+        }"#;
+        let tap_holds = r#"        tap-holds {
+            - left-thumb-left {
+                tap dpad-up
+                hold mod-x
+                tapping-term-ms 200
+                on-other-key-press hold
+                tap-ms 50
+            }
+            - right-pinky2 {
+                tap dpad-down
+                hold mod-y
+                tapping-term-ms 150
+            }
+        }"#;
+        let latches = r#"        latches {
+            mod-y lock
+            l-analog toggle
+        }"#;
+        let overlays = r#"        overlays {
+            - mod-x diagonal b cstick-up {
+                stick 59 43
+            }
+            - horizontal l-analog {
+                cstick 80 0
+                stick 40 0
+            }
+        }"#;
+        let cases: [&[(&str, &str)]; 7] = [
+            &[],
+            &[("        layers {\n        }", layers)],
+            &[("        chords {\n        }", chords)],
+            &[("        tap-holds {\n        }", tap_holds)],
+            &[("        latches {\n        }", latches)],
+            &[("        overlays {\n        }", overlays)],
+            &[
+                ("logic project-plus", "logic melee"),
+                (
+                    "stick-horizontal second-input-priority",
+                    "stick-horizontal second-input-priority-no-reactivation",
+                ),
+                (
+                    "stick-vertical second-input-priority",
+                    "stick-vertical neutral",
+                ),
+                ("ruleset unrestricted", "ruleset melee-2024"),
+            ],
+        ];
+        let texts = cases.into_iter().map(config_kdl_with);
+        for text in texts.chain([migrated.to_string()]) {
+            let flash = parse(
+                NamedSource::new("config.kdl", text.clone()),
+                document(&text),
+            )
+            .unwrap()
+            .finalize();
+            let device = rukaibox_config_kdl::parse_config(&text).unwrap();
+            assert_eq!(device, flash);
+        }
+    }
+
+    #[test]
+    fn device_parser_rejects() {
+        // Both parsers must refuse the same configs, so the controller never stores a config the flash tool would refuse.
+        for replacement in [
+            // A missing node
+            ("        cstick-mode smash\n", ""),
+            // An out of range coordinate
+            (
+                "        coordinates {\n        }",
+                "        coordinates {\n            max 1 200\n        }",
+            ),
+            // A ruleset violation
+            ("ruleset unrestricted", "ruleset melee-2024"),
+            // Too many bindings
+            (
+                "r-digital right-index",
+                "r-digital right-index right-index2 right-ring right-ring2 right-middle",
+            ),
+        ] {
+            let text = config_kdl_with(&[replacement]);
+            assert!(
+                parse(
+                    NamedSource::new("config.kdl", text.clone()),
+                    document(&text)
+                )
+                .is_err(),
+                "{replacement:?}"
+            );
+            assert!(
+                rukaibox_config_kdl::parse_config(&text).is_err(),
+                "{replacement:?}"
+            );
+        }
+    }
+}
@@ -3,11 +3,19 @@ use picoboot_rs::{
     PICO_FLASH_START, PICO_PAGE_SIZE, PICO_SECTOR_SIZE, PICO_STACK_POINTER, PicobootConnection,
     TargetID,
 };
-use rukaibox_config::{CONFIG_OFFSET, CONFIG_SIZE, FIRMWARE_OFFSET, FIRMWARE_SIZE};
+use rukaibox_config::{
+    CONFIG_KDL_OFFSET, CONFIG_OFFSET, CONFIG_SIZE, Config, FIRMWARE_INFO_OFFSET, FIRMWARE_OFFSET,
+    FIRMWARE_SIZE, FirmwareInfo, REMAPPED_CONFIG_OFFSET, SCHEMA_FINGERPRINT,
+};
 use rusb::Context;
 
-pub fn flash_device(firmware: &[u8], config: &[u8]) -> Result<()> {
-    if firmware.len() >= FIRMWARE_SIZE {
+/// Flash the config and its KDL source to the device, along with the firmware if provided.
+///
+/// When the firmware is not provided, the firmware already on the device is checked to be compatible with the config.
+pub fn flash_device(firmware: Option<&[u8]>, config: &[u8], config_kdl: &[u8]) -> Result<()> {
+    if let Some(firmware) = firmware
+        && firmware.len() >= FIRMWARE_SIZE
+    {
         return Err(miette!(
             "Firmware is too large to flash, is {:?} bytes but must be less than {:?} bytes.",
             firmware.len(),
@@ -17,11 +25,50 @@ pub fn flash_device(firmware: &[u8], config: &[u8]) -> Result<()> {
     if config.len() >= CONFIG_SIZE {
         return Err(miette!(
             "Config is too large to flash, is {:?} bytes but must be less than {:?} bytes.",
-            firmware.len(),
+            config.len(),
             CONFIG_SIZE
         ));
     }
 
+    let mut conn = connect()?;
+    match firmware {
+        Some(firmware) => flash_bytes_at_offset(&mut conn, firmware, FIRMWARE_OFFSET),
+        None => check_device_firmware_compatible(&mut conn)?,
+    }
+    flash_bytes_at_offset(&mut conn, config, CONFIG_OFFSET);
+    flash_bytes_at_offset(&mut conn, config_kdl, CONFIG_KDL_OFFSET);
+    // Bindings changed via the controller's remap mode would override the new config, so remove them.
+    conn.flash_erase(
+        PICO_FLASH_START + REMAPPED_CONFIG_OFFSET as u32,
+        CONFIG_SIZE as u32,
+    )
+    .expect("failed to erase flash");
+
+    reboot(&mut conn);
+    Ok(())
+}
+
+/// Read the config the firmware on the device will use, the remapped config if there is one, otherwise the flashed config.
+pub fn read_device_config() -> Result<Config> {
+    let mut conn = connect()?;
+    let config = [REMAPPED_CONFIG_OFFSET, CONFIG_OFFSET]
+        .into_iter()
+        .find_map(|offset| {
+            let bytes = conn
+                .flash_read(PICO_FLASH_START + offset as u32, CONFIG_SIZE as u32)
+                .expect("failed to read flash");
+            crate::config::decode_config(&bytes)
+        });
+    reboot(&mut conn);
+    config.ok_or_else(|| {
+        miette!(
+            "The device does not contain a config for config schema {SCHEMA_FINGERPRINT:08x}.\n\
+             It may not have been flashed yet, or it was flashed with a different version of the flash tool."
+        )
+    })
+}
+
+fn connect() -> Result<PicobootConnection<Context>> {
     let ctx = Context::new().map_err(|e| miette!(e).context("could not initialize libusb"))?;
     // create connection object
     let mut conn =
@@ -31,11 +78,11 @@ pub fn flash_device(firmware: &[u8], config: &[u8]) -> Result<()> {
     conn.access_exclusive_eject()
         .expect("failed to claim access");
     conn.exit_xip().expect("failed to exit from xip mode");
+    Ok(conn)
+}
 
-    flash_bytes_at_offset(&mut conn, firmware, FIRMWARE_OFFSET);
-    flash_bytes_at_offset(&mut conn, config, CONFIG_OFFSET);
-
-    // reboot device to start firmware
+/// Reboot the device to start the firmware.
+fn reboot(conn: &mut PicobootConnection<Context>) {
     let delay = 500; // in milliseconds
     match conn.get_device_type() {
         TargetID::Rp2040 => {
@@ -44,8 +91,27 @@ pub fn flash_device(firmware: &[u8], config: &[u8]) -> Result<()> {
         }
         TargetID::Rp2350 => conn.reboot2_normal(delay).expect("failed to reboot device"),
     }
+}
 
-    Ok(())
+fn check_device_firmware_compatible(conn: &mut PicobootConnection<Context>) -> Result<()> {
+    let bytes = conn
+        .flash_read(
+            PICO_FLASH_START + FIRMWARE_INFO_OFFSET as u32,
+            PICO_PAGE_SIZE,
+        )
+        .expect("failed to read flash");
+    match FirmwareInfo::from_bytes(&bytes) {
+        Some(info) if info.schema_fingerprint == SCHEMA_FINGERPRINT => Ok(()),
+        Some(info) => Err(miette!(
+            "The firmware on the device expects config schema {:08x} but this flash tool produces config schema {SCHEMA_FINGERPRINT:08x}.\n\
+             Flash the firmware alongside the config by running without --config-only.",
+            info.schema_fingerprint
+        )),
+        None => Err(miette!(
+            "The firmware on the device is too old or is not a rukaibox firmware, so the config cannot be checked for compatibility.\n\
+             Flash the firmware alongside the config by running without --config-only."
+        )),
+    }
 }
 
 fn flash_bytes_at_offset(conn: &mut PicobootConnection<Context>, data: &[u8], offset: usize) {
@@ -1,18 +1,56 @@
-use miette::Result;
+use miette::{Result, miette};
 
 pub mod config;
 pub mod elf;
 pub mod flash;
 
+const USAGE: &str = "Usage: rukaibox_flash [--upgrade-config] [--config-only] [--show-ruleset]
+
+Options:
+  --config-only     Only flash the config, leaving the firmware on the device untouched.
+                    Fails if the firmware on the device is incompatible with the config.
+  --upgrade-config  When config.kdl is from an older version, write the upgraded config back to config.kdl
+  --show-ruleset    Print the ruleset of each profile on the device without flashing anything.
+                    The firmware refuses to load a config that breaks its rulesets.";
+
 fn main() -> Result<()> {
-    let config = config::load()?;
-    let config_bytes = config::encode_config(&config)?;
+    let mut upgrade_config = false;
+    let mut config_only = false;
+    let mut show_ruleset = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--upgrade-config" => upgrade_config = true,
+            "--config-only" => config_only = true,
+            "--show-ruleset" => show_ruleset = true,
+            arg => return Err(miette!("Unknown argument {arg:?}\n\n{USAGE}")),
+        }
+    }
 
-    let firmware_bytes = elf::elf_to_bin(include_bytes!(env!(
-        "CARGO_BIN_FILE_RUKAIBOX_FIRMWARE_rukaibox_firmware"
-    )))?;
+    if show_ruleset {
+        let config = flash::read_device_config()?;
+        for (i, profile) in config.profiles.iter().enumerate() {
+            println!(
+                "Profile {} ({:?}) ruleset {}",
+                i + 1,
+                profile.logic,
+                profile.ruleset.name()
+            );
+        }
+        return Ok(());
+    }
+
+    let (config, config_source) = config::load(upgrade_config)?;
+    let config_bytes = config::encode_config(&config)?;
+    let config_kdl_bytes = config::encode_config_kdl(&config_source)?;
 
-    flash::flash_device(&firmware_bytes, &config_bytes)?;
+    if config_only {
+        flash::flash_device(None, &config_bytes, &config_kdl_bytes)?;
+    } else {
+        let firmware_bytes = elf::elf_to_bin(include_bytes!(env!(
+            "CARGO_BIN_FILE_RUKAIBOX_FIRMWARE_rukaibox_firmware"
+        )))?;
+        flash::flash_device(Some(&firmware_bytes), &config_bytes, &config_kdl_bytes)?;
+    }
 
     println!("Succesfully flashed!");
     Ok(())
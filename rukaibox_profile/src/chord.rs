@@ -0,0 +1,148 @@
+//! Chords press a logical button while several physical buttons are held together.
+//!
+//! See [`rukaibox_config::Profile::chords`] for how chords interact with the single button bindings.
+
+use crate::input::{ButtonInputLogical, ButtonInputResults, PHYSICAL_BUTTONS};
+use rukaibox_config::{Chord, LogicalButtonToPhysicalButton};
+
+/// Tracks which physical buttons have been consumed by a chord.
+#[derive(Default)]
+pub struct ChordState {
+    /// A bitmask indexed by the `PhysicalButton` discriminant.
+    /// A button is set from the poll a consuming chord completes on until the button is released.
+    consumed: u32,
+}
+
+impl ChordState {
+    /// Maps the physical buttons to logical buttons through both the single button bindings and `chords`.
+    pub fn map(
+        &mut self,
+        chords: &[Chord],
+        map: &LogicalButtonToPhysicalButton,
+        physical: &ButtonInputResults,
+    ) -> ButtonInputLogical {
+        for button in PHYSICAL_BUTTONS {
+            if !physical.get_button_value(button) {
+                self.consumed &= !(1 << button as u32);
+            }
+        }
+        for chord in chords {
+            if chord.consume && chord_held(chord, physical) {
+                for button in &chord.buttons {
+                    self.consumed |= 1 << *button as u32;
+                }
+            }
+        }
+
+        let mut unconsumed = physical.clone();
+        for button in PHYSICAL_BUTTONS {
+            if self.consumed & (1 << button as u32) != 0 {
+                unconsumed.set_button_value(button, false);
+            }
+        }
+        let mut buttons = unconsumed.to_gc(map);
+        for chord in chords {
+            if chord_held(chord, physical) {
+                *buttons.get_mut(chord.logical) = true;
+            }
+        }
+        buttons
+    }
+}
+
+fn chord_held(chord: &Chord, physical: &ButtonInputResults) -> bool {
+    chord
+        .buttons
+        .iter()
+        .all(|button| physical.get_button_value(*button))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChordState;
+    use crate::profile::tests::{buttons, pressed};
+    use rukaibox_config::{Chord, LogicalButton, PhysicalButton};
+
+    /// Z while mod-x and b are held together, using the bindings of [`buttons`].
+    fn mod_x_b_chord(consume: bool) -> Chord {
+        Chord {
+            buttons: [PhysicalButton::LeftThumbLeft, PhysicalButton::RightRing]
+                .into_iter()
+                .collect(),
+            logical: LogicalButton::Z,
+            consume,
+        }
+    }
+
+    /// Maps each set of held physical buttons in order, returning whether mod-x, b and z are pressed.
+    fn map_sequence(chords: &[Chord], polls: &[&[PhysicalButton]]) -> Vec<(bool, bool, bool)> {
+        let map = buttons();
+        let mut state = ChordState::default();
+        polls
+            .iter()
+            .map(|held| {
+                let logical = state.map(chords, &map, &pressed(held));
+                (logical.mod_x, logical.b, logical.z)
+            })
+            .collect()
+    }
+
+    const MOD_X: PhysicalButton = PhysicalButton::LeftThumbLeft;
+    const B: PhysicalButton = PhysicalButton::RightRing;
+
+    #[test]
+    fn forwarded_members() {
+        assert_eq!(
+            map_sequence(&[mod_x_b_chord(false)], &[&[MOD_X], &[MOD_X, B], &[B], &[]]),
+            [
+                (true, false, false),
+                (true, true, true),
+                (false, true, false),
+                (false, false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn consumed_members() {
+        // Mod-x is forwarded until the chord completes, then b stays consumed after mod-x ends the chord
+        assert_eq!(
+            map_sequence(
+                &[mod_x_b_chord(true)],
+                &[&[MOD_X], &[MOD_X, B], &[B], &[], &[B]]
+            ),
+            [
+                (true, false, false),
+                (false, false, true),
+                (false, false, false),
+                (false, false, false),
+                (false, true, false)
+            ]
+        );
+        // Pressed on the same poll, neither member is forwarded
+        assert_eq!(
+            map_sequence(&[mod_x_b_chord(true)], &[&[MOD_X, B], &[MOD_X], &[]]),
+            [
+                (false, false, true),
+                (false, false, false),
+                (false, false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_chords() {
+        // Both chords share b, and each is matched against the held physical buttons
+        let y_chord = Chord {
+            buttons: [PhysicalButton::LeftThumbRight, B].into_iter().collect(),
+            logical: LogicalButton::Y,
+            consume: true,
+        };
+        let map = buttons();
+        let mut state = ChordState::default();
+        let physical = pressed(&[MOD_X, PhysicalButton::LeftThumbRight, B]);
+        let logical = state.map(&[mod_x_b_chord(true), y_chord], &map, &physical);
+        assert!(logical.z && logical.y);
+        assert!(!logical.mod_x && !logical.mod_y && !logical.b);
+    }
+}
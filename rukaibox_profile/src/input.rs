@@ -0,0 +1,386 @@
+use crate::time::Instant;
+use rukaibox_config::{Binding, LogicalButton, LogicalButtonToPhysicalButton, PhysicalButton};
+
+/// Every physical button that can be pressed, i.e. all except [`PhysicalButton::None`].
+pub const PHYSICAL_BUTTONS: [PhysicalButton; 21] = [
+    PhysicalButton::Start,
+    PhysicalButton::LeftPinky,
+    PhysicalButton::LeftRing,
+    PhysicalButton::LeftMiddle,
+    PhysicalButton::LeftIndex,
+    PhysicalButton::LeftMiddle2,
+    PhysicalButton::LeftThumbLeft,
+    PhysicalButton::LeftThumbRight,
+    PhysicalButton::RightIndex,
+    PhysicalButton::RightMiddle,
+    PhysicalButton::RightRing,
+    PhysicalButton::RightPinky,
+    PhysicalButton::RightIndex2,
+    PhysicalButton::RightMiddle2,
+    PhysicalButton::RightRing2,
+    PhysicalButton::RightPinky2,
+    PhysicalButton::RightThumbLeft,
+    PhysicalButton::RightThumbRight,
+    PhysicalButton::RightThumbUp,
+    PhysicalButton::RightThumbDown,
+    PhysicalButton::RightThumbMiddle,
+];
+
+/// The state of each physical button, named the same as the fields of `ButtonInput` in the firmware.
+#[derive(Default, Clone)]
+pub struct ButtonInputResults {
+    pub left_pinky: bool,
+    pub left_ring: bool,
+    pub left_middle: bool,
+    pub left_index: bool,
+
+    pub left_middle_2: bool,
+
+    pub left_thumb_left: bool,
+    pub left_thumb_right: bool,
+
+    pub right_index: bool,
+    pub right_middle: bool,
+    pub right_ring: bool,
+    pub right_pinky: bool,
+
+    pub right_index_2: bool,
+    pub right_middle_2: bool,
+    pub right_ring_2: bool,
+    pub right_pinky_2: bool,
+
+    pub right_thumb_left: bool,
+    pub right_thumb_right: bool,
+    pub right_thumb_up: bool,
+    pub right_thumb_down: bool,
+    pub right_thumb_middle: bool,
+
+    pub start: bool,
+}
+
+impl ButtonInputResults {
+    pub fn to_gc(&self, map: &LogicalButtonToPhysicalButton) -> ButtonInputLogical {
+        ButtonInputLogical {
+            mod_x: self.binding_pressed(&map.mod_x),
+            mod_y: self.binding_pressed(&map.mod_y),
+            start: self.binding_pressed(&map.start),
+            a: self.binding_pressed(&map.a),
+            b: self.binding_pressed(&map.b),
+            x: self.binding_pressed(&map.x),
+            y: self.binding_pressed(&map.y),
+            z: self.binding_pressed(&map.z),
+            dpad_up: self.binding_pressed(&map.dpad_up),
+            dpad_down: self.binding_pressed(&map.dpad_down),
+            dpad_left: self.binding_pressed(&map.dpad_left),
+            dpad_right: self.binding_pressed(&map.dpad_right),
+            l_digital: self.binding_pressed(&map.l_digital),
+            r_digital: self.binding_pressed(&map.r_digital),
+            l_analog: self.binding_pressed(&map.l_analog),
+            r_analog: self.binding_pressed(&map.r_analog),
+            stick_left: self.binding_pressed(&map.stick_left),
+            stick_right: self.binding_pressed(&map.stick_right),
+            stick_up: self.binding_pressed(&map.stick_up),
+            stick_down: self.binding_pressed(&map.stick_down),
+            cstick_left: self.binding_pressed(&map.cstick_left),
+            cstick_right: self.binding_pressed(&map.cstick_right),
+            cstick_up: self.binding_pressed(&map.cstick_up),
+            cstick_down: self.binding_pressed(&map.cstick_down),
+        }
+    }
+
+    pub(crate) fn set_button_value(&mut self, button: PhysicalButton, value: bool) {
+        let field = match button {
+            PhysicalButton::Start => &mut self.start,
+            PhysicalButton::LeftPinky => &mut self.left_pinky,
+            PhysicalButton::LeftRing => &mut self.left_ring,
+            PhysicalButton::LeftMiddle => &mut self.left_middle,
+            PhysicalButton::LeftIndex => &mut self.left_index,
+            PhysicalButton::LeftMiddle2 => &mut self.left_middle_2,
+            PhysicalButton::LeftThumbLeft => &mut self.left_thumb_left,
+            PhysicalButton::LeftThumbRight => &mut self.left_thumb_right,
+
+            PhysicalButton::RightIndex => &mut self.right_index,
+            PhysicalButton::RightMiddle => &mut self.right_middle,
+            PhysicalButton::RightRing => &mut self.right_ring,
+            PhysicalButton::RightPinky => &mut self.right_pinky,
+            PhysicalButton::RightIndex2 => &mut self.right_index_2,
+            PhysicalButton::RightMiddle2 => &mut self.right_middle_2,
+            PhysicalButton::RightRing2 => &mut self.right_ring_2,
+            PhysicalButton::RightPinky2 => &mut self.right_pinky_2,
+
+            PhysicalButton::RightThumbLeft => &mut self.right_thumb_left,
+            PhysicalButton::RightThumbRight => &mut self.right_thumb_right,
+            PhysicalButton::RightThumbUp => &mut self.right_thumb_up,
+            PhysicalButton::RightThumbDown => &mut self.right_thumb_down,
+            PhysicalButton::RightThumbMiddle => &mut self.right_thumb_middle,
+            PhysicalButton::None => return,
+        };
+        *field = value;
+    }
+
+    /// Whether any of the physical buttons in `binding` are pressed.
+    pub fn binding_pressed(&self, binding: &Binding) -> bool {
+        binding
+            .buttons
+            .iter()
+            .any(|button| self.get_button_value(*button))
+    }
+
+    pub fn get_button_value(&self, button: PhysicalButton) -> bool {
+        match button {
+            PhysicalButton::Start => self.start,
+            PhysicalButton::LeftPinky => self.left_pinky,
+            PhysicalButton::LeftRing => self.left_ring,
+            PhysicalButton::LeftMiddle => self.left_middle,
+            PhysicalButton::LeftIndex => self.left_index,
+            PhysicalButton::LeftMiddle2 => self.left_middle_2,
+            PhysicalButton::LeftThumbLeft => self.left_thumb_left,
+            PhysicalButton::LeftThumbRight => self.left_thumb_right,
+
+            PhysicalButton::RightIndex => self.right_index,
+            PhysicalButton::RightMiddle => self.right_middle,
+            PhysicalButton::RightRing => self.right_ring,
+            PhysicalButton::RightPinky => self.right_pinky,
+            PhysicalButton::RightIndex2 => self.right_index_2,
+            PhysicalButton::RightMiddle2 => self.right_middle_2,
+            PhysicalButton::RightRing2 => self.right_ring_2,
+            PhysicalButton::RightPinky2 => self.right_pinky_2,
+
+            PhysicalButton::RightThumbLeft => self.right_thumb_left,
+            PhysicalButton::RightThumbRight => self.right_thumb_right,
+            PhysicalButton::RightThumbUp => self.right_thumb_up,
+            PhysicalButton::RightThumbDown => self.right_thumb_down,
+            PhysicalButton::RightThumbMiddle => self.right_thumb_middle,
+            PhysicalButton::None => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ButtonInputLogical {
+    pub mod_x: bool,
+    pub mod_y: bool,
+
+    pub start: bool,
+    pub a: bool,
+    pub b: bool,
+    pub x: bool,
+    pub y: bool,
+    pub z: bool,
+
+    pub dpad_up: bool,
+    pub dpad_down: bool,
+    pub dpad_left: bool,
+    pub dpad_right: bool,
+
+    pub l_digital: bool,
+    pub r_digital: bool,
+    pub l_analog: bool,
+    pub r_analog: bool,
+
+    pub stick_left: bool,
+    pub stick_right: bool,
+    pub stick_up: bool,
+    pub stick_down: bool,
+
+    pub cstick_left: bool,
+    pub cstick_right: bool,
+    pub cstick_up: bool,
+    pub cstick_down: bool,
+}
+
+impl ButtonInputLogical {
+    pub fn get(&self, button: LogicalButton) -> bool {
+        match button {
+            LogicalButton::LAnalog => self.l_analog,
+            LogicalButton::RAnalog => self.r_analog,
+            LogicalButton::LDigital => self.l_digital,
+            LogicalButton::RDigital => self.r_digital,
+            LogicalButton::StickUp => self.stick_up,
+            LogicalButton::StickDown => self.stick_down,
+            LogicalButton::StickLeft => self.stick_left,
+            LogicalButton::StickRight => self.stick_right,
+            LogicalButton::CstickUp => self.cstick_up,
+            LogicalButton::CstickDown => self.cstick_down,
+            LogicalButton::CstickLeft => self.cstick_left,
+            LogicalButton::CstickRight => self.cstick_right,
+            LogicalButton::DpadUp => self.dpad_up,
+            LogicalButton::DpadDown => self.dpad_down,
+            LogicalButton::DpadLeft => self.dpad_left,
+            LogicalButton::DpadRight => self.dpad_right,
+            LogicalButton::ModX => self.mod_x,
+            LogicalButton::ModY => self.mod_y,
+            LogicalButton::Start => self.start,
+            LogicalButton::A => self.a,
+            LogicalButton::B => self.b,
+            LogicalButton::X => self.x,
+            LogicalButton::Y => self.y,
+            LogicalButton::Z => self.z,
+        }
+    }
+
+    pub fn get_mut(&mut self, button: LogicalButton) -> &mut bool {
+        match button {
+            LogicalButton::LAnalog => &mut self.l_analog,
+            LogicalButton::RAnalog => &mut self.r_analog,
+            LogicalButton::LDigital => &mut self.l_digital,
+            LogicalButton::RDigital => &mut self.r_digital,
+            LogicalButton::StickUp => &mut self.stick_up,
+            LogicalButton::StickDown => &mut self.stick_down,
+            LogicalButton::StickLeft => &mut self.stick_left,
+            LogicalButton::StickRight => &mut self.stick_right,
+            LogicalButton::CstickUp => &mut self.cstick_up,
+            LogicalButton::CstickDown => &mut self.cstick_down,
+            LogicalButton::CstickLeft => &mut self.cstick_left,
+            LogicalButton::CstickRight => &mut self.cstick_right,
+            LogicalButton::DpadUp => &mut self.dpad_up,
+            LogicalButton::DpadDown => &mut self.dpad_down,
+            LogicalButton::DpadLeft => &mut self.dpad_left,
+            LogicalButton::DpadRight => &mut self.dpad_right,
+            LogicalButton::ModX => &mut self.mod_x,
+            LogicalButton::ModY => &mut self.mod_y,
+            LogicalButton::Start => &mut self.start,
+            LogicalButton::A => &mut self.a,
+            LogicalButton::B => &mut self.b,
+            LogicalButton::X => &mut self.x,
+            LogicalButton::Y => &mut self.y,
+            LogicalButton::Z => &mut self.z,
+        }
+    }
+}
+
+/// The button state of the current poll, along with when each button was last pressed and released.
+#[derive(Default, Clone)]
+pub struct TimedInput {
+    pub buttons: ButtonInputResults,
+    /// The time of the current poll
+    pub now: Instant,
+    pressed_at: [Option<Instant>; PHYSICAL_BUTTONS.len()],
+    released_at: [Option<Instant>; PHYSICAL_BUTTONS.len()],
+}
+
+impl TimedInput {
+    /// Record the button state of a new poll taken at `now`.
+    pub fn update(&mut self, buttons: ButtonInputResults, now: Instant) {
+        for (i, button) in PHYSICAL_BUTTONS.into_iter().enumerate() {
+            let was_pressed = self.buttons.get_button_value(button);
+            match (was_pressed, buttons.get_button_value(button)) {
+                (false, true) => self.pressed_at[i] = Some(now),
+                (true, false) => self.released_at[i] = Some(now),
+                _ => {}
+            }
+        }
+        self.buttons = buttons;
+        self.now = now;
+    }
+
+    /// When the button was last pressed, or None if it has not been pressed since boot.
+    pub fn pressed_at(&self, button: PhysicalButton) -> Option<Instant> {
+        self.pressed_at.get(button as usize).copied().flatten()
+    }
+
+    /// When the button was last released, or None if it has not been released since boot.
+    pub fn released_at(&self, button: PhysicalButton) -> Option<Instant> {
+        self.released_at.get(button as usize).copied().flatten()
+    }
+
+    /// When the most recently pressed of the physical buttons bound to `logical` was pressed.
+    /// Chords are not considered.
+    pub fn logical_pressed_at(
+        &self,
+        map: &LogicalButtonToPhysicalButton,
+        logical: LogicalButton,
+    ) -> Option<Instant> {
+        map.get(logical)
+            .buttons
+            .iter()
+            .filter_map(|button| self.pressed_at(*button))
+            .max()
+    }
+
+    /// When the most recently released of the physical buttons bound to `logical` was released.
+    /// Chords are not considered.
+    pub fn logical_released_at(
+        &self,
+        map: &LogicalButtonToPhysicalButton,
+        logical: LogicalButton,
+    ) -> Option<Instant> {
+        map.get(logical)
+            .buttons
+            .iter()
+            .filter_map(|button| self.released_at(*button))
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimedInput;
+    use crate::{
+        profile::tests::pressed,
+        time::{Instant, SimulatedClock},
+    };
+    use rukaibox_config::{Binding, LogicalButton, LogicalButtonToPhysicalButton, PhysicalButton};
+
+    #[test]
+    fn press_and_release_timestamps() {
+        let mut clock = SimulatedClock::default();
+        let mut input = TimedInput::default();
+        input.update(pressed(&[]), clock.now());
+        assert_eq!(input.pressed_at(PhysicalButton::Start), None);
+        assert_eq!(input.released_at(PhysicalButton::Start), None);
+
+        clock.advance_millis(1);
+        input.update(pressed(&[PhysicalButton::Start]), clock.now());
+        clock.advance_millis(1);
+        input.update(pressed(&[PhysicalButton::Start]), clock.now());
+        assert_eq!(input.now, Instant::from_micros(2000));
+        assert_eq!(
+            input.pressed_at(PhysicalButton::Start),
+            Some(Instant::from_micros(1000))
+        );
+        assert_eq!(input.released_at(PhysicalButton::Start), None);
+
+        clock.advance_millis(1);
+        input.update(pressed(&[]), clock.now());
+        assert_eq!(
+            input.pressed_at(PhysicalButton::Start),
+            Some(Instant::from_micros(1000))
+        );
+        assert_eq!(
+            input.released_at(PhysicalButton::Start),
+            Some(Instant::from_micros(3000))
+        );
+        assert_eq!(input.pressed_at(PhysicalButton::None), None);
+    }
+
+    #[test]
+    fn logical_timestamps_use_latest_bound_button() {
+        let map = LogicalButtonToPhysicalButton {
+            stick_up: Binding {
+                buttons: [PhysicalButton::RightPinky, PhysicalButton::LeftMiddle2]
+                    .into_iter()
+                    .collect(),
+            },
+            ..Default::default()
+        };
+        let mut clock = SimulatedClock::default();
+        let mut input = TimedInput::default();
+
+        clock.advance_millis(1);
+        input.update(pressed(&[PhysicalButton::RightPinky]), clock.now());
+        clock.advance_millis(1);
+        input.update(
+            pressed(&[PhysicalButton::RightPinky, PhysicalButton::LeftMiddle2]),
+            clock.now(),
+        );
+        assert_eq!(
+            input.logical_pressed_at(&map, LogicalButton::StickUp),
+            Some(Instant::from_micros(2000))
+        );
+        assert_eq!(
+            input.logical_released_at(&map, LogicalButton::StickUp),
+            None
+        );
+    }
+}
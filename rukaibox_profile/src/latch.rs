@@ -0,0 +1,154 @@
+//! Latches keep a logical button pressed without it being held.
+//!
+//! See [`rukaibox_config::LatchKind`] for how each kind is switched on and off.
+
+use crate::{input::ButtonInputLogical, time::Instant};
+use rukaibox_config::{Latch, LatchKind};
+
+/// Tracks which latches are keeping their button pressed across polls.
+#[derive(Default)]
+pub struct LatchState {
+    /// A bitmask indexed by the latch's position in the profile.
+    latched: u32,
+    /// The logical buttons held on the previous poll, indexed the same as `latched`.
+    held: u32,
+    /// When the press that may start a double tap of each latch began, indexed the same as `latched`.
+    tapped_at: [Option<Instant>; 4],
+}
+
+impl LatchState {
+    /// Applies `latches` to `buttons`, the logical buttons held on the poll taken at `now`.
+    pub fn apply(&mut self, latches: &[Latch], buttons: &mut ButtonInputLogical, now: Instant) {
+        let double_tap = LatchKind::DOUBLE_TAP_MS as u64 * 1000;
+        for (i, latch) in latches.iter().enumerate() {
+            let bit = 1 << i;
+            let button = buttons.get_mut(latch.logical);
+            let pressed = *button && self.held & bit == 0;
+            if *button {
+                self.held |= bit;
+            } else {
+                self.held &= !bit;
+            }
+
+            if pressed {
+                match latch.kind {
+                    LatchKind::Toggle => self.latched ^= bit,
+                    // The press that unlocks the button never starts another double tap.
+                    LatchKind::Lock if self.latched & bit != 0 => {
+                        self.latched &= !bit;
+                        self.tapped_at[i] = None;
+                    }
+                    LatchKind::Lock => match self.tapped_at[i] {
+                        Some(tapped) if now.micros_since(tapped) < double_tap => {
+                            self.latched |= bit;
+                            self.tapped_at[i] = None;
+                        }
+                        _ => self.tapped_at[i] = Some(now),
+                    },
+                }
+            }
+
+            let latched = self.latched & bit != 0;
+            *button = match latch.kind {
+                LatchKind::Toggle => latched,
+                LatchKind::Lock => *button || latched,
+            };
+        }
+    }
+
+    /// Returns true while any latch is keeping its button pressed.
+    pub fn any_latched(&self) -> bool {
+        self.latched != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LatchState;
+    use crate::profile::tests::{buttons, timed_sequence};
+    use rukaibox_config::{Latch, LatchKind, LogicalButton, PhysicalButton};
+
+    /// Bound to mod-y by [`buttons`].
+    const MOD_Y: PhysicalButton = PhysicalButton::LeftThumbRight;
+
+    /// Applies the latch to mod-y for each `(held, millis since the previous poll)` in order,
+    /// returning whether mod-y is pressed on each poll.
+    fn latch_sequence(kind: LatchKind, polls: &[(bool, u64)]) -> Vec<bool> {
+        let latches = [Latch {
+            logical: LogicalButton::ModY,
+            kind,
+        }];
+        let polls: Vec<(&[PhysicalButton], u64)> = polls
+            .iter()
+            .map(|(held, millis)| (if *held { &[MOD_Y][..] } else { &[] }, *millis))
+            .collect();
+        let map = buttons();
+        let mut state = LatchState::default();
+        timed_sequence(&polls, |timed| {
+            let mut buttons = timed.buttons.to_gc(&map);
+            state.apply(&latches, &mut buttons, timed.now);
+            buttons.mod_y
+        })
+    }
+
+    #[test]
+    fn toggle() {
+        // Holding the button does nothing further, the next press switches it off while held
+        assert_eq!(
+            latch_sequence(
+                LatchKind::Toggle,
+                &[(true, 0), (true, 10), (false, 10), (true, 10), (false, 10)]
+            ),
+            [true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn lock() {
+        // Pressed while held until the second tap locks it, then the next press unlocks it
+        assert_eq!(
+            latch_sequence(
+                LatchKind::Lock,
+                &[
+                    (true, 0),
+                    (false, 50),
+                    (true, 50),
+                    (false, 50),
+                    (false, 1000),
+                    (true, 10),
+                    (false, 10)
+                ]
+            ),
+            [true, false, true, true, true, true, false]
+        );
+    }
+
+    #[test]
+    fn slow_double_tap() {
+        let window = LatchKind::DOUBLE_TAP_MS as u64;
+        assert_eq!(
+            latch_sequence(
+                LatchKind::Lock,
+                &[(true, 0), (false, 10), (true, window - 10), (false, 10)]
+            ),
+            [true, false, true, false]
+        );
+        // The unlocking press does not start another double tap
+        assert_eq!(
+            latch_sequence(
+                LatchKind::Lock,
+                &[
+                    (true, 0),
+                    (false, 10),
+                    (true, 10),
+                    (false, 10),
+                    (true, 10),
+                    (false, 10),
+                    (true, 10),
+                    (false, 10)
+                ]
+            ),
+            [true, false, true, true, true, false, true, false]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Layers override some of a profile's button bindings while they are active.
+//!
+//! See [`rukaibox_config::Profile::layers`] for how layers stack.
+
+use crate::input::ButtonInputResults;
+use rukaibox_config::{Layer, LayerActivation, LogicalButtonToPhysicalButton};
+
+/// Tracks which layers are active across polls.
+#[derive(Default)]
+pub struct LayerState {
+    /// A bitmask indexed by the layer's position in the profile.
+    active: u32,
+    /// Layers switched on by a [`LayerActivation::Toggle`] button.
+    toggled: u32,
+    /// The activation buttons held on the previous poll, indexed the same as `active`.
+    held: u32,
+}
+
+impl LayerState {
+    /// Updates the active layers for a new poll, returning true if they changed.
+    pub fn update(&mut self, layers: &[Layer], physical: &ButtonInputResults) -> bool {
+        let previous = self.active;
+        for (i, layer) in layers.iter().enumerate() {
+            let bit = 1 << i;
+            let held = physical.get_button_value(layer.activation.button());
+            let pressed = held && self.held & bit == 0;
+            set_bit(&mut self.held, bit, held);
+            let active = match layer.activation {
+                LayerActivation::Hold(_) => held,
+                LayerActivation::Toggle(_) => {
+                    if pressed {
+                        self.toggled ^= bit;
+                    }
+                    self.toggled & bit != 0
+                }
+            };
+            set_bit(&mut self.active, bit, active);
+        }
+        self.active != previous
+    }
+
+    /// Returns `base` with the bindings of every active layer applied.
+    pub fn mapping(
+        &self,
+        layers: &[Layer],
+        base: &LogicalButtonToPhysicalButton,
+    ) -> LogicalButtonToPhysicalButton {
+        let mut mapping = base.clone();
+        // Apply the lowest priority layer first, so that higher priority layers take over its buttons.
+        for (i, layer) in layers.iter().enumerate().rev() {
+            if self.active & (1 << i) == 0 {
+                continue;
+            }
+            for layer_binding in &layer.bindings {
+                for button in &layer_binding.binding.buttons {
+                    mapping.unbind(*button);
+                }
+                *mapping.get_mut(layer_binding.logical) = layer_binding.binding.clone();
+            }
+        }
+        mapping
+    }
+
+    /// Returns `physical` with every layer activation button released, since they only control layers.
+    pub fn without_activation(
+        &self,
+        layers: &[Layer],
+        physical: &ButtonInputResults,
+    ) -> ButtonInputResults {
+        let mut physical = physical.clone();
+        for layer in layers {
+            physical.set_button_value(layer.activation.button(), false);
+        }
+        physical
+    }
+}
+
+fn set_bit(bits: &mut u32, bit: u32, value: bool) {
+    if value {
+        *bits |= bit;
+    } else {
+        *bits &= !bit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayerState;
+    use crate::profile::tests::{buttons, pressed};
+    use rukaibox_config::{
+        Binding, Layer, LayerActivation, LayerBinding, LogicalButton, PhysicalButton,
+    };
+
+    fn layer(activation: LayerActivation, bindings: &[(LogicalButton, PhysicalButton)]) -> Layer {
+        Layer {
+            activation,
+            bindings: bindings
+                .iter()
+                .map(|(logical, physical)| LayerBinding {
+                    logical: *logical,
+                    binding: Binding::new(*physical),
+                })
+                .collect(),
+        }
+    }
+
+    const KEY: PhysicalButton = PhysicalButton::RightPinky2;
+    const CSTICK_UP: PhysicalButton = PhysicalButton::RightThumbUp;
+
+    /// Dpad-up on the C-stick up button.
+    fn menu(activation: LayerActivation) -> Layer {
+        layer(activation, &[(LogicalButton::DpadUp, CSTICK_UP)])
+    }
+
+    /// Updates with each set of held buttons in order, returning whether C-stick up activates dpad-up.
+    fn dpad_sequence(layers: &[Layer], polls: &[&[PhysicalButton]]) -> Vec<bool> {
+        let base = buttons();
+        let mut state = LayerState::default();
+        let mut mapping = base.clone();
+        polls
+            .iter()
+            .map(|held| {
+                let physical = pressed(held);
+                if state.update(layers, &physical) {
+                    mapping = state.mapping(layers, &base);
+                }
+                let physical = state.without_activation(layers, &physical);
+                let logical = physical.to_gc(&mapping);
+                // The layer's button is taken from C-stick up, and the activation button does nothing itself.
+                assert!(!(logical.dpad_up && logical.cstick_up));
+                logical.dpad_up
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hold() {
+        let layers = [menu(LayerActivation::Hold(KEY))];
+        assert_eq!(
+            dpad_sequence(
+                &layers,
+                &[&[CSTICK_UP], &[KEY, CSTICK_UP], &[KEY], &[CSTICK_UP]]
+            ),
+            [false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn toggle() {
+        let layers = [menu(LayerActivation::Toggle(KEY))];
+        // Switched on by the first press and off by the second, holding the button does nothing further
+        assert_eq!(
+            dpad_sequence(
+                &layers,
+                &[
+                    &[KEY],
+                    &[KEY, CSTICK_UP],
+                    &[CSTICK_UP],
+                    &[KEY, CSTICK_UP],
+                    &[CSTICK_UP],
+                    &[]
+                ]
+            ),
+            [false, true, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn priority() {
+        // Both layers bind C-stick up, the first listed layer wins while both are active
+        let layers = [
+            menu(LayerActivation::Hold(KEY)),
+            layer(
+                LayerActivation::Hold(PhysicalButton::LeftMiddle2),
+                &[(LogicalButton::X, CSTICK_UP)],
+            ),
+        ];
+        let base = buttons();
+        let mut state = LayerState::default();
+        let physical = pressed(&[KEY, PhysicalButton::LeftMiddle2, CSTICK_UP]);
+        assert!(state.update(&layers, &physical));
+        let logical = state
+            .without_activation(&layers, &physical)
+            .to_gc(&state.mapping(&layers, &base));
+        assert!(logical.dpad_up);
+        assert!(!logical.x && !logical.cstick_up);
+
+        // The second layer applies alone once the first is released
+        let physical = pressed(&[PhysicalButton::LeftMiddle2, CSTICK_UP]);
+        assert!(state.update(&layers, &physical));
+        let logical = state
+            .without_activation(&layers, &physical)
+            .to_gc(&state.mapping(&layers, &base));
+        assert!(logical.x);
+        assert!(!logical.dpad_up && !logical.cstick_up);
+
+        // Nothing changed, so the mapping does not need to be rebuilt
+        assert!(!state.update(&layers, &physical));
+    }
+}
@@ -0,0 +1,63 @@
+//! The logic for mapping button presses to controller outputs.
+//!
+//! Kept separate from the firmware so that it can be built and tested on the host.
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod chord;
+pub mod input;
+pub mod latch;
+pub mod layer;
+pub mod profile;
+pub mod socd;
+pub mod switch;
+pub mod tap_hold;
+pub mod time;
+pub mod travel;
+
+/// The state of a gamecube controller, converted to `joybus_pio::GamecubeInput` by the firmware.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GamecubeInput {
+    pub start: bool,
+    pub a: bool,
+    pub b: bool,
+    pub x: bool,
+    pub y: bool,
+    pub z: bool,
+    pub dpad_up: bool,
+    pub dpad_down: bool,
+    pub dpad_left: bool,
+    pub dpad_right: bool,
+    pub l_digital: bool,
+    pub r_digital: bool,
+    pub stick_x: u8,
+    pub stick_y: u8,
+    pub cstick_x: u8,
+    pub cstick_y: u8,
+    pub l_analog: u8,
+    pub r_analog: u8,
+}
+
+impl GamecubeInput {
+    /// No buttons pressed and both sticks centered.
+    pub const NEUTRAL: GamecubeInput = GamecubeInput {
+        start: false,
+        a: false,
+        b: false,
+        x: false,
+        y: false,
+        z: false,
+        dpad_up: false,
+        dpad_down: false,
+        dpad_left: false,
+        dpad_right: false,
+        l_digital: false,
+        r_digital: false,
+        stick_x: 128,
+        stick_y: 128,
+        cstick_x: 128,
+        cstick_y: 128,
+        l_analog: 0,
+        r_analog: 0,
+    };
+}
@@ -0,0 +1,1254 @@
+mod melee;
+mod project_plus;
+mod rivals1;
+mod rivals2;
+mod ultimate;
+
+use crate::{
+    GamecubeInput,
+    chord::ChordState,
+    input::{ButtonInputLogical, TimedInput},
+    latch::LatchState,
+    layer::LayerState,
+    socd::SocdState,
+    switch::SwitchState,
+    tap_hold::TapHoldState,
+    time::Instant,
+    travel::StickTravel,
+};
+use arrayvec::ArrayVec;
+use melee::MeleeLogic;
+use project_plus::ProjectPlusLogic;
+use rivals1::Rivals1Logic;
+use rivals2::Rivals2Logic;
+use rukaibox_config::{
+    BaseLogic, Chord, Config, Coordinate, Latch, Layer, LogicalButton,
+    LogicalButtonToPhysicalButton, Overlay, OverlayCondition, Profile, SocdTypes, TapHold,
+    TriggerLevels,
+};
+use ultimate::UltimateLogic;
+
+/// The game specific parts of a profile.
+///
+/// Everything shared between games is handled by [`MapProfile`]:
+/// SOCD resolution, direction extraction, analog triggers and the mod-x + mod-y dpad layer.
+/// A game only has to pick the stick coordinates for the resolved input.
+pub trait ProfileLogic {
+    /// Suppress start while mod-x or mod-y is held.
+    const MODIFIERS_SUPPRESS_START: bool = false;
+
+    fn new(profile: &Profile) -> Self;
+
+    /// Returns the main stick offset from center.
+    fn stick(&mut self, input: &ProfileInput) -> (i16, i16);
+
+    /// Returns the C-stick offset from center.
+    fn cstick(&mut self, input: &ProfileInput) -> (i16, i16);
+
+    /// Called with the input before SOCD is resolved.
+    /// Returning true resolves horizontal SOCD in favour of the most recently pressed direction, regardless of the profile's SOCD type.
+    fn horizontal_socd_override(&mut self, _input: &ProfileInput) -> bool {
+        false
+    }
+}
+
+/// The input given to [`ProfileLogic`], after SOCD has been resolved unless stated otherwise.
+pub struct ProfileInput<'a> {
+    pub buttons: ButtonInputLogical,
+    pub stick: Direction,
+    pub cstick: Direction,
+    timed: &'a TimedInput,
+    button_mapping: &'a LogicalButtonToPhysicalButton,
+    tap_hold_state: &'a TapHoldState,
+}
+
+impl<'a> ProfileInput<'a> {
+    fn new(
+        buttons: ButtonInputLogical,
+        timed: &'a TimedInput,
+        button_mapping: &'a LogicalButtonToPhysicalButton,
+        tap_hold_state: &'a TapHoldState,
+    ) -> Self {
+        ProfileInput {
+            stick: Direction::new(
+                buttons.stick_left,
+                buttons.stick_right,
+                buttons.stick_down,
+                buttons.stick_up,
+            ),
+            cstick: Direction::new(
+                buttons.cstick_left,
+                buttons.cstick_right,
+                buttons.cstick_down,
+                buttons.cstick_up,
+            ),
+            buttons,
+            timed,
+            button_mapping,
+            tap_hold_state,
+        }
+    }
+
+    /// The time of the current poll.
+    pub fn now(&self) -> Instant {
+        self.timed.now
+    }
+
+    /// When the logical button was last pressed, before SOCD resolution.
+    /// A tap-hold counts as pressed when its press is decided.
+    pub fn pressed_at(&self, logical: LogicalButton) -> Option<Instant> {
+        let tap_hold = self.tap_hold_state.pressed_at(logical);
+        self.timed
+            .logical_pressed_at(self.button_mapping, logical)
+            .max(tap_hold)
+    }
+
+    /// When the logical button was last released, before SOCD resolution.
+    pub fn released_at(&self, logical: LogicalButton) -> Option<Instant> {
+        let tap_hold = self.tap_hold_state.released_at(logical);
+        self.timed
+            .logical_released_at(self.button_mapping, logical)
+            .max(tap_hold)
+    }
+}
+
+/// The direction a stick is held in, each axis is -1, 0 or 1.
+#[derive(Clone, Copy)]
+pub struct Direction {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Direction {
+    fn new(left: bool, right: bool, down: bool, up: bool) -> Self {
+        Direction {
+            x: axis_direction(left, right),
+            y: axis_direction(down, up),
+        }
+    }
+
+    pub fn horizontal(self) -> bool {
+        self.x != 0
+    }
+
+    pub fn vertical(self) -> bool {
+        self.y != 0
+    }
+
+    pub fn diagonal(self) -> bool {
+        self.horizontal() && self.vertical()
+    }
+
+    /// Returns the offset from center of `coordinate` held in this direction.
+    pub fn offset(self, coordinate: Coordinate) -> (i16, i16) {
+        (self.x * coordinate.x as i16, self.y * coordinate.y as i16)
+    }
+}
+
+fn axis_direction(negative: bool, positive: bool) -> i16 {
+    match (negative, positive) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => 0,
+    }
+}
+
+pub struct MapProfile {
+    logic: GameLogic,
+    pipeline: Pipeline,
+    switch_state: SwitchState,
+}
+
+enum GameLogic {
+    ProjectPlus(ProjectPlusLogic),
+    Rivals1(Rivals1Logic),
+    Rivals2(Rivals2Logic),
+    Ultimate(UltimateLogic),
+    Melee(MeleeLogic),
+}
+
+/// The parts of a profile shared between all games.
+struct Pipeline {
+    socd_state: SocdState,
+    socd_types: SocdTypes,
+    /// The profile's bindings with the active layers applied.
+    button_mapping: LogicalButtonToPhysicalButton,
+    base_mapping: LogicalButtonToPhysicalButton,
+    layer_state: LayerState,
+    layers: ArrayVec<Layer, 4>,
+    chord_state: ChordState,
+    chords: ArrayVec<Chord, 8>,
+    tap_hold_state: TapHoldState,
+    tap_holds: ArrayVec<TapHold, 4>,
+    latch_state: LatchState,
+    latches: ArrayVec<Latch, 4>,
+    overlays: ArrayVec<Overlay, 8>,
+    triggers: TriggerLevels,
+    crouch_walk_prevention: Option<Coordinate>,
+    stick_travel: StickTravel,
+    cstick_travel: StickTravel,
+}
+
+impl MapProfile {
+    pub fn new(config: &Profile) -> Self {
+        let logic = match config.logic {
+            BaseLogic::ProjectPlus => GameLogic::ProjectPlus(ProjectPlusLogic::new(config)),
+            BaseLogic::Rivals1 => GameLogic::Rivals1(Rivals1Logic::new(config)),
+            BaseLogic::Rivals2 => GameLogic::Rivals2(Rivals2Logic::new(config)),
+            BaseLogic::Ultimate => GameLogic::Ultimate(UltimateLogic::new(config)),
+            BaseLogic::Melee => GameLogic::Melee(MeleeLogic::new(config)),
+        };
+        MapProfile {
+            logic,
+            pipeline: Pipeline {
+                socd_state: Default::default(),
+                socd_types: config.socd.clone(),
+                button_mapping: config.buttons.clone(),
+                base_mapping: config.buttons.clone(),
+                layer_state: Default::default(),
+                layers: config.layers.clone(),
+                chord_state: Default::default(),
+                chords: config.chords.clone(),
+                tap_hold_state: Default::default(),
+                tap_holds: config.tap_holds.clone(),
+                latch_state: Default::default(),
+                latches: config.latches.clone(),
+                overlays: config.overlays.clone(),
+                triggers: config.triggers.clone(),
+                crouch_walk_prevention: config.crouch_walk_prevention,
+                stick_travel: StickTravel::new(config.travel_time.stick_micros),
+                cstick_travel: StickTravel::new(config.travel_time.cstick_micros),
+            },
+            switch_state: Default::default(),
+        }
+    }
+
+    pub fn map_to_gamecube(&mut self, input: &TimedInput) -> GamecubeInput {
+        let mut suppressed;
+        let input = if self.switch_state.suppressing() {
+            suppressed = input.clone();
+            self.switch_state.suppress(&mut suppressed.buttons);
+            &suppressed
+        } else {
+            input
+        };
+        match &mut self.logic {
+            GameLogic::ProjectPlus(logic) => self.pipeline.map(logic, input),
+            GameLogic::Rivals1(logic) => self.pipeline.map(logic, input),
+            GameLogic::Rivals2(logic) => self.pipeline.map(logic, input),
+            GameLogic::Ultimate(logic) => self.pipeline.map(logic, input),
+            GameLogic::Melee(logic) => self.pipeline.map(logic, input),
+        }
+    }
+
+    /// Returns true while a latch is keeping its button pressed, so the firmware can show it on the LED.
+    pub fn latched(&self) -> bool {
+        self.pipeline.latch_state.any_latched()
+    }
+
+    /// Switches to a profile when its activation combination is completed, see [`SwitchState::update`].
+    /// Returns the index of the newly activated profile, if any.
+    ///
+    /// The buttons of a completed combination are left out of [`MapProfile::map_to_gamecube`] until they are released.
+    pub fn change_profile(&mut self, input: &TimedInput, config: &Config) -> Option<usize> {
+        let index = self.switch_state.update(&config.profiles, input)?;
+        // Carried over so the combination stays suppressed and does not switch again while it is held.
+        let switch_state = core::mem::take(&mut self.switch_state);
+        *self = Self::new(&config.profiles[index]);
+        self.switch_state = switch_state;
+        Some(index)
+    }
+}
+
+impl Pipeline {
+    fn map<L: ProfileLogic>(&mut self, logic: &mut L, timed: &TimedInput) -> GamecubeInput {
+        // The mapping only needs rebuilding when a layer is switched on or off.
+        if self.layer_state.update(&self.layers, &timed.buttons) {
+            self.button_mapping = self.layer_state.mapping(&self.layers, &self.base_mapping);
+        }
+        self.tap_hold_state.update(&self.tap_holds, timed);
+        let mut physical = self
+            .layer_state
+            .without_activation(&self.layers, &timed.buttons);
+        for tap_hold in &self.tap_holds {
+            physical.set_button_value(tap_hold.button, false);
+        }
+        let mut buttons = self
+            .chord_state
+            .map(&self.chords, &self.button_mapping, &physical);
+        // Tap-holds are pressed before SOCD resolution, so they resolve the same as any other button pressed on the poll they are decided on.
+        self.tap_hold_state.press(&mut buttons);
+        self.latch_state
+            .apply(&self.latches, &mut buttons, timed.now);
+        let unresolved =
+            ProfileInput::new(buttons, timed, &self.button_mapping, &self.tap_hold_state);
+        // Whichever direction was pressed last wins, regardless of the profile's SOCD type.
+        let horizontal_override = logic.horizontal_socd_override(&unresolved).then(|| {
+            unresolved.pressed_at(LogicalButton::StickLeft)
+                > unresolved.pressed_at(LogicalButton::StickRight)
+        });
+
+        let buttons = self.resolve_socd(buttons, horizontal_override);
+        let input = ProfileInput::new(buttons, timed, &self.button_mapping, &self.tap_hold_state);
+        let mut output = self.map_resolved(logic, &input);
+
+        let stick = (output.stick_x, output.stick_y);
+        (output.stick_x, output.stick_y) = self.stick_travel.update(stick, timed.now);
+        let cstick = (output.cstick_x, output.cstick_y);
+        (output.cstick_x, output.cstick_y) = self.cstick_travel.update(cstick, timed.now);
+        output
+    }
+
+    /// `horizontal_override` is Some(true) when an overriding left should win over right, and Some(false) for the reverse.
+    fn resolve_socd(
+        &mut self,
+        mut buttons: ButtonInputLogical,
+        horizontal_override: Option<bool>,
+    ) -> ButtonInputLogical {
+        let socd = &mut self.socd_state;
+        let types = &self.socd_types;
+
+        // Always resolve so the axis state keeps tracking the held directions while the override applies.
+        let both = buttons.stick_left && buttons.stick_right;
+        (buttons.stick_left, buttons.stick_right) = socd.stick_horizontal.resolve(
+            types.stick_horizontal,
+            buttons.stick_left,
+            buttons.stick_right,
+        );
+        if let Some(left) = horizontal_override
+            && both
+        {
+            buttons.stick_left = left;
+            buttons.stick_right = !left;
+        }
+        (buttons.stick_up, buttons.stick_down) =
+            socd.stick_vertical
+                .resolve(types.stick_vertical, buttons.stick_up, buttons.stick_down);
+        (buttons.cstick_left, buttons.cstick_right) = socd.cstick_horizontal.resolve(
+            types.cstick_horizontal,
+            buttons.cstick_left,
+            buttons.cstick_right,
+        );
+        (buttons.cstick_up, buttons.cstick_down) = socd.cstick_vertical.resolve(
+            types.cstick_vertical,
+            buttons.cstick_up,
+            buttons.cstick_down,
+        );
+        (buttons.dpad_left, buttons.dpad_right) =
+            socd.dpad_horizontal
+                .resolve(types.dpad, buttons.dpad_left, buttons.dpad_right);
+        (buttons.dpad_up, buttons.dpad_down) =
+            socd.dpad_vertical
+                .resolve(types.dpad, buttons.dpad_up, buttons.dpad_down);
+
+        buttons
+    }
+
+    /// Run the stages of the pipeline that come after SOCD resolution.
+    fn map_resolved<L: ProfileLogic>(&self, logic: &mut L, input: &ProfileInput) -> GamecubeInput {
+        let ButtonInputLogical {
+            mod_x,
+            mod_y,
+            start,
+            a,
+            b,
+            x,
+            y,
+            z,
+            dpad_up,
+            dpad_down,
+            dpad_left,
+            dpad_right,
+            l_digital,
+            r_digital,
+            l_analog,
+            r_analog,
+            cstick_left,
+            cstick_right,
+            cstick_up,
+            cstick_down,
+            ..
+        } = input.buttons;
+
+        // Derive stick values
+
+        // The logic is still run when an overlay applies, so that any state it tracks stays up to date.
+        let overlay = self
+            .overlays
+            .iter()
+            .find(|overlay| overlay_applies(overlay, input));
+
+        let (stick_x_offset, stick_y_offset) = logic.stick(input);
+        let (stick_x_offset, stick_y_offset) = match self.crouch_walk_prevention {
+            Some(coordinate) if entered_diagonal_from_crouch(input) => {
+                input.stick.offset(coordinate)
+            }
+            _ => (stick_x_offset, stick_y_offset),
+        };
+        let (stick_x_offset, stick_y_offset) = match overlay.and_then(|overlay| overlay.stick) {
+            Some(coordinate) => input.stick.offset(coordinate),
+            None => (stick_x_offset, stick_y_offset),
+        };
+        let stick_x = (128 + stick_x_offset) as u8;
+        let stick_y = (128 + stick_y_offset) as u8;
+
+        let (cstick_x_offset, cstick_y_offset) = logic.cstick(input);
+        let (cstick_x_offset, cstick_y_offset) = match overlay.and_then(|overlay| overlay.cstick) {
+            Some(coordinate) => input.cstick.offset(coordinate),
+            None => (cstick_x_offset, cstick_y_offset),
+        };
+        let cstick_x = (128 + cstick_x_offset) as u8;
+        let cstick_y = (128 + cstick_y_offset) as u8;
+
+        // Derive analog trigger values
+
+        let analog_level = if mod_x {
+            self.triggers.analog_mod_x
+        } else if mod_y {
+            self.triggers.analog_mod_y
+        } else {
+            self.triggers.analog
+        };
+        let trigger = |digital: bool, analog: bool| {
+            let analog = if analog { analog_level } else { 0 };
+            let digital = if digital { self.triggers.digital } else { 0 };
+            analog.max(digital)
+        };
+        let l_analog = trigger(l_digital, l_analog);
+        let r_analog = trigger(r_digital, r_analog);
+
+        // Derive dpad values
+
+        let dpad_up = (mod_x && mod_y && cstick_up) || dpad_up;
+        let dpad_down = (mod_x && mod_y && cstick_down) || dpad_down;
+        let dpad_left = (mod_x && mod_y && cstick_left) || dpad_left;
+        let dpad_right = (mod_x && mod_y && cstick_right) || dpad_right;
+
+        // disable cstick when dpad in use
+        let cstick_x = if dpad_left || dpad_right {
+            128
+        } else {
+            cstick_x
+        };
+        let cstick_y = if dpad_up || dpad_down { 128 } else { cstick_y };
+
+        let start = start && !(L::MODIFIERS_SUPPRESS_START && (mod_x || mod_y));
+
+        GamecubeInput {
+            start,
+            a,
+            b,
+            x,
+            y,
+            z,
+            dpad_up,
+            dpad_down,
+            dpad_left,
+            dpad_right,
+            l_digital,
+            r_digital,
+            stick_x,
+            stick_y,
+            cstick_x,
+            cstick_y,
+            l_analog,
+            r_analog,
+        }
+    }
+}
+
+/// Holding down and then pressing a horizontal direction would otherwise jump straight to a diagonal,
+/// which can start a crouch walk or dash where a real stick would stay crouched.
+/// Modifiers pick their own coordinates, so are left alone.
+fn entered_diagonal_from_crouch(input: &ProfileInput) -> bool {
+    let buttons = &input.buttons;
+    if !buttons.stick_down || !input.stick.horizontal() || buttons.mod_x || buttons.mod_y {
+        return false;
+    }
+    let horizontal = if input.stick.x > 0 {
+        LogicalButton::StickRight
+    } else {
+        LogicalButton::StickLeft
+    };
+    match (
+        input.pressed_at(LogicalButton::StickDown),
+        input.pressed_at(horizontal),
+    ) {
+        (Some(down), Some(horizontal)) => down < horizontal,
+        _ => false,
+    }
+}
+
+fn overlay_applies(overlay: &Overlay, input: &ProfileInput) -> bool {
+    let stick = input.stick;
+    overlay.conditions.iter().all(|condition| match condition {
+        OverlayCondition::Held(button) => input.buttons.get(*button),
+        OverlayCondition::Horizontal => stick.horizontal() && !stick.vertical(),
+        OverlayCondition::Vertical => stick.vertical() && !stick.horizontal(),
+        OverlayCondition::Diagonal => stick.diagonal(),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::MapProfile;
+    use crate::{
+        GamecubeInput,
+        input::{ButtonInputResults, TimedInput},
+        time::SimulatedClock,
+    };
+    use rukaibox_config::{
+        BaseLogic, Binding, Chord, Config, Coordinate, Coordinates, Latch, LatchKind, Layer,
+        LayerActivation, LogicalButton, LogicalButtonToPhysicalButton, Overlay, OverlayCondition,
+        PhysicalButton, Profile, Ruleset, RulesetViolation, SocdType, SocdTypes, TapHold,
+        TravelTime, TriggerLevels,
+    };
+
+    /// The held buttons and the expected stick value for each of [`DIRECTIONS`].
+    pub type Case = (&'static [LogicalButton], [(u8, u8); 8]);
+
+    /// Stick directions in the order used by the expected value tables.
+    pub const DIRECTIONS: [&[LogicalButton]; 8] = [
+        &[LogicalButton::StickRight],
+        &[LogicalButton::StickUp, LogicalButton::StickRight],
+        &[LogicalButton::StickUp],
+        &[LogicalButton::StickUp, LogicalButton::StickLeft],
+        &[LogicalButton::StickLeft],
+        &[LogicalButton::StickDown, LogicalButton::StickLeft],
+        &[LogicalButton::StickDown],
+        &[LogicalButton::StickDown, LogicalButton::StickRight],
+    ];
+
+    /// The same C-stick directions as [`DIRECTIONS`].
+    pub const CSTICK_DIRECTIONS: [&[LogicalButton]; 8] = [
+        &[LogicalButton::CstickRight],
+        &[LogicalButton::CstickUp, LogicalButton::CstickRight],
+        &[LogicalButton::CstickUp],
+        &[LogicalButton::CstickUp, LogicalButton::CstickLeft],
+        &[LogicalButton::CstickLeft],
+        &[LogicalButton::CstickDown, LogicalButton::CstickLeft],
+        &[LogicalButton::CstickDown],
+        &[LogicalButton::CstickDown, LogicalButton::CstickRight],
+    ];
+
+    /// The default Project+ bindings, except every logical button is bound to a unique physical button.
+    pub fn buttons() -> LogicalButtonToPhysicalButton {
+        LogicalButtonToPhysicalButton {
+            mod_x: Binding::new(PhysicalButton::LeftThumbLeft),
+            mod_y: Binding::new(PhysicalButton::LeftThumbRight),
+            start: Binding::new(PhysicalButton::Start),
+            a: Binding::new(PhysicalButton::RightThumbMiddle),
+            b: Binding::new(PhysicalButton::RightRing),
+            x: Binding::new(PhysicalButton::RightMiddle),
+            y: Binding::new(PhysicalButton::RightMiddle2),
+            z: Binding::new(PhysicalButton::RightIndex2),
+            dpad_up: Binding::new(PhysicalButton::RightPinky2),
+            dpad_down: Binding::default(),
+            dpad_left: Binding::default(),
+            dpad_right: Binding::default(),
+            l_digital: Binding::new(PhysicalButton::LeftPinky),
+            r_digital: Binding::new(PhysicalButton::RightIndex),
+            l_analog: Binding::new(PhysicalButton::LeftMiddle2),
+            r_analog: Binding::new(PhysicalButton::RightRing2),
+            stick_left: Binding::new(PhysicalButton::LeftRing),
+            stick_right: Binding::new(PhysicalButton::LeftIndex),
+            stick_up: Binding::new(PhysicalButton::RightPinky),
+            stick_down: Binding::new(PhysicalButton::LeftMiddle),
+            cstick_left: Binding::new(PhysicalButton::RightThumbLeft),
+            cstick_right: Binding::new(PhysicalButton::RightThumbRight),
+            cstick_up: Binding::new(PhysicalButton::RightThumbUp),
+            cstick_down: Binding::new(PhysicalButton::RightThumbDown),
+        }
+    }
+
+    pub fn new_profile(logic: BaseLogic) -> MapProfile {
+        MapProfile::new(&Profile {
+            logic,
+            buttons: buttons(),
+            coordinates: Coordinates::new(logic),
+            triggers: TriggerLevels::new(logic),
+            ..Default::default()
+        })
+    }
+
+    /// The physical buttons bound to `logical` by [`buttons`] pressed, and every other button released.
+    pub fn press(logical: &[LogicalButton]) -> ButtonInputResults {
+        let buttons = buttons();
+        let held: Vec<PhysicalButton> = logical
+            .iter()
+            .flat_map(|logical| buttons.get(*logical).buttons.clone())
+            .collect();
+        pressed(&held)
+    }
+
+    /// The physical buttons in `held` pressed, and every other button released.
+    pub fn pressed(held: &[PhysicalButton]) -> ButtonInputResults {
+        let mut input = ButtonInputResults::default();
+        for button in held {
+            input.set_button_value(*button, true);
+        }
+        input
+    }
+
+    /// Updates a [`TimedInput`] with each `(held physical buttons, millis since the previous poll)` in order,
+    /// returning the result of `poll` for each update.
+    pub fn timed_sequence<T>(
+        polls: &[(&[PhysicalButton], u64)],
+        mut poll: impl FnMut(&TimedInput) -> T,
+    ) -> Vec<T> {
+        let mut clock = SimulatedClock::default();
+        let mut input = TimedInput::default();
+        polls
+            .iter()
+            .map(|(held, millis)| {
+                clock.advance_millis(*millis);
+                input.update(pressed(held), clock.now());
+                poll(&input)
+            })
+            .collect()
+    }
+
+    /// Drives a profile through a sequence of polls, with time controlled by a [`SimulatedClock`].
+    pub struct Simulation {
+        pub profile: MapProfile,
+        pub input: TimedInput,
+        pub clock: SimulatedClock,
+    }
+
+    impl Simulation {
+        pub fn new(logic: BaseLogic) -> Self {
+            Self::from_profile(new_profile(logic))
+        }
+
+        pub fn from_profile(profile: MapProfile) -> Self {
+            Simulation {
+                profile,
+                input: TimedInput::default(),
+                clock: SimulatedClock::default(),
+            }
+        }
+
+        /// Map a poll at the current time with only `held` pressed.
+        pub fn poll(&mut self, held: &[LogicalButton]) -> GamecubeInput {
+            self.input.update(press(held), self.clock.now());
+            self.profile.map_to_gamecube(&self.input)
+        }
+
+        /// Like [`Simulation::poll`], but first switches profiles through `config` the same as the firmware.
+        pub fn poll_switching(
+            &mut self,
+            held: &[LogicalButton],
+            config: &Config,
+        ) -> (Option<usize>, GamecubeInput) {
+            self.input.update(press(held), self.clock.now());
+            let switched = self.profile.change_profile(&self.input, config);
+            (switched, self.profile.map_to_gamecube(&self.input))
+        }
+    }
+
+    /// Map a single poll of the held buttons with a fresh profile, so no SOCD state carries over.
+    pub fn map(logic: BaseLogic, held: &[&[LogicalButton]]) -> GamecubeInput {
+        let held: Vec<LogicalButton> = held.iter().flat_map(|x| x.iter().copied()).collect();
+        Simulation::new(logic).poll(&held)
+    }
+
+    /// Checks the main stick for every direction while holding `held`.
+    pub fn assert_stick(logic: BaseLogic, held: &[LogicalButton], expected: [(u8, u8); 8]) {
+        for (direction, expected) in DIRECTIONS.iter().zip(expected) {
+            let result = map(logic, &[held, direction]);
+            assert_eq!(
+                (result.stick_x, result.stick_y),
+                expected,
+                "stick for {held:?} + {direction:?}"
+            );
+        }
+    }
+
+    /// Checks the C-stick for every C-stick direction while holding `held`.
+    pub fn assert_cstick(logic: BaseLogic, held: &[LogicalButton], expected: [(u8, u8); 8]) {
+        for (direction, expected) in CSTICK_DIRECTIONS.iter().zip(expected) {
+            let result = map(logic, &[held, direction]);
+            assert_eq!(
+                (result.cstick_x, result.cstick_y),
+                expected,
+                "cstick for {held:?} + {direction:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn coordinate_override() {
+        use LogicalButton::*;
+
+        let mut coordinates = Coordinates::new(BaseLogic::ProjectPlus);
+        *coordinates.get_mut("mod-x-diagonal-cstick-right").unwrap() = Coordinate::new(70, 60);
+        let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::ProjectPlus,
+            buttons: buttons(),
+            coordinates,
+            ..Default::default()
+        }));
+
+        let result = simulation.poll(&[ModX, StickUp, StickLeft, CstickRight]);
+        assert_eq!((result.stick_x, result.stick_y), (128 - 70, 128 + 60));
+
+        // Other coordinates keep their defaults
+        let result = simulation.poll(&[ModX, StickUp, StickLeft, CstickLeft]);
+        assert_eq!((result.stick_x, result.stick_y), (128 - 84, 128 + 50));
+    }
+
+    #[test]
+    fn overlays() {
+        use LogicalButton::*;
+        use OverlayCondition::*;
+
+        let overlay = |conditions: &[OverlayCondition], stick, cstick| Overlay {
+            conditions: conditions.iter().copied().collect(),
+            stick,
+            cstick,
+        };
+        let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::Rivals2,
+            buttons: buttons(),
+            coordinates: Coordinates::new(BaseLogic::Rivals2),
+            overlays: [
+                overlay(
+                    &[Held(ModX), Diagonal, Held(B), Held(CstickUp)],
+                    Some(Coordinate::new(60, 40)),
+                    None,
+                ),
+                overlay(&[Held(ModX), Diagonal], None, Some(Coordinate::new(30, 20))),
+                overlay(&[Held(ModX)], Some(Coordinate::new(1, 1)), None),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        }));
+
+        // The first overlay with all conditions met is used
+        let result = simulation.poll(&[ModX, StickUp, StickLeft, B, CstickUp]);
+        assert_eq!((result.stick_x, result.stick_y), (128 - 60, 128 + 40));
+        assert_eq!((result.cstick_x, result.cstick_y), (128, 128 + 127));
+
+        // Coordinates not set by the overlay come from the logic
+        let result = simulation.poll(&[ModX, StickUp, StickLeft, B, CstickDown]);
+        assert_eq!((result.stick_x, result.stick_y), (128 - 123, 128 + 51));
+        assert_eq!((result.cstick_x, result.cstick_y), (128, 128 - 20));
+
+        let result = simulation.poll(&[ModX, StickUp]);
+        assert_eq!((result.stick_x, result.stick_y), (128, 129));
+
+        // No overlay applies
+        let result = simulation.poll(&[StickUp]);
+        assert_eq!((result.stick_x, result.stick_y), (128, 128 + 127));
+    }
+
+    #[test]
+    fn trigger_levels() {
+        use LogicalButton::*;
+
+        let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::ProjectPlus,
+            buttons: buttons(),
+            triggers: TriggerLevels {
+                analog: 49,
+                analog_mod_x: 80,
+                analog_mod_y: 120,
+                digital: 30,
+            },
+            ..Default::default()
+        }));
+
+        let result = simulation.poll(&[LAnalog]);
+        assert_eq!((result.l_digital, result.l_analog), (false, 49));
+        let result = simulation.poll(&[ModX, RAnalog]);
+        assert_eq!((result.r_digital, result.r_analog), (false, 80));
+        let result = simulation.poll(&[ModY, LAnalog]);
+        assert_eq!((result.l_digital, result.l_analog), (false, 120));
+
+        // Digital triggers also send their own analog level
+        let result = simulation.poll(&[RDigital]);
+        assert_eq!((result.r_digital, result.r_analog), (true, 30));
+        let result = simulation.poll(&[LDigital, LAnalog]);
+        assert_eq!((result.l_digital, result.l_analog), (true, 49));
+    }
+
+    /// Polls each step in order 16ms apart, returning the main stick after every poll.
+    fn crouch_walk_polls(
+        crouch_walk_prevention: Option<Coordinate>,
+        steps: &[&[LogicalButton]],
+    ) -> Vec<(u8, u8)> {
+        let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::ProjectPlus,
+            buttons: buttons(),
+            crouch_walk_prevention,
+            ..Default::default()
+        }));
+        steps
+            .iter()
+            .map(|held| {
+                let result = simulation.poll(held);
+                simulation.clock.advance_millis(16);
+                (result.stick_x, result.stick_y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crouch_walk_prevention() {
+        use LogicalButton::*;
+
+        let safe = Some(Coordinate::new(40, 70));
+        let crouch = (128, 28);
+        let down_right = (228, 28);
+        let safe_down_right = (128 + 40, 128 - 70);
+        let safe_down_left = (128 - 40, 128 - 70);
+        let right = (228, 128);
+
+        // Down then right stays crouch safe until down is released
+        assert_eq!(
+            crouch_walk_polls(
+                safe,
+                &[&[StickDown], &[StickDown, StickRight], &[StickRight]]
+            ),
+            [crouch, safe_down_right, right]
+        );
+        assert_eq!(
+            crouch_walk_polls(safe, &[&[StickDown], &[StickDown, StickLeft]]),
+            [crouch, safe_down_left]
+        );
+
+        // Right then down is a normal diagonal
+        assert_eq!(
+            crouch_walk_polls(safe, &[&[StickRight], &[StickDown, StickRight]]),
+            [right, down_right]
+        );
+
+        // Pressed on the same poll is a normal diagonal
+        assert_eq!(
+            crouch_walk_polls(safe, &[&[StickDown, StickRight]]),
+            [down_right]
+        );
+
+        // Re-pressing right while down is held is crouch safe again
+        assert_eq!(
+            crouch_walk_polls(
+                safe,
+                &[
+                    &[StickRight],
+                    &[StickDown, StickRight],
+                    &[StickDown],
+                    &[StickDown, StickRight]
+                ]
+            ),
+            [right, down_right, crouch, safe_down_right]
+        );
+
+        // Releasing and pressing down again after right is a normal diagonal
+        assert_eq!(
+            crouch_walk_polls(
+                safe,
+                &[
+                    &[StickDown],
+                    &[StickDown, StickRight],
+                    &[StickRight],
+                    &[StickDown, StickRight]
+                ]
+            ),
+            [crouch, safe_down_right, right, down_right]
+        );
+
+        // Modifiers keep their own coordinates
+        assert_eq!(
+            crouch_walk_polls(safe, &[&[ModX, StickDown], &[ModX, StickDown, StickRight]])[1],
+            (198, 94)
+        );
+
+        // Disabled
+        assert_eq!(
+            crouch_walk_polls(None, &[&[StickDown], &[StickDown, StickRight]]),
+            [crouch, down_right]
+        );
+    }
+
+    #[test]
+    fn travel_time() {
+        use LogicalButton::*;
+
+        let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::ProjectPlus,
+            buttons: buttons(),
+            travel_time: TravelTime {
+                stick_micros: 4000,
+                cstick_micros: 0,
+            },
+            ..Default::default()
+        }));
+
+        let result = simulation.poll(&[StickRight, CstickUp]);
+        assert_eq!((result.stick_x, result.stick_y), (128, 128));
+        assert_eq!((result.cstick_x, result.cstick_y), (128, 228));
+        simulation.clock.advance_millis(1);
+        assert_eq!(simulation.poll(&[StickRight]).stick_x, 153);
+        simulation.clock.advance_millis(3);
+        assert_eq!(simulation.poll(&[StickRight]).stick_x, 228);
+    }
+
+    #[test]
+    fn socd_across_polls() {
+        use LogicalButton::*;
+
+        let mut simulation = Simulation::new(BaseLogic::ProjectPlus);
+        assert_eq!(simulation.poll(&[StickLeft]).stick_x, 28);
+        simulation.clock.advance_millis(16);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 228);
+        simulation.clock.advance_millis(16);
+        assert_eq!(simulation.poll(&[StickLeft]).stick_x, 28);
+    }
+
+    #[test]
+    fn assign() {
+        use PhysicalButton::*;
+
+        let mut buttons = buttons();
+        buttons.assign(LogicalButton::StickUp, LeftMiddle2);
+        assert_eq!(
+            buttons.stick_up.buttons.as_slice(),
+            [RightPinky, LeftMiddle2]
+        );
+        assert!(buttons.l_analog.buttons.is_empty());
+
+        // Assigning a button that is already bound moves it to the end, and a full binding drops its oldest button
+        for physical in [RightRing2, RightPinky, Start, LeftPinky] {
+            buttons.assign(LogicalButton::StickUp, physical);
+        }
+        assert_eq!(
+            buttons.stick_up.buttons.as_slice(),
+            [RightRing2, RightPinky, Start, LeftPinky]
+        );
+        assert!(buttons.start.buttons.is_empty());
+    }
+
+    #[test]
+    fn socd_per_axis() {
+        use LogicalButton::*;
+
+        let mut buttons = buttons();
+        buttons.assign(DpadDown, PhysicalButton::LeftMiddle2);
+        let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::Melee,
+            socd: SocdTypes {
+                stick_horizontal: SocdType::Neutral,
+                stick_vertical: SocdType::UpOrLeftPriority,
+                cstick_horizontal: SocdType::DownOrRightPriority,
+                cstick_vertical: SocdType::SecondInputPriority,
+                dpad: SocdType::DownOrRightPriority,
+            },
+            buttons,
+            ..Default::default()
+        }));
+
+        let result = simulation.poll(&[
+            StickLeft,
+            StickRight,
+            StickUp,
+            StickDown,
+            CstickLeft,
+            CstickRight,
+        ]);
+        assert_eq!((result.stick_x, result.stick_y), (128, 208));
+        assert_eq!((result.cstick_x, result.cstick_y), (208, 128));
+
+        // The physical button pressed for LAnalog is bound to dpad-down by this profile
+        let result = simulation.poll(&[DpadUp, LAnalog]);
+        assert_eq!((result.dpad_up, result.dpad_down), (false, true));
+    }
+
+    #[test]
+    fn tap_hold_socd() {
+        use LogicalButton::*;
+
+        // Held on the physical button bound to dpad-up, stick-left is only pressed once the hold is decided
+        let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::ProjectPlus,
+            buttons: buttons(),
+            tap_holds: [TapHold {
+                button: PhysicalButton::RightPinky2,
+                tap: DpadUp,
+                hold: StickLeft,
+                tapping_term_ms: 100,
+                hold_on_other_key_press: false,
+                tap_ms: TapHold::DEFAULT_TAP_MS,
+            }]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        }));
+        assert_eq!(simulation.poll(&[StickRight]).stick_x, 228);
+        simulation.clock.advance_millis(16);
+        let result = simulation.poll(&[DpadUp, StickRight]);
+        assert_eq!((result.stick_x, result.dpad_up), (228, false));
+        // Decided after stick-right was pressed, so it wins with second input priority
+        simulation.clock.advance_millis(100);
+        assert_eq!(simulation.poll(&[DpadUp, StickRight]).stick_x, 28);
+        simulation.clock.advance_millis(16);
+        assert_eq!(simulation.poll(&[StickRight]).stick_x, 228);
+    }
+
+    #[test]
+    fn latches() {
+        use LogicalButton::*;
+
+        let profile = Profile {
+            logic: BaseLogic::ProjectPlus,
+            buttons: buttons(),
+            activation_combination: [PhysicalButton::Start, PhysicalButton::RightPinky2]
+                .into_iter()
+                .collect(),
+            latches: [Latch {
+                logical: LAnalog,
+                kind: LatchKind::Toggle,
+            }]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let mut simulation = Simulation::from_profile(MapProfile::new(&profile));
+        assert_eq!(simulation.poll(&[LAnalog]).l_analog, 49);
+        assert_eq!(simulation.poll(&[]).l_analog, 49);
+        assert!(simulation.profile.latched());
+
+        // Reactivating the profile releases the latch
+        let config = Config {
+            version: 0,
+            profiles: [profile].into_iter().collect(),
+        };
+        assert_eq!(
+            simulation.poll_switching(&[Start, DpadUp], &config).0,
+            Some(0)
+        );
+        assert!(!simulation.profile.latched());
+        assert_eq!(simulation.poll(&[]).l_analog, 0);
+    }
+
+    /// A Project+ profile switched to by holding start and the buttons bound to `combination`.
+    fn switchable(combination: &[LogicalButton], activation_hold_ms: u32) -> Profile {
+        Profile {
+            logic: BaseLogic::ProjectPlus,
+            buttons: buttons(),
+            activation_combination: [LogicalButton::Start]
+                .iter()
+                .chain(combination)
+                .flat_map(|logical| buttons().get(*logical).buttons.clone())
+                .collect(),
+            activation_hold_ms,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn profile_switching() {
+        use LogicalButton::*;
+
+        let config = Config {
+            version: 0,
+            profiles: [switchable(&[A], 0), switchable(&[StickLeft], 0)]
+                .into_iter()
+                .collect(),
+        };
+        let mut simulation = Simulation::from_profile(MapProfile::new(&config.profiles[0]));
+        let (switched, result) = simulation.poll_switching(&[Start], &config);
+        assert_eq!((switched, result.start), (None, true));
+
+        // Only switches on the poll the combination is completed, and none of its buttons reach the game
+        let (switched, result) = simulation.poll_switching(&[Start, StickLeft], &config);
+        assert_eq!(
+            (switched, result.start, result.stick_x),
+            (Some(1), false, 128)
+        );
+        let (switched, result) = simulation.poll_switching(&[Start, StickLeft], &config);
+        assert_eq!((switched, result.start, result.stick_x), (None, false, 128));
+
+        // Each button stays suppressed until it is released, even once the combination is broken
+        let (switched, result) = simulation.poll_switching(&[Start, B], &config);
+        assert_eq!((switched, result.start, result.b), (None, false, true));
+        let (switched, result) = simulation.poll_switching(&[StickLeft], &config);
+        assert_eq!((switched, result.stick_x), (None, 28));
+
+        // Completing the combination again switches again
+        simulation.poll_switching(&[], &config);
+        assert_eq!(
+            simulation.poll_switching(&[Start, StickLeft], &config).0,
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn profile_switching_hold() {
+        use LogicalButton::*;
+
+        let config = Config {
+            version: 0,
+            profiles: [switchable(&[A], 0), switchable(&[StickLeft], 500)]
+                .into_iter()
+                .collect(),
+        };
+        let mut simulation = Simulation::from_profile(MapProfile::new(&config.profiles[0]));
+        let mut switch_after = |millis: u64, held: &[LogicalButton]| {
+            simulation.clock.advance_millis(millis);
+            let (switched, result) = simulation.poll_switching(held, &config);
+            // Suppressed while the combination is held, whether or not it has switched yet
+            assert!(!result.start);
+            switched
+        };
+        assert_eq!(switch_after(0, &[Start, StickLeft]), None);
+        assert_eq!(switch_after(499, &[Start, StickLeft]), None);
+        assert_eq!(switch_after(1, &[Start, StickLeft]), Some(1));
+        assert_eq!(switch_after(1000, &[Start, StickLeft]), None);
+
+        // Releasing early restarts the hold
+        assert_eq!(switch_after(16, &[Start]), None);
+        assert_eq!(switch_after(16, &[Start, StickLeft]), None);
+        assert_eq!(switch_after(16, &[Start]), None);
+        assert_eq!(switch_after(490, &[Start, StickLeft]), None);
+    }
+
+    #[test]
+    fn melee_2024_ruleset() {
+        use LogicalButton::*;
+
+        let violations = |profile: &Profile| {
+            let mut violations = vec![];
+            profile.check_ruleset(|violation| violations.push(violation));
+            violations
+        };
+        let melee = || Profile {
+            logic: BaseLogic::Melee,
+            // The c-stick and dpad keep the default second-input-priority, which is allowed on them
+            socd: SocdTypes {
+                stick_horizontal: SocdType::SecondInputPriorityNoReactivation,
+                stick_vertical: SocdType::UpOrLeftPriority,
+                ..Default::default()
+            },
+            coordinates: Coordinates::new(BaseLogic::Melee),
+            ruleset: Ruleset::Melee2024,
+            ..Default::default()
+        };
+        assert_eq!(violations(&melee()), []);
+        assert_eq!(
+            violations(&Profile {
+                latches: [Latch::default()].into_iter().collect(),
+                ..melee()
+            }),
+            [RulesetViolation::Latch { index: 0 }]
+        );
+        assert_eq!(
+            violations(&Profile {
+                socd: SocdTypes::new(SocdType::SecondInputPriority),
+                ..melee()
+            }),
+            [
+                RulesetViolation::Socd {
+                    axis: "stick-horizontal"
+                },
+                RulesetViolation::Socd {
+                    axis: "stick-vertical"
+                }
+            ]
+        );
+        let chord = |consume| Chord {
+            buttons: [PhysicalButton::LeftThumbLeft, PhysicalButton::RightRing]
+                .into_iter()
+                .collect(),
+            logical: Z,
+            consume,
+        };
+        assert_eq!(
+            violations(&Profile {
+                chords: [chord(false), chord(true)].into_iter().collect(),
+                layers: [Layer {
+                    activation: LayerActivation::Hold(PhysicalButton::RightPinky2),
+                    bindings: Default::default(),
+                }]
+                .into_iter()
+                .collect(),
+                tap_holds: [TapHold {
+                    button: PhysicalButton::LeftThumbLeft,
+                    tap: DpadUp,
+                    hold: ModX,
+                    tapping_term_ms: 200,
+                    hold_on_other_key_press: false,
+                    tap_ms: TapHold::DEFAULT_TAP_MS,
+                }]
+                .into_iter()
+                .collect(),
+                ..melee()
+            }),
+            [
+                RulesetViolation::Chord { index: 1 },
+                RulesetViolation::Layer { index: 0 },
+                RulesetViolation::TapHold { index: 0 },
+            ]
+        );
+
+        let project_plus = Profile {
+            ruleset: Ruleset::Melee2024,
+            ..Default::default()
+        };
+        assert_eq!(violations(&project_plus)[0], RulesetViolation::Logic);
+        assert_eq!(
+            violations(&Profile {
+                ruleset: Ruleset::Unrestricted,
+                ..project_plus
+            }),
+            []
+        );
+
+        let mut overlays = Profile {
+            overlays: [
+                Overlay {
+                    conditions: [OverlayCondition::Held(ModX), OverlayCondition::Held(B)]
+                        .into_iter()
+                        .collect(),
+                    stick: Some(Coordinate::new(80, 80)),
+                    cstick: None,
+                },
+                Overlay {
+                    conditions: [OverlayCondition::Diagonal, OverlayCondition::Held(A)]
+                        .into_iter()
+                        .collect(),
+                    stick: None,
+                    cstick: Some(Coordinate::new(81, 0)),
+                },
+            ]
+            .into_iter()
+            .collect(),
+            crouch_walk_prevention: Some(Coordinate::new(40, 90)),
+            ..melee()
+        };
+        assert_eq!(
+            violations(&overlays),
+            [
+                RulesetViolation::OverlayCondition {
+                    index: 1,
+                    condition: "a"
+                },
+                RulesetViolation::OverlayCoordinate { index: 1, max: 80 },
+                RulesetViolation::CrouchWalkPrevention { max: 80 },
+            ]
+        );
+
+        if let Coordinates::Melee(coordinates) = &mut overlays.coordinates {
+            coordinates.diagonal = Coordinate::new(56, 81);
+        }
+        overlays.overlays.clear();
+        overlays.crouch_walk_prevention = None;
+        assert_eq!(
+            overlays.ruleset_violation(),
+            Some(RulesetViolation::Coordinate {
+                name: "diagonal",
+                max: 80
+            })
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use super::{ProfileInput, ProfileLogic};
+use crate::input::ButtonInputLogical;
+use rukaibox_config::{Coordinates, MeleeCoordinates, Profile};
+
+pub struct MeleeLogic {
+    coordinates: MeleeCoordinates,
+}
+
+impl ProfileLogic for MeleeLogic {
+    fn new(profile: &Profile) -> Self {
+        let coordinates = match &profile.coordinates {
+            Coordinates::Melee(coordinates) => coordinates.clone(),
+            _ => Default::default(),
+        };
+        MeleeLogic { coordinates }
+    }
+
+    fn stick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let ButtonInputLogical {
+            mod_x,
+            mod_y,
+            b,
+            l_digital,
+            r_digital,
+            l_analog,
+            r_analog,
+            stick_down,
+            cstick_left,
+            cstick_right,
+            cstick_up,
+            cstick_down,
+            ..
+        } = input.buttons;
+        let stick = input.stick;
+        // Lightshield is included so that wavedashes work the same out of lightshield.
+        let shield = l_digital || r_digital || l_analog || r_analog;
+        let c = &self.coordinates;
+
+        let coordinate = if mod_x {
+            if stick.diagonal() {
+                if shield {
+                    c.mod_x_diagonal_shield
+                } else if b {
+                    if cstick_right {
+                        c.mod_x_diagonal_b_cstick_right
+                    } else if cstick_up {
+                        c.mod_x_diagonal_b_cstick_up
+                    } else if cstick_left {
+                        c.mod_x_diagonal_b_cstick_left
+                    } else if cstick_down {
+                        c.mod_x_diagonal_b_cstick_down
+                    } else {
+                        c.mod_x_diagonal_b
+                    }
+                } else if cstick_right {
+                    c.mod_x_diagonal_cstick_right
+                } else if cstick_up {
+                    c.mod_x_diagonal_cstick_up
+                } else if cstick_left {
+                    c.mod_x_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_x_diagonal_cstick_down
+                } else {
+                    c.mod_x_diagonal
+                }
+            } else if stick.horizontal() {
+                c.mod_x_horizontal
+            } else {
+                c.mod_x_vertical
+            }
+        } else if mod_y {
+            if stick.diagonal() {
+                if shield {
+                    if stick_down {
+                        c.mod_y_diagonal_shield_down
+                    } else {
+                        c.mod_y_diagonal_shield
+                    }
+                } else if b {
+                    if cstick_right {
+                        c.mod_y_diagonal_b_cstick_right
+                    } else if cstick_up {
+                        c.mod_y_diagonal_b_cstick_up
+                    } else if cstick_left {
+                        c.mod_y_diagonal_b_cstick_left
+                    } else if cstick_down {
+                        c.mod_y_diagonal_b_cstick_down
+                    } else {
+                        c.mod_y_diagonal_b
+                    }
+                } else if cstick_right {
+                    c.mod_y_diagonal_cstick_right
+                } else if cstick_up {
+                    c.mod_y_diagonal_cstick_up
+                } else if cstick_left {
+                    c.mod_y_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_y_diagonal_cstick_down
+                } else {
+                    c.mod_y_diagonal
+                }
+            } else if stick.horizontal() {
+                c.mod_y_horizontal
+            } else {
+                c.mod_y_vertical
+            }
+        } else if stick.diagonal() {
+            if shield && stick_down {
+                c.diagonal_shield_down
+            } else {
+                c.diagonal
+            }
+        } else {
+            c.max
+        };
+        stick.offset(coordinate)
+    }
+
+    fn cstick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let cstick = input.cstick;
+        let c = &self.coordinates;
+
+        // ASDI slideoff overrides angled fsmash, so check it first.
+        if cstick.diagonal() {
+            cstick.offset(c.cstick_diagonal)
+        } else if input.buttons.mod_x && cstick.horizontal() {
+            let angled = c.cstick_mod_x_horizontal;
+            (cstick.x * angled.x as i16, input.stick.y * angled.y as i16)
+        } else {
+            cstick.offset(c.cstick_max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::tests::{Case, assert_cstick, assert_stick, map};
+    use rukaibox_config::{BaseLogic, LogicalButton::*};
+
+    #[test]
+    fn stick_coordinates() {
+        // Each row lists the held buttons followed by the expected stick value for each direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[CstickRight], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[CstickLeft], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[CstickUp], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[CstickDown], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[RDigital], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 73), (128, 48), (184, 73)]),
+            (&[RAnalog], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 73), (128, 48), (184, 73)]),
+            (&[B], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[ModX], [(181, 128), (187, 153), (128, 171), (69, 153), (75, 128), (69, 103), (128, 85), (187, 103)]),
+            (&[ModX, CstickRight], [(181, 128), (177, 170), (128, 171), (79, 170), (75, 128), (79, 86), (128, 85), (177, 86)]),
+            (&[ModX, CstickLeft], [(181, 128), (191, 167), (128, 171), (65, 167), (75, 128), (65, 89), (128, 85), (191, 89)]),
+            (&[ModX, CstickUp], [(181, 128), (184, 169), (128, 171), (72, 169), (75, 128), (72, 87), (128, 85), (184, 87)]),
+            (&[ModX, CstickDown], [(181, 128), (184, 157), (128, 171), (72, 157), (75, 128), (72, 99), (128, 85), (184, 99)]),
+            (&[ModX, RDigital], [(181, 128), (179, 158), (128, 171), (77, 158), (75, 128), (77, 98), (128, 85), (179, 98)]),
+            (&[ModX, B], [(181, 128), (201, 159), (128, 171), (55, 159), (75, 128), (55, 97), (128, 85), (201, 97)]),
+            (&[ModX, B, CstickRight], [(181, 128), (179, 171), (128, 171), (77, 171), (75, 128), (77, 85), (128, 85), (179, 85)]),
+            (&[ModX, B, CstickLeft], [(181, 128), (196, 170), (128, 171), (60, 170), (75, 128), (60, 86), (128, 85), (196, 86)]),
+            (&[ModX, B, CstickUp], [(181, 128), (187, 171), (128, 171), (69, 171), (75, 128), (69, 85), (128, 85), (187, 85)]),
+            (&[ModX, B, CstickDown], [(181, 128), (198, 164), (128, 171), (58, 164), (75, 128), (58, 92), (128, 85), (198, 92)]),
+            (&[ModY], [(155, 128), (153, 187), (128, 187), (103, 187), (101, 128), (103, 69), (128, 69), (153, 69)]),
+            (&[ModY, CstickRight], [(155, 128), (170, 177), (128, 187), (86, 177), (101, 128), (86, 79), (128, 69), (170, 79)]),
+            (&[ModY, CstickLeft], [(155, 128), (167, 191), (128, 187), (89, 191), (101, 128), (89, 65), (128, 69), (167, 65)]),
+            (&[ModY, CstickUp], [(155, 128), (169, 184), (128, 187), (87, 184), (101, 128), (87, 72), (128, 69), (169, 72)]),
+            (&[ModY, CstickDown], [(155, 128), (157, 184), (128, 187), (99, 184), (101, 128), (99, 72), (128, 69), (157, 72)]),
+            (&[ModY, RDigital], [(155, 128), (166, 198), (128, 187), (90, 198), (101, 128), (88, 60), (128, 69), (168, 60)]),
+            (&[ModY, B], [(155, 128), (159, 201), (128, 187), (97, 201), (101, 128), (97, 55), (128, 69), (159, 55)]),
+            (&[ModY, B, CstickRight], [(155, 128), (171, 179), (128, 187), (85, 179), (101, 128), (85, 77), (128, 69), (171, 77)]),
+            (&[ModY, B, CstickLeft], [(155, 128), (170, 196), (128, 187), (86, 196), (101, 128), (86, 60), (128, 69), (170, 60)]),
+            (&[ModY, B, CstickUp], [(155, 128), (171, 187), (128, 187), (85, 187), (101, 128), (85, 69), (128, 69), (171, 69)]),
+            (&[ModY, B, CstickDown], [(155, 128), (164, 198), (128, 187), (92, 198), (101, 128), (92, 58), (128, 69), (164, 58)]),
+        ];
+        for (held, expected) in cases {
+            assert_stick(BaseLogic::Melee, held, *expected);
+        }
+    }
+
+    #[test]
+    fn cstick_coordinates() {
+        // Each row lists the held buttons followed by the expected C-stick value for each C-stick direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(208, 128), (170, 196), (128, 208), (86, 196), (48, 128), (86, 60), (128, 48), (170, 60)]),
+            (&[ModX], [(196, 128), (170, 196), (128, 208), (86, 196), (60, 128), (86, 60), (128, 48), (170, 60)]),
+            (&[ModY], [(208, 128), (170, 196), (128, 208), (86, 196), (48, 128), (86, 60), (128, 48), (170, 60)]),
+            (&[ModX, StickUp], [(196, 170), (170, 196), (128, 208), (86, 196), (60, 170), (86, 60), (128, 48), (170, 60)]),
+            (&[ModX, StickDown], [(196, 86), (170, 196), (128, 208), (86, 196), (60, 86), (86, 60), (128, 48), (170, 60)]),
+        ];
+        for (held, expected) in cases {
+            assert_cstick(BaseLogic::Melee, held, *expected);
+        }
+    }
+
+    #[test]
+    fn cardinals_are_exactly_one() {
+        // Melee clamps to 1.0 at an offset of 80, anything further would be wasted range
+        let result = map(BaseLogic::Melee, &[&[StickRight]]);
+        assert_eq!((result.stick_x, result.stick_y), (128 + 80, 128));
+        let result = map(BaseLogic::Melee, &[&[CstickDown]]);
+        assert_eq!((result.cstick_x, result.cstick_y), (128, 128 - 80));
+    }
+
+    #[test]
+    fn neutral() {
+        let result = map(BaseLogic::Melee, &[]);
+        assert_eq!(result, crate::GamecubeInput::NEUTRAL);
+    }
+
+    #[test]
+    fn triggers() {
+        let result = map(BaseLogic::Melee, &[&[LDigital]]);
+        assert_eq!((result.l_digital, result.l_analog), (true, 0));
+        let result = map(BaseLogic::Melee, &[&[RAnalog]]);
+        assert_eq!((result.r_digital, result.r_analog), (false, 49));
+    }
+}
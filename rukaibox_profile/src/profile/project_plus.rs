@@ -0,0 +1,323 @@
+use super::{ProfileInput, ProfileLogic};
+use crate::input::ButtonInputLogical;
+use rukaibox_config::{Coordinates, LogicalButton, Profile, ProjectPlusCoordinates};
+
+pub struct ProjectPlusLogic {
+    coordinates: ProjectPlusCoordinates,
+    /// How long after releasing down the ledgedash SOCD override lasts, in microseconds.
+    ledgedash_window: Option<u64>,
+}
+
+impl ProfileLogic for ProjectPlusLogic {
+    fn new(profile: &Profile) -> Self {
+        let coordinates = match &profile.coordinates {
+            Coordinates::ProjectPlus(coordinates) => coordinates.clone(),
+            _ => Default::default(),
+        };
+        let ledgedash_window = match profile.ledgedash_socd_override_ms {
+            0 => None,
+            ms => Some(ms as u64 * 1000),
+        };
+        ProjectPlusLogic {
+            coordinates,
+            ledgedash_window,
+        }
+    }
+
+    fn stick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let ButtonInputLogical {
+            mod_x,
+            mod_y,
+            b,
+            l_digital,
+            r_digital,
+            stick_up,
+            cstick_left,
+            cstick_right,
+            cstick_up,
+            cstick_down,
+            ..
+        } = input.buttons;
+        let stick = input.stick;
+        let c = &self.coordinates;
+
+        let coordinate = if mod_x {
+            if stick.diagonal() {
+                if cstick_right {
+                    c.mod_x_diagonal_cstick_right
+                } else if cstick_left {
+                    c.mod_x_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_x_diagonal_cstick_down
+                } else if cstick_up {
+                    c.mod_x_diagonal_cstick_up
+                } else if r_digital || l_digital {
+                    c.mod_x_diagonal_shield
+                } else if b {
+                    c.mod_x_diagonal_b
+                } else {
+                    c.mod_x_diagonal
+                }
+            } else if stick.vertical() {
+                c.mod_x_vertical
+            } else {
+                c.mod_x_horizontal
+            }
+        } else if mod_y {
+            if stick.diagonal() {
+                if cstick_right {
+                    c.mod_y_diagonal_cstick_right
+                } else if cstick_left {
+                    c.mod_y_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_y_diagonal_cstick_down
+                } else if cstick_up {
+                    c.mod_y_diagonal_cstick_up
+                } else if r_digital || l_digital {
+                    c.mod_y_diagonal_shield
+                } else if b {
+                    c.mod_y_diagonal_b
+                } else {
+                    c.mod_y_diagonal
+                }
+            } else if stick.vertical() {
+                c.mod_y_vertical
+            } else {
+                c.mod_y_horizontal
+            }
+        } else if stick.diagonal() && stick_up {
+            c.diagonal_up
+        } else {
+            c.max
+        };
+        stick.offset(coordinate)
+    }
+
+    fn horizontal_socd_override(&mut self, input: &ProfileInput) -> bool {
+        // Ledgedashes drop from ledge with down and then press towards the stage while the direction away
+        // from the stage may still be held, which SOCD would otherwise neutralize or ignore.
+        let Some(window) = self.ledgedash_window else {
+            return false;
+        };
+        let buttons = &input.buttons;
+        if !buttons.stick_left || !buttons.stick_right || buttons.stick_down {
+            return false;
+        }
+        let Some(down_released) = input.released_at(LogicalButton::StickDown) else {
+            return false;
+        };
+        let horizontal_pressed = input
+            .pressed_at(LogicalButton::StickLeft)
+            .max(input.pressed_at(LogicalButton::StickRight));
+
+        horizontal_pressed.is_some_and(|pressed| pressed >= down_released)
+            && input.now().micros_since(down_released) <= window
+    }
+
+    fn cstick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let cstick = input.cstick;
+        let c = &self.coordinates;
+
+        // Unlike Ultimate, angled smashes are checked first so mod-x still angles a diagonal C-stick.
+        if input.buttons.mod_x && cstick.horizontal() {
+            // Allow for angled smash attacks
+            let angled = c.cstick_mod_x_horizontal;
+            (cstick.x * angled.x as i16, input.stick.y * angled.y as i16)
+        } else if cstick.diagonal() {
+            // ASDI slideoff
+            cstick.offset(c.cstick_diagonal)
+        } else {
+            cstick.offset(c.cstick_max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::MapProfile;
+    use crate::profile::tests::{Case, Simulation, assert_cstick, assert_stick, buttons, map};
+    use rukaibox_config::{
+        BaseLogic, Coordinates, LogicalButton, LogicalButton::*, Profile, SocdType, SocdTypes,
+    };
+
+    #[test]
+    fn stick_coordinates() {
+        // Each row lists the held buttons followed by the expected stick value for each direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickRight], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickLeft], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickUp], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickDown], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[RDigital], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[A], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[Z], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickRight], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickUp], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickLeft], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickDown], [(228, 128), (211, 221), (128, 228), (45, 221), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[ModX], [(198, 128), (198, 162), (128, 188), (58, 162), (58, 128), (58, 94), (128, 68), (198, 94)]),
+            (&[ModX, CstickRight], [(198, 128), (200, 189), (128, 188), (56, 189), (58, 128), (56, 67), (128, 68), (200, 67)]),
+            (&[ModX, CstickLeft], [(198, 128), (212, 178), (128, 188), (44, 178), (58, 128), (44, 78), (128, 68), (212, 78)]),
+            (&[ModX, CstickUp], [(198, 128), (205, 183), (128, 188), (51, 183), (58, 128), (51, 73), (128, 68), (205, 73)]),
+            (&[ModX, CstickDown], [(198, 128), (210, 164), (128, 188), (46, 164), (58, 128), (46, 92), (128, 68), (210, 92)]),
+            (&[ModX, RDigital], [(198, 128), (210, 163), (128, 188), (46, 163), (58, 128), (46, 93), (128, 68), (210, 93)]),
+            (&[ModX, A], [(198, 128), (198, 162), (128, 188), (58, 162), (58, 128), (58, 94), (128, 68), (198, 94)]),
+            (&[ModX, B], [(198, 128), (213, 159), (128, 188), (43, 159), (58, 128), (43, 97), (128, 68), (213, 97)]),
+            (&[ModX, Z], [(198, 128), (198, 162), (128, 188), (58, 162), (58, 128), (58, 94), (128, 68), (198, 94)]),
+            (&[ModX, B, CstickRight], [(198, 128), (200, 189), (128, 188), (56, 189), (58, 128), (56, 67), (128, 68), (200, 67)]),
+            (&[ModX, B, CstickUp], [(198, 128), (205, 183), (128, 188), (51, 183), (58, 128), (51, 73), (128, 68), (205, 73)]),
+            (&[ModX, B, CstickLeft], [(198, 128), (212, 178), (128, 188), (44, 178), (58, 128), (44, 78), (128, 68), (212, 78)]),
+            (&[ModX, B, CstickDown], [(198, 128), (210, 164), (128, 188), (46, 164), (58, 128), (46, 92), (128, 68), (210, 92)]),
+            (&[ModY], [(163, 128), (156, 186), (128, 198), (100, 186), (93, 128), (100, 70), (128, 58), (156, 70)]),
+            (&[ModY, CstickRight], [(163, 128), (190, 200), (128, 198), (66, 200), (93, 128), (66, 56), (128, 58), (190, 56)]),
+            (&[ModY, CstickLeft], [(163, 128), (168, 212), (128, 198), (88, 212), (93, 128), (88, 44), (128, 58), (168, 44)]),
+            (&[ModY, CstickUp], [(163, 128), (183, 205), (128, 198), (73, 205), (93, 128), (73, 51), (128, 58), (183, 51)]),
+            (&[ModY, CstickDown], [(163, 128), (162, 210), (128, 198), (94, 210), (93, 128), (94, 46), (128, 58), (162, 46)]),
+            (&[ModY, RDigital], [(163, 128), (179, 210), (128, 198), (77, 210), (93, 128), (77, 46), (128, 58), (179, 46)]),
+            (&[ModY, A], [(163, 128), (156, 186), (128, 198), (100, 186), (93, 128), (100, 70), (128, 58), (156, 70)]),
+            (&[ModY, B], [(163, 128), (156, 213), (128, 198), (100, 213), (93, 128), (100, 43), (128, 58), (156, 43)]),
+            (&[ModY, Z], [(163, 128), (156, 186), (128, 198), (100, 186), (93, 128), (100, 70), (128, 58), (156, 70)]),
+            (&[ModY, B, CstickRight], [(163, 128), (190, 200), (128, 198), (66, 200), (93, 128), (66, 56), (128, 58), (190, 56)]),
+            (&[ModY, B, CstickUp], [(163, 128), (183, 205), (128, 198), (73, 205), (93, 128), (73, 51), (128, 58), (183, 51)]),
+            (&[ModY, B, CstickLeft], [(163, 128), (168, 212), (128, 198), (88, 212), (93, 128), (88, 44), (128, 58), (168, 44)]),
+            (&[ModY, B, CstickDown], [(163, 128), (162, 210), (128, 198), (94, 210), (93, 128), (94, 46), (128, 58), (162, 46)]),
+        ];
+        for (held, expected) in cases {
+            assert_stick(BaseLogic::ProjectPlus, held, *expected);
+        }
+    }
+
+    #[test]
+    fn cstick_coordinates() {
+        // Each row lists the held buttons followed by the expected C-stick value for each C-stick direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(228, 128), (180, 213), (128, 228), (76, 213), (28, 128), (76, 43), (128, 28), (180, 43)]),
+            (&[ModX], [(193, 128), (193, 128), (128, 228), (63, 128), (63, 128), (63, 128), (128, 28), (193, 128)]),
+            (&[ModY], [(228, 128), (180, 213), (128, 228), (76, 213), (28, 128), (76, 43), (128, 28), (180, 43)]),
+            (&[StickUp], [(228, 128), (180, 213), (128, 228), (76, 213), (28, 128), (76, 43), (128, 28), (180, 43)]),
+            (&[ModX, StickUp], [(193, 151), (193, 151), (128, 228), (63, 151), (63, 151), (63, 151), (128, 28), (193, 151)]),
+            (&[ModX, StickDown], [(193, 105), (193, 105), (128, 228), (63, 105), (63, 105), (63, 105), (128, 28), (193, 105)]),
+        ];
+        for (held, expected) in cases {
+            assert_cstick(BaseLogic::ProjectPlus, held, *expected);
+        }
+    }
+
+    #[test]
+    fn neutral() {
+        let result = map(BaseLogic::ProjectPlus, &[]);
+        assert_eq!(result, crate::GamecubeInput::NEUTRAL);
+    }
+
+    #[test]
+    fn triggers() {
+        let result = map(BaseLogic::ProjectPlus, &[&[LDigital]]);
+        assert_eq!((result.l_digital, result.l_analog), (true, 0));
+        let result = map(BaseLogic::ProjectPlus, &[&[RDigital]]);
+        assert_eq!((result.r_digital, result.r_analog), (true, 0));
+        let result = map(BaseLogic::ProjectPlus, &[&[LAnalog]]);
+        assert_eq!((result.l_digital, result.l_analog), (false, 49));
+        let result = map(BaseLogic::ProjectPlus, &[&[RAnalog]]);
+        assert_eq!((result.r_digital, result.r_analog), (false, 49));
+    }
+
+    #[test]
+    fn dpad_layer() {
+        // mod-x + mod-y turns the C-stick into a dpad
+        let cases: [(LogicalButton, [bool; 4]); 4] = [
+            (CstickUp, [true, false, false, false]),
+            (CstickDown, [false, true, false, false]),
+            (CstickLeft, [false, false, true, false]),
+            (CstickRight, [false, false, false, true]),
+        ];
+        for (cstick, expected) in cases {
+            let result = map(BaseLogic::ProjectPlus, &[&[ModX, ModY, cstick]]);
+            let dpad = [
+                result.dpad_up,
+                result.dpad_down,
+                result.dpad_left,
+                result.dpad_right,
+            ];
+            assert_eq!(dpad, expected, "dpad for {cstick:?}");
+            assert_eq!((result.cstick_x, result.cstick_y), (128, 128));
+        }
+
+        // Dpad buttons also disable the C-stick
+        let result = map(BaseLogic::ProjectPlus, &[&[DpadUp, CstickUp]]);
+        assert!(result.dpad_up);
+        assert_eq!((result.cstick_x, result.cstick_y), (128, 128));
+    }
+
+    fn ledgedash_simulation(socd: SocdType, ledgedash_socd_override_ms: u32) -> Simulation {
+        Simulation::from_profile(MapProfile::new(&Profile {
+            logic: BaseLogic::ProjectPlus,
+            socd: SocdTypes::new(socd),
+            buttons: buttons(),
+            coordinates: Coordinates::new(BaseLogic::ProjectPlus),
+            ledgedash_socd_override_ms,
+            ..Default::default()
+        }))
+    }
+
+    /// Drop from ledge with down while holding left, then press right after down is released.
+    fn drop_from_ledge(simulation: &mut Simulation) {
+        simulation.poll(&[StickLeft, StickDown]);
+        simulation.clock.advance_millis(16);
+        simulation.poll(&[StickLeft]);
+        simulation.clock.advance_millis(16);
+    }
+
+    #[test]
+    fn ledgedash_socd_override() {
+        let mut simulation = ledgedash_simulation(SocdType::Neutral, 100);
+        drop_from_ledge(&mut simulation);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 228);
+
+        // Still overridden while held within the window
+        simulation.clock.advance_millis(50);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 228);
+
+        // Back to the profile's SOCD type after the window
+        simulation.clock.advance_millis(100);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 128);
+    }
+
+    #[test]
+    fn ledgedash_socd_override_no_reactivation() {
+        // The override leaves left overridden by right, so releasing right must not reactivate left
+        let mut simulation = ledgedash_simulation(SocdType::SecondInputPriorityNoReactivation, 100);
+        drop_from_ledge(&mut simulation);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 228);
+        simulation.clock.advance_millis(16);
+        assert_eq!(simulation.poll(&[StickLeft]).stick_x, 128);
+    }
+
+    #[test]
+    fn ledgedash_socd_override_after_window() {
+        let mut simulation = ledgedash_simulation(SocdType::Neutral, 100);
+        drop_from_ledge(&mut simulation);
+        simulation.clock.advance_millis(200);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 128);
+    }
+
+    #[test]
+    fn ledgedash_socd_override_requires_press_after_down() {
+        let mut simulation = ledgedash_simulation(SocdType::Neutral, 100);
+        simulation.poll(&[StickLeft, StickRight, StickDown]);
+        simulation.clock.advance_millis(16);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 128);
+    }
+
+    #[test]
+    fn ledgedash_socd_override_disabled() {
+        let mut simulation = ledgedash_simulation(SocdType::Neutral, 0);
+        drop_from_ledge(&mut simulation);
+        assert_eq!(simulation.poll(&[StickLeft, StickRight]).stick_x, 128);
+    }
+}
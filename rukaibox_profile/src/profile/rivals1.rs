@@ -0,0 +1,160 @@
+use super::{ProfileInput, ProfileLogic};
+use crate::input::ButtonInputLogical;
+use rukaibox_config::{Coordinates, Profile, Rivals1Coordinates};
+
+pub struct Rivals1Logic {
+    coordinates: Rivals1Coordinates,
+}
+
+impl ProfileLogic for Rivals1Logic {
+    fn new(profile: &Profile) -> Self {
+        let coordinates = match &profile.coordinates {
+            Coordinates::Rivals1(coordinates) => coordinates.clone(),
+            _ => Default::default(),
+        };
+        Rivals1Logic { coordinates }
+    }
+
+    fn stick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let ButtonInputLogical {
+            mod_x,
+            mod_y,
+            l_digital,
+            r_digital,
+            cstick_left,
+            cstick_right,
+            cstick_up,
+            cstick_down,
+            ..
+        } = input.buttons;
+        let stick = input.stick;
+        let shield = l_digital || r_digital;
+        let c = &self.coordinates;
+
+        let coordinate = if mod_x {
+            if stick.diagonal() {
+                if shield {
+                    // wavedash
+                    c.mod_x_diagonal_shield
+                } else if cstick_right {
+                    c.mod_x_diagonal_cstick_right
+                } else if cstick_up {
+                    c.mod_x_diagonal_cstick_up
+                } else if cstick_left {
+                    c.mod_x_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_x_diagonal_cstick_down
+                } else {
+                    c.mod_x_diagonal
+                }
+            } else if stick.horizontal() {
+                c.mod_x_horizontal
+            } else {
+                c.mod_x_vertical
+            }
+        } else if mod_y {
+            if stick.diagonal() {
+                if shield {
+                    // wavedash
+                    c.mod_y_diagonal_shield
+                } else if cstick_right {
+                    c.mod_y_diagonal_cstick_right
+                } else if cstick_up {
+                    c.mod_y_diagonal_cstick_up
+                } else if cstick_left {
+                    c.mod_y_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_y_diagonal_cstick_down
+                } else {
+                    c.mod_y_diagonal
+                }
+            } else if stick.horizontal() {
+                c.mod_y_horizontal
+            } else {
+                c.mod_y_vertical
+            }
+        } else if stick.diagonal() {
+            c.diagonal
+        } else {
+            c.max
+        };
+        stick.offset(coordinate)
+    }
+
+    fn cstick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        if input.buttons.mod_x && input.cstick.horizontal() {
+            // Allow for angled tilts and smash attacks
+            let angled = self.coordinates.cstick_mod_x_horizontal;
+            (
+                input.cstick.x * angled.x as i16,
+                input.stick.y * angled.y as i16,
+            )
+        } else {
+            input.cstick.offset(self.coordinates.cstick_max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::tests::{Case, assert_cstick, assert_stick, map};
+    use rukaibox_config::{BaseLogic, LogicalButton::*};
+
+    #[test]
+    fn stick_coordinates() {
+        // Each row lists the held buttons followed by the expected stick value for each direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[CstickRight], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[RDigital], [(208, 128), (184, 184), (128, 208), (72, 184), (48, 128), (72, 72), (128, 48), (184, 72)]),
+            (&[ModX], [(194, 128), (181, 163), (128, 172), (75, 163), (62, 128), (75, 93), (128, 84), (181, 93)]),
+            (&[ModX, CstickRight], [(194, 128), (177, 154), (128, 172), (79, 154), (62, 128), (79, 102), (128, 84), (177, 102)]),
+            (&[ModX, CstickLeft], [(194, 128), (181, 167), (128, 172), (75, 167), (62, 128), (75, 89), (128, 84), (181, 89)]),
+            (&[ModX, CstickUp], [(194, 128), (184, 161), (128, 172), (72, 161), (62, 128), (72, 95), (128, 84), (184, 95)]),
+            (&[ModX, CstickDown], [(194, 128), (181, 171), (128, 172), (75, 171), (62, 128), (75, 85), (128, 84), (181, 85)]),
+            (&[ModX, RDigital], [(194, 128), (179, 158), (128, 172), (77, 158), (62, 128), (77, 98), (128, 84), (179, 98)]),
+            (&[ModY], [(172, 128), (163, 181), (128, 195), (93, 181), (84, 128), (93, 75), (128, 61), (163, 75)]),
+            (&[ModY, CstickRight], [(172, 128), (154, 177), (128, 195), (102, 177), (84, 128), (102, 79), (128, 61), (154, 79)]),
+            (&[ModY, CstickLeft], [(172, 128), (167, 181), (128, 195), (89, 181), (84, 128), (89, 75), (128, 61), (167, 75)]),
+            (&[ModY, CstickUp], [(172, 128), (161, 184), (128, 195), (95, 184), (84, 128), (95, 72), (128, 61), (161, 72)]),
+            (&[ModY, CstickDown], [(172, 128), (171, 181), (128, 195), (85, 181), (84, 128), (85, 75), (128, 61), (171, 75)]),
+            (&[ModY, RDigital], [(172, 128), (166, 198), (128, 195), (90, 198), (84, 128), (90, 58), (128, 61), (166, 58)]),
+        ];
+        for (held, expected) in cases {
+            assert_stick(BaseLogic::Rivals1, held, *expected);
+        }
+    }
+
+    #[test]
+    fn cstick_coordinates() {
+        // Each row lists the held buttons followed by the expected C-stick value for each C-stick direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(208, 128), (208, 208), (128, 208), (48, 208), (48, 128), (48, 48), (128, 48), (208, 48)]),
+            (&[ModX], [(208, 128), (208, 128), (128, 208), (48, 128), (48, 128), (48, 128), (128, 48), (208, 128)]),
+            (&[ModY], [(208, 128), (208, 208), (128, 208), (48, 208), (48, 128), (48, 48), (128, 48), (208, 48)]),
+            (&[ModX, StickUp], [(208, 187), (208, 187), (128, 208), (48, 187), (48, 187), (48, 187), (128, 48), (208, 187)]),
+            (&[ModX, StickDown], [(208, 69), (208, 69), (128, 208), (48, 69), (48, 69), (48, 69), (128, 48), (208, 69)]),
+        ];
+        for (held, expected) in cases {
+            assert_cstick(BaseLogic::Rivals1, held, *expected);
+        }
+    }
+
+    #[test]
+    fn neutral() {
+        let result = map(BaseLogic::Rivals1, &[]);
+        assert_eq!(result, crate::GamecubeInput::NEUTRAL);
+    }
+
+    #[test]
+    fn triggers() {
+        let result = map(BaseLogic::Rivals1, &[&[LDigital]]);
+        assert_eq!((result.l_digital, result.l_analog), (true, 0));
+        let result = map(BaseLogic::Rivals1, &[&[RAnalog]]);
+        assert_eq!((result.r_digital, result.r_analog), (false, 49));
+    }
+}
@@ -0,0 +1,231 @@
+use super::{ProfileInput, ProfileLogic};
+use crate::input::ButtonInputLogical;
+use rukaibox_config::{Coordinates, Profile, Rivals2Coordinates};
+
+pub struct Rivals2Logic {
+    coordinates: Rivals2Coordinates,
+}
+
+impl ProfileLogic for Rivals2Logic {
+    fn new(profile: &Profile) -> Self {
+        let coordinates = match &profile.coordinates {
+            Coordinates::Rivals2(coordinates) => coordinates.clone(),
+            _ => Default::default(),
+        };
+        Rivals2Logic { coordinates }
+    }
+
+    fn stick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let ButtonInputLogical {
+            mod_x,
+            mod_y,
+            a,
+            b,
+            z,
+            l_digital,
+            r_digital,
+            ..
+        } = input.buttons;
+        let stick = input.stick;
+        let diagonal = stick.diagonal();
+        let shield = l_digital || r_digital;
+        let c = &self.coordinates;
+
+        // cstick modifiers on up b left out since they seem zetterburn specific, they can be added with an overlay in config.kdl instead.
+        let coordinate = if mod_x {
+            if diagonal && !shield {
+                if a {
+                    // angled tilts
+                    c.mod_x_diagonal_a
+                } else if z {
+                    // shortest up B
+                    // (x, y), (53, 68), (~0.31, ~0.188) [coords, code_values, in-game values]
+                    c.mod_x_diagonal_z
+                } else if b {
+                    // 100% up B (just hold B)
+                    // (x, y), (123, 51), (1.14~, 0.29~) [coords, code_values, in-game values]
+                    c.mod_x_diagonal_b
+                } else {
+                    // 60% up B (just release B)
+                    // (x, y), (68, 42), (~0.49, ~0.188) [coords, code_values, in-game values]
+                    c.mod_x_diagonal
+                }
+            } else if diagonal && shield {
+                //for max-length diagonal wavedash while holding ModX
+                c.mod_x_diagonal_shield
+            } else if stick.vertical() {
+                // 48 (0.31~ in-game), 0.3 allows tilts and shield drop
+                c.mod_x_vertical
+            } else {
+                //76 gives 0.58~ in-game for a medium speed walk. will also do tilts
+                c.mod_x_horizontal
+            }
+        } else if mod_y {
+            if diagonal && !shield {
+                if z {
+                    // shortest up B
+                    // (x, y), (42, 53), (~0.188, ~0.31) [coords, code_values, in-game values]
+                    c.mod_y_diagonal_z
+                } else if b {
+                    // 100% up B (just hold B)
+                    // (x, y), (51, 123), (~0.29, ~1.14) [coords, code_values, in-game values]
+                    c.mod_y_diagonal_b
+                } else {
+                    // 60% up B (just release B)
+                    // (x, y), (42, 68), (~0.188, ~0.49) [coords, code_values, in-game values]
+                    c.mod_y_diagonal
+                }
+            } else if stick.vertical() {
+                // 0.75~ in-game. will shield drop and tap jump; will not fast fall
+                c.mod_y_vertical
+            } else {
+                //53 equates to 0.318~ in-game. 0.3 is min to achieve a walk
+                c.mod_y_horizontal
+            }
+        } else if diagonal && shield {
+            // (0.77~, 0.77~) to prevent spot dodging when pressing diagonal on the ground
+            c.diagonal_shield
+        } else if diagonal && !shield {
+            //added this conditional to give joystick accurate diagonals rather than (+/- 1.2, 1.2) should be (0.87~, 0.87~)
+            // x: (0.78 in-game), reduced below 0.8 to allow crouch tilts/crouch turn-around tilts
+            // y: 0.83 in-game >0.8 allows fast fall
+            c.diagonal
+        } else {
+            // when only stick inputs, set to maximum
+            c.max
+        };
+        stick.offset(coordinate)
+    }
+
+    fn cstick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        if input.buttons.mod_x && input.cstick.horizontal() {
+            // Allow for angled smash attacks
+            let angled = self.coordinates.cstick_mod_x_horizontal;
+            (
+                input.cstick.x * angled.x as i16,
+                input.stick.y * angled.y as i16,
+            )
+        } else {
+            input.cstick.offset(self.coordinates.cstick_max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::tests::{Case, assert_cstick, assert_stick, map};
+    use rukaibox_config::{BaseLogic, LogicalButton, LogicalButton::*};
+
+    #[test]
+    fn stick_coordinates() {
+        // Each row lists the held buttons followed by the expected stick value for each direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[CstickRight], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[CstickLeft], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[CstickUp], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[CstickDown], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[RDigital], [(255, 128), (220, 220), (128, 255), (36, 220), (1, 128), (36, 36), (128, 1), (220, 36)]),
+            (&[A], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[B], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[Z], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[B, CstickRight], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[B, CstickUp], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[B, CstickLeft], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[B, CstickDown], [(255, 128), (220, 224), (128, 255), (36, 224), (1, 128), (36, 32), (128, 1), (220, 32)]),
+            (&[ModX], [(204, 128), (196, 170), (128, 181), (60, 170), (52, 128), (60, 86), (128, 75), (196, 86)]),
+            (&[ModX, CstickRight], [(204, 128), (196, 170), (128, 181), (60, 170), (52, 128), (60, 86), (128, 75), (196, 86)]),
+            (&[ModX, CstickLeft], [(204, 128), (196, 170), (128, 181), (60, 170), (52, 128), (60, 86), (128, 75), (196, 86)]),
+            (&[ModX, CstickUp], [(204, 128), (196, 170), (128, 181), (60, 170), (52, 128), (60, 86), (128, 75), (196, 86)]),
+            (&[ModX, CstickDown], [(204, 128), (196, 170), (128, 181), (60, 170), (52, 128), (60, 86), (128, 75), (196, 86)]),
+            (&[ModX, RDigital], [(204, 128), (204, 170), (128, 181), (52, 170), (52, 128), (52, 86), (128, 75), (204, 86)]),
+            (&[ModX, A], [(204, 128), (197, 181), (128, 181), (59, 181), (52, 128), (59, 75), (128, 75), (197, 75)]),
+            (&[ModX, B], [(204, 128), (251, 179), (128, 181), (5, 179), (52, 128), (5, 77), (128, 75), (251, 77)]),
+            (&[ModX, Z], [(204, 128), (181, 170), (128, 181), (75, 170), (52, 128), (75, 86), (128, 75), (181, 86)]),
+            (&[ModX, B, CstickRight], [(204, 128), (251, 179), (128, 181), (5, 179), (52, 128), (5, 77), (128, 75), (251, 77)]),
+            (&[ModX, B, CstickUp], [(204, 128), (251, 179), (128, 181), (5, 179), (52, 128), (5, 77), (128, 75), (251, 77)]),
+            (&[ModX, B, CstickLeft], [(204, 128), (251, 179), (128, 181), (5, 179), (52, 128), (5, 77), (128, 75), (251, 77)]),
+            (&[ModX, B, CstickDown], [(204, 128), (251, 179), (128, 181), (5, 179), (52, 128), (5, 77), (128, 75), (251, 77)]),
+            (&[ModY], [(181, 128), (170, 196), (128, 218), (86, 196), (75, 128), (86, 60), (128, 38), (170, 60)]),
+            (&[ModY, CstickRight], [(181, 128), (170, 196), (128, 218), (86, 196), (75, 128), (86, 60), (128, 38), (170, 60)]),
+            (&[ModY, CstickLeft], [(181, 128), (170, 196), (128, 218), (86, 196), (75, 128), (86, 60), (128, 38), (170, 60)]),
+            (&[ModY, CstickUp], [(181, 128), (170, 196), (128, 218), (86, 196), (75, 128), (86, 60), (128, 38), (170, 60)]),
+            (&[ModY, CstickDown], [(181, 128), (170, 196), (128, 218), (86, 196), (75, 128), (86, 60), (128, 38), (170, 60)]),
+            (&[ModY, RDigital], [(181, 128), (128, 218), (128, 218), (128, 218), (75, 128), (128, 38), (128, 38), (128, 38)]),
+            (&[ModY, A], [(181, 128), (170, 196), (128, 218), (86, 196), (75, 128), (86, 60), (128, 38), (170, 60)]),
+            (&[ModY, B], [(181, 128), (179, 251), (128, 218), (77, 251), (75, 128), (77, 5), (128, 38), (179, 5)]),
+            (&[ModY, Z], [(181, 128), (170, 181), (128, 218), (86, 181), (75, 128), (86, 75), (128, 38), (170, 75)]),
+            (&[ModY, B, CstickRight], [(181, 128), (179, 251), (128, 218), (77, 251), (75, 128), (77, 5), (128, 38), (179, 5)]),
+            (&[ModY, B, CstickUp], [(181, 128), (179, 251), (128, 218), (77, 251), (75, 128), (77, 5), (128, 38), (179, 5)]),
+            (&[ModY, B, CstickLeft], [(181, 128), (179, 251), (128, 218), (77, 251), (75, 128), (77, 5), (128, 38), (179, 5)]),
+            (&[ModY, B, CstickDown], [(181, 128), (179, 251), (128, 218), (77, 251), (75, 128), (77, 5), (128, 38), (179, 5)]),
+        ];
+        for (held, expected) in cases {
+            assert_stick(BaseLogic::Rivals2, held, *expected);
+        }
+    }
+
+    #[test]
+    fn cstick_coordinates() {
+        // Each row lists the held buttons followed by the expected C-stick value for each C-stick direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(255, 128), (255, 255), (128, 255), (1, 255), (1, 128), (1, 1), (128, 1), (255, 1)]),
+            (&[ModX], [(193, 128), (193, 128), (128, 255), (63, 128), (63, 128), (63, 128), (128, 1), (193, 128)]),
+            (&[ModY], [(255, 128), (255, 255), (128, 255), (1, 255), (1, 128), (1, 1), (128, 1), (255, 1)]),
+            (&[ModX, StickUp], [(193, 151), (193, 151), (128, 255), (63, 151), (63, 151), (63, 151), (128, 1), (193, 151)]),
+            (&[ModX, StickDown], [(193, 105), (193, 105), (128, 255), (63, 105), (63, 105), (63, 105), (128, 1), (193, 105)]),
+        ];
+        for (held, expected) in cases {
+            assert_cstick(BaseLogic::Rivals2, held, *expected);
+        }
+    }
+
+    #[test]
+    fn neutral() {
+        let result = map(BaseLogic::Rivals2, &[]);
+        assert_eq!(result, crate::GamecubeInput::NEUTRAL);
+    }
+
+    #[test]
+    fn triggers() {
+        let result = map(BaseLogic::Rivals2, &[&[LDigital]]);
+        assert_eq!((result.l_digital, result.l_analog), (true, 0));
+        let result = map(BaseLogic::Rivals2, &[&[RDigital]]);
+        assert_eq!((result.r_digital, result.r_analog), (true, 0));
+        let result = map(BaseLogic::Rivals2, &[&[LAnalog]]);
+        assert_eq!((result.l_digital, result.l_analog), (false, 49));
+        let result = map(BaseLogic::Rivals2, &[&[RAnalog]]);
+        assert_eq!((result.r_digital, result.r_analog), (false, 49));
+    }
+
+    #[test]
+    fn dpad_layer() {
+        // mod-x + mod-y turns the C-stick into a dpad
+        let cases: [(LogicalButton, [bool; 4]); 4] = [
+            (CstickUp, [true, false, false, false]),
+            (CstickDown, [false, true, false, false]),
+            (CstickLeft, [false, false, true, false]),
+            (CstickRight, [false, false, false, true]),
+        ];
+        for (cstick, expected) in cases {
+            let result = map(BaseLogic::Rivals2, &[&[ModX, ModY, cstick]]);
+            let dpad = [
+                result.dpad_up,
+                result.dpad_down,
+                result.dpad_left,
+                result.dpad_right,
+            ];
+            assert_eq!(dpad, expected, "dpad for {cstick:?}");
+            assert_eq!((result.cstick_x, result.cstick_y), (128, 128));
+        }
+
+        // Dpad buttons also disable the C-stick
+        let result = map(BaseLogic::Rivals2, &[&[DpadUp, CstickUp]]);
+        assert!(result.dpad_up);
+        assert_eq!((result.cstick_x, result.cstick_y), (128, 128));
+    }
+}
@@ -0,0 +1,322 @@
+use super::{ProfileInput, ProfileLogic};
+use crate::input::ButtonInputLogical;
+use rukaibox_config::{Coordinates, CstickMode, Profile, UltimateCoordinates};
+
+pub struct UltimateLogic {
+    coordinates: UltimateCoordinates,
+    cstick_mode: CstickMode,
+}
+
+impl ProfileLogic for UltimateLogic {
+    const MODIFIERS_SUPPRESS_START: bool = true;
+
+    fn new(profile: &Profile) -> Self {
+        let coordinates = match &profile.coordinates {
+            Coordinates::Ultimate(coordinates) => coordinates.clone(),
+            _ => Default::default(),
+        };
+        UltimateLogic {
+            coordinates,
+            cstick_mode: profile.cstick_mode,
+        }
+    }
+
+    fn stick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let ButtonInputLogical {
+            mod_x,
+            mod_y,
+            a,
+            b,
+            l_digital,
+            r_digital,
+            stick_left,
+            cstick_left,
+            cstick_right,
+            cstick_up,
+            cstick_down,
+            ..
+        } = input.buttons;
+        let stick = input.stick;
+        let shield = l_digital || r_digital;
+        let c = &self.coordinates;
+
+        let coordinate = if mod_x {
+            if stick.diagonal() {
+                if b {
+                    if cstick_right {
+                        c.mod_x_diagonal_b_cstick_right
+                    } else if cstick_up {
+                        c.mod_x_diagonal_b_cstick_up
+                    } else if cstick_left {
+                        c.mod_x_diagonal_b_cstick_left
+                    } else if cstick_down {
+                        c.mod_x_diagonal_b_cstick_down
+                    } else {
+                        c.mod_x_diagonal_b
+                    }
+                } else if cstick_right {
+                    c.mod_x_diagonal_cstick_right
+                } else if cstick_up {
+                    c.mod_x_diagonal_cstick_up
+                } else if cstick_left {
+                    c.mod_x_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_x_diagonal_cstick_down
+                } else if shield {
+                    c.mod_x_diagonal_shield
+                } else if a {
+                    c.mod_x_diagonal_a
+                } else {
+                    c.mod_x_diagonal
+                }
+            } else if stick.horizontal() {
+                if b {
+                    c.mod_x_horizontal_b
+                } else if shield {
+                    c.mod_x_horizontal_shield
+                } else {
+                    c.mod_x_horizontal
+                }
+            } else if shield {
+                c.mod_x_vertical_shield
+            } else {
+                c.mod_x_vertical
+            }
+        } else if mod_y {
+            if stick.diagonal() {
+                if shield {
+                    if stick_left {
+                        c.mod_y_diagonal_shield_left
+                    } else {
+                        c.mod_y_diagonal_shield
+                    }
+                } else if a {
+                    c.mod_y_diagonal_a
+                } else if b {
+                    if cstick_right {
+                        c.mod_y_diagonal_b_cstick_right
+                    } else if cstick_up {
+                        c.mod_y_diagonal_b_cstick_up
+                    } else if cstick_left {
+                        c.mod_y_diagonal_b_cstick_left
+                    } else if cstick_down {
+                        c.mod_y_diagonal_b_cstick_down
+                    } else {
+                        c.mod_y_diagonal_b
+                    }
+                } else if cstick_right {
+                    c.mod_y_diagonal_cstick_right
+                } else if cstick_up {
+                    c.mod_y_diagonal_cstick_up
+                } else if cstick_left {
+                    c.mod_y_diagonal_cstick_left
+                } else if cstick_down {
+                    c.mod_y_diagonal_cstick_down
+                } else {
+                    c.mod_y_diagonal
+                }
+            } else if stick.horizontal() {
+                c.mod_y_horizontal
+            } else if a {
+                c.mod_y_vertical_a
+            } else {
+                c.mod_y_vertical
+            }
+        } else {
+            c.max
+        };
+        stick.offset(coordinate)
+    }
+
+    fn cstick(&mut self, input: &ProfileInput) -> (i16, i16) {
+        let cstick = input.cstick;
+        let c = &self.coordinates;
+
+        // ASDI slideoff overrides angled fsmash, so check it first.
+        if cstick.diagonal() {
+            cstick.offset(c.cstick_diagonal)
+        } else if input.buttons.mod_x && cstick.horizontal() {
+            let angled = c.cstick_mod_x_horizontal;
+            (cstick.x * angled.x as i16, input.stick.y * angled.y as i16)
+        } else {
+            let smash = match self.cstick_mode {
+                CstickMode::Smash => true,
+                CstickMode::TiltUnlessModX => input.buttons.mod_x,
+                CstickMode::TiltUnlessModY => input.buttons.mod_y,
+            };
+            if smash {
+                cstick.offset(c.cstick_max)
+            } else {
+                cstick.offset(c.cstick_tilt)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::profile::MapProfile;
+    use crate::profile::tests::{
+        CSTICK_DIRECTIONS, Case, Simulation, assert_cstick, assert_stick, buttons, map,
+    };
+    use rukaibox_config::{
+        BaseLogic, Coordinates, CstickMode, LogicalButton, LogicalButton::*, Profile, TriggerLevels,
+    };
+
+    #[test]
+    fn stick_coordinates() {
+        // Each row lists the held buttons followed by the expected stick value for each direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickRight], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickLeft], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickUp], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[CstickDown], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[RDigital], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[A], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[Z], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickRight], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickUp], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickLeft], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[B, CstickDown], [(228, 128), (228, 228), (128, 228), (28, 228), (28, 128), (28, 28), (128, 28), (228, 28)]),
+            (&[ModX], [(181, 128), (181, 163), (128, 188), (75, 163), (75, 128), (75, 93), (128, 68), (181, 93)]),
+            (&[ModX, CstickRight], [(181, 128), (181, 156), (128, 188), (75, 156), (75, 128), (75, 100), (128, 68), (181, 100)]),
+            (&[ModX, CstickLeft], [(181, 128), (181, 167), (128, 188), (75, 167), (75, 128), (75, 89), (128, 68), (181, 89)]),
+            (&[ModX, CstickUp], [(181, 128), (181, 159), (128, 188), (75, 159), (75, 128), (75, 97), (128, 68), (181, 97)]),
+            (&[ModX, CstickDown], [(181, 128), (181, 171), (128, 188), (75, 171), (75, 128), (75, 85), (128, 68), (181, 85)]),
+            (&[ModX, RDigital], [(179, 128), (179, 158), (128, 179), (77, 158), (77, 128), (77, 98), (128, 77), (179, 98)]),
+            (&[ModX, A], [(181, 128), (198, 162), (128, 188), (58, 162), (75, 128), (58, 94), (128, 68), (198, 94)]),
+            (&[ModX, B], [(194, 128), (195, 172), (128, 188), (61, 172), (62, 128), (61, 84), (128, 68), (195, 84)]),
+            (&[ModX, Z], [(181, 128), (181, 163), (128, 188), (75, 163), (75, 128), (75, 93), (128, 68), (181, 93)]),
+            (&[ModX, B, CstickRight], [(194, 128), (195, 163), (128, 188), (61, 163), (62, 128), (61, 93), (128, 68), (195, 93)]),
+            (&[ModX, B, CstickUp], [(194, 128), (195, 167), (128, 188), (61, 167), (62, 128), (61, 89), (128, 68), (195, 89)]),
+            (&[ModX, B, CstickLeft], [(194, 128), (195, 177), (128, 188), (61, 177), (62, 128), (61, 79), (128, 68), (195, 79)]),
+            (&[ModX, B, CstickDown], [(194, 128), (195, 183), (128, 188), (61, 183), (62, 128), (61, 73), (128, 68), (195, 73)]),
+            (&[ModY], [(163, 128), (163, 181), (128, 181), (93, 181), (93, 128), (93, 75), (128, 75), (163, 75)]),
+            (&[ModY, CstickRight], [(163, 128), (156, 181), (128, 181), (100, 181), (93, 128), (100, 75), (128, 75), (156, 75)]),
+            (&[ModY, CstickLeft], [(163, 128), (177, 181), (128, 181), (79, 181), (93, 128), (79, 75), (128, 75), (177, 75)]),
+            (&[ModY, CstickUp], [(163, 128), (159, 181), (128, 181), (97, 181), (93, 128), (97, 75), (128, 75), (159, 75)]),
+            (&[ModY, CstickDown], [(163, 128), (171, 181), (128, 181), (85, 181), (93, 128), (85, 75), (128, 75), (171, 75)]),
+            (&[ModY, RDigital], [(163, 128), (166, 198), (128, 181), (88, 196), (93, 128), (88, 60), (128, 75), (166, 58)]),
+            (&[ModY, A], [(163, 128), (166, 197), (128, 164), (90, 197), (93, 128), (90, 59), (128, 92), (166, 59)]),
+            (&[ModY, B], [(163, 128), (172, 195), (128, 181), (84, 195), (93, 128), (84, 61), (128, 75), (172, 61)]),
+            (&[ModY, Z], [(163, 128), (163, 181), (128, 181), (93, 181), (93, 128), (93, 75), (128, 75), (163, 75)]),
+            (&[ModY, B, CstickRight], [(163, 128), (163, 195), (128, 181), (93, 195), (93, 128), (93, 61), (128, 75), (163, 61)]),
+            (&[ModY, B, CstickUp], [(163, 128), (167, 195), (128, 181), (89, 195), (93, 128), (89, 61), (128, 75), (167, 61)]),
+            (&[ModY, B, CstickLeft], [(163, 128), (177, 195), (128, 181), (79, 195), (93, 128), (79, 61), (128, 75), (177, 61)]),
+            (&[ModY, B, CstickDown], [(163, 128), (183, 195), (128, 181), (73, 195), (93, 128), (73, 61), (128, 75), (183, 61)]),
+        ];
+        for (held, expected) in cases {
+            assert_stick(BaseLogic::Ultimate, held, *expected);
+        }
+    }
+
+    #[test]
+    fn cstick_coordinates() {
+        // Each row lists the held buttons followed by the expected C-stick value for each C-stick direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[Case] = &[
+            (&[], [(228, 128), (170, 196), (128, 228), (86, 196), (28, 128), (86, 60), (128, 28), (170, 60)]),
+            (&[ModX], [(193, 128), (170, 196), (128, 228), (86, 196), (63, 128), (86, 60), (128, 28), (170, 60)]),
+            (&[ModY], [(228, 128), (170, 196), (128, 228), (86, 196), (28, 128), (86, 60), (128, 28), (170, 60)]),
+            (&[ModX, StickUp], [(193, 172), (170, 196), (128, 228), (86, 196), (63, 172), (86, 60), (128, 28), (170, 60)]),
+            (&[ModX, StickDown], [(193, 84), (170, 196), (128, 228), (86, 196), (63, 84), (86, 60), (128, 28), (170, 60)]),
+        ];
+        for (held, expected) in cases {
+            assert_cstick(BaseLogic::Ultimate, held, *expected);
+        }
+    }
+
+    #[test]
+    fn neutral() {
+        let result = map(BaseLogic::Ultimate, &[]);
+        assert_eq!(result, crate::GamecubeInput::NEUTRAL);
+    }
+
+    #[test]
+    fn triggers() {
+        // Ultimate ignores digital triggers, so they also fully press the analog trigger
+        let result = map(BaseLogic::Ultimate, &[&[LDigital]]);
+        assert_eq!((result.l_digital, result.l_analog), (true, 140));
+        let result = map(BaseLogic::Ultimate, &[&[RDigital]]);
+        assert_eq!((result.r_digital, result.r_analog), (true, 140));
+        let result = map(BaseLogic::Ultimate, &[&[LAnalog]]);
+        assert_eq!((result.l_digital, result.l_analog), (false, 140));
+        let result = map(BaseLogic::Ultimate, &[&[RAnalog]]);
+        assert_eq!((result.r_digital, result.r_analog), (false, 140));
+    }
+
+    #[test]
+    fn start_suppressed_by_modifiers() {
+        assert!(map(BaseLogic::Ultimate, &[&[Start]]).start);
+        assert!(!map(BaseLogic::Ultimate, &[&[Start, ModX]]).start);
+        assert!(!map(BaseLogic::Ultimate, &[&[Start, ModY]]).start);
+    }
+
+    #[test]
+    fn dpad_layer() {
+        // mod-x + mod-y turns the C-stick into a dpad
+        let cases: [(LogicalButton, [bool; 4]); 4] = [
+            (CstickUp, [true, false, false, false]),
+            (CstickDown, [false, true, false, false]),
+            (CstickLeft, [false, false, true, false]),
+            (CstickRight, [false, false, false, true]),
+        ];
+        for (cstick, expected) in cases {
+            let result = map(BaseLogic::Ultimate, &[&[ModX, ModY, cstick]]);
+            let dpad = [
+                result.dpad_up,
+                result.dpad_down,
+                result.dpad_left,
+                result.dpad_right,
+            ];
+            assert_eq!(dpad, expected, "dpad for {cstick:?}");
+            assert_eq!((result.cstick_x, result.cstick_y), (128, 128));
+        }
+
+        // Dpad buttons also disable the C-stick
+        let result = map(BaseLogic::Ultimate, &[&[DpadUp, CstickUp]]);
+        assert!(result.dpad_up);
+        assert_eq!((result.cstick_x, result.cstick_y), (128, 128));
+    }
+
+    #[test]
+    fn cstick_tilt_mode() {
+        // Each row lists the C-stick mode and held buttons followed by the expected C-stick value for each C-stick direction:
+        // right, up-right, up, up-left, left, down-left, down, down-right
+        #[rustfmt::skip]
+        let cases: &[(CstickMode, Case)] = &[
+            (CstickMode::Smash, (&[], [(228, 128), (170, 196), (128, 228), (86, 196), (28, 128), (86, 60), (128, 28), (170, 60)])),
+            (CstickMode::TiltUnlessModX, (&[], [(181, 128), (170, 196), (128, 181), (86, 196), (75, 128), (86, 60), (128, 75), (170, 60)])),
+            (CstickMode::TiltUnlessModX, (&[ModX], [(193, 128), (170, 196), (128, 228), (86, 196), (63, 128), (86, 60), (128, 28), (170, 60)])),
+            (CstickMode::TiltUnlessModX, (&[ModY], [(181, 128), (170, 196), (128, 181), (86, 196), (75, 128), (86, 60), (128, 75), (170, 60)])),
+            (CstickMode::TiltUnlessModY, (&[], [(181, 128), (170, 196), (128, 181), (86, 196), (75, 128), (86, 60), (128, 75), (170, 60)])),
+            (CstickMode::TiltUnlessModY, (&[ModY], [(228, 128), (170, 196), (128, 228), (86, 196), (28, 128), (86, 60), (128, 28), (170, 60)])),
+            // Angled smashes are unaffected by the mode
+            (CstickMode::TiltUnlessModY, (&[ModX], [(193, 128), (170, 196), (128, 181), (86, 196), (63, 128), (86, 60), (128, 75), (170, 60)])),
+        ];
+        for (cstick_mode, (held, expected)) in cases {
+            for (direction, expected) in CSTICK_DIRECTIONS.iter().zip(expected) {
+                let mut simulation = Simulation::from_profile(MapProfile::new(&Profile {
+                    logic: BaseLogic::Ultimate,
+                    buttons: buttons(),
+                    coordinates: Coordinates::new(BaseLogic::Ultimate),
+                    triggers: TriggerLevels::new(BaseLogic::Ultimate),
+                    cstick_mode: *cstick_mode,
+                    ..Default::default()
+                }));
+                let held: Vec<LogicalButton> = held.iter().chain(*direction).copied().collect();
+                let result = simulation.poll(&held);
+                assert_eq!(
+                    (result.cstick_x, result.cstick_y),
+                    *expected,
+                    "C-stick for {cstick_mode:?} {held:?}"
+                );
+            }
+        }
+    }
+}
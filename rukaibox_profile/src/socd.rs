@@ -0,0 +1,394 @@
+use rukaibox_config::SocdType;
+
+/// Simultaneous Opposing Cardinal Directions
+/// https://www.hitboxarcade.com/blogs/support/what-is-socd
+///
+/// The state of every axis that SOCD is resolved for.
+#[derive(Default)]
+pub struct SocdState {
+    pub stick_horizontal: AxisState,
+    pub stick_vertical: AxisState,
+    pub cstick_horizontal: AxisState,
+    pub cstick_vertical: AxisState,
+    pub dpad_horizontal: AxisState,
+    pub dpad_vertical: AxisState,
+}
+
+/// Tracks the two opposing directions of an axis across polls.
+#[derive(Default)]
+pub struct AxisState {
+    /// The directions held the last time both directions were not held.
+    prev_input1: bool,
+    prev_input2: bool,
+    /// Set while a direction is held after being overridden by the opposite direction.
+    overridden_input1: bool,
+    overridden_input2: bool,
+}
+
+impl AxisState {
+    /// Returns which of the held directions are active, `input1` is up or left and `input2` is down or right.
+    pub fn resolve(&mut self, socd: SocdType, input1: bool, input2: bool) -> (bool, bool) {
+        self.overridden_input1 &= input1;
+        self.overridden_input2 &= input2;
+
+        let resolved = if input1 && input2 {
+            match socd {
+                // Whichever was held alone before is the first input, ties go to input1
+                SocdType::SecondInputPriority | SocdType::SecondInputPriorityNoReactivation => {
+                    if self.prev_input1 {
+                        (false, true)
+                    } else {
+                        (true, false)
+                    }
+                }
+                SocdType::FirstInputPriority => {
+                    if self.prev_input2 {
+                        (false, true)
+                    } else {
+                        (true, false)
+                    }
+                }
+                SocdType::Neutral => (false, false),
+                SocdType::UpOrLeftPriority => (true, false),
+                SocdType::DownOrRightPriority => (false, true),
+            }
+        } else {
+            self.prev_input1 = input1;
+            self.prev_input2 = input2;
+            (input1, input2)
+        };
+
+        self.overridden_input1 |= input1 && !resolved.0;
+        self.overridden_input2 |= input2 && !resolved.1;
+        if socd == SocdType::SecondInputPriorityNoReactivation {
+            // Releasing the second input does not give control back to the first input until it is pressed again.
+            (
+                resolved.0 && !self.overridden_input1,
+                resolved.1 && !self.overridden_input2,
+            )
+        } else {
+            resolved
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AxisState;
+    use rukaibox_config::SocdType;
+
+    const ALL: [SocdType; 6] = [
+        SocdType::SecondInputPriority,
+        SocdType::SecondInputPriorityNoReactivation,
+        SocdType::FirstInputPriority,
+        SocdType::Neutral,
+        SocdType::UpOrLeftPriority,
+        SocdType::DownOrRightPriority,
+    ];
+
+    /// Resolves each `(input1, input2)` pair in order, returning the resolved outputs.
+    fn resolve_sequence(socd: SocdType, inputs: &[(bool, bool)]) -> Vec<(bool, bool)> {
+        let mut state = AxisState::default();
+        inputs
+            .iter()
+            .map(|(input1, input2)| state.resolve(socd, *input1, *input2))
+            .collect()
+    }
+
+    /// Input1 is held, input2 is pressed and released, then input2 is pressed again and input1 is released.
+    const HOLD_1_TAP_2: [(bool, bool); 6] = [
+        (true, false),
+        (true, true),
+        (true, false),
+        (true, true),
+        (false, true),
+        (false, false),
+    ];
+
+    /// The same as [`HOLD_1_TAP_2`] with the directions swapped.
+    const HOLD_2_TAP_1: [(bool, bool); 6] = [
+        (false, true),
+        (true, true),
+        (false, true),
+        (true, true),
+        (true, false),
+        (false, false),
+    ];
+
+    /// Both directions are pressed on the same poll, then each is released in turn.
+    const SAME_POLL: [(bool, bool); 5] = [
+        (true, true),
+        (false, true),
+        (true, true),
+        (true, false),
+        (false, false),
+    ];
+
+    #[test]
+    fn second_input_priority() {
+        let socd = SocdType::SecondInputPriority;
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_1_TAP_2),
+            [
+                (true, false),
+                (false, true),
+                (true, false),
+                (false, true),
+                (false, true),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_2_TAP_1),
+            [
+                (false, true),
+                (true, false),
+                (false, true),
+                (true, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+        // Pressed on the same poll, input1 wins the tie
+        assert_eq!(
+            resolve_sequence(socd, &SAME_POLL),
+            [
+                (true, false),
+                (false, true),
+                (true, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn second_input_priority_no_reactivation() {
+        let socd = SocdType::SecondInputPriorityNoReactivation;
+        // Input1 stays inactive once overridden, until it is released
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_1_TAP_2),
+            [
+                (true, false),
+                (false, true),
+                (false, false),
+                (false, true),
+                (false, true),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_2_TAP_1),
+            [
+                (false, true),
+                (true, false),
+                (false, false),
+                (true, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+        // Input2 lost the tie so it is not active after input1 is released
+        assert_eq!(
+            resolve_sequence(socd, &SAME_POLL),
+            [
+                (true, false),
+                (false, false),
+                (true, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+        // Pressing the overridden direction again activates it
+        assert_eq!(
+            resolve_sequence(
+                socd,
+                &[(true, false), (true, true), (false, true), (true, true)]
+            ),
+            [(true, false), (false, true), (false, true), (true, false)]
+        );
+    }
+
+    #[test]
+    fn first_input_priority() {
+        let socd = SocdType::FirstInputPriority;
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_1_TAP_2),
+            [
+                (true, false),
+                (true, false),
+                (true, false),
+                (true, false),
+                (false, true),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_2_TAP_1),
+            [
+                (false, true),
+                (false, true),
+                (false, true),
+                (false, true),
+                (true, false),
+                (false, false)
+            ]
+        );
+        // Input2 is held alone before input1 is pressed again, so it is the first input
+        assert_eq!(
+            resolve_sequence(socd, &SAME_POLL),
+            [
+                (true, false),
+                (false, true),
+                (false, true),
+                (true, false),
+                (false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn neutral() {
+        let socd = SocdType::Neutral;
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_1_TAP_2),
+            [
+                (true, false),
+                (false, false),
+                (true, false),
+                (false, false),
+                (false, true),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_2_TAP_1),
+            [
+                (false, true),
+                (false, false),
+                (false, true),
+                (false, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &SAME_POLL),
+            [
+                (false, false),
+                (false, true),
+                (false, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn up_or_left_priority() {
+        let socd = SocdType::UpOrLeftPriority;
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_1_TAP_2),
+            [
+                (true, false),
+                (true, false),
+                (true, false),
+                (true, false),
+                (false, true),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_2_TAP_1),
+            [
+                (false, true),
+                (true, false),
+                (false, true),
+                (true, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &SAME_POLL),
+            [
+                (true, false),
+                (false, true),
+                (true, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn down_or_right_priority() {
+        let socd = SocdType::DownOrRightPriority;
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_1_TAP_2),
+            [
+                (true, false),
+                (false, true),
+                (true, false),
+                (false, true),
+                (false, true),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &HOLD_2_TAP_1),
+            [
+                (false, true),
+                (false, true),
+                (false, true),
+                (false, true),
+                (true, false),
+                (false, false)
+            ]
+        );
+        assert_eq!(
+            resolve_sequence(socd, &SAME_POLL),
+            [
+                (false, true),
+                (false, true),
+                (false, true),
+                (true, false),
+                (false, false)
+            ]
+        );
+    }
+
+    /// Checks every sequence of 6 polls against the properties shared by all modes.
+    #[test]
+    fn every_sequence() {
+        const POLLS: u32 = 6;
+        let inputs = [(false, false), (true, false), (false, true), (true, true)];
+        for socd in ALL {
+            for sequence in 0..inputs.len().pow(POLLS) {
+                let polls: Vec<_> = (0..POLLS)
+                    .map(|i| inputs[sequence / inputs.len().pow(i) % inputs.len()])
+                    .collect();
+                let resolved = resolve_sequence(socd, &polls);
+                for (i, (input, output)) in polls.iter().zip(&resolved).enumerate() {
+                    let context = format!("{socd:?} {polls:?} poll {i}");
+                    // Only held directions are active, and never both at once
+                    assert!(!output.0 || input.0, "{context}");
+                    assert!(!output.1 || input.1, "{context}");
+                    assert!(!(output.0 && output.1), "{context}");
+                    // A direction held alone is active, unless it was overridden without reactivation
+                    if input.0 != input.1 && socd != SocdType::SecondInputPriorityNoReactivation {
+                        assert_eq!(output, input, "{context}");
+                    }
+                    // Only neutral leaves both held directions inactive
+                    if *input == (true, true) {
+                        assert_eq!(
+                            *output == (false, false),
+                            socd == SocdType::Neutral,
+                            "{context}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Switching profiles with each profile's activation combination.
+
+use crate::{
+    input::{ButtonInputResults, PHYSICAL_BUTTONS, TimedInput},
+    time::Instant,
+};
+use rukaibox_config::{MAX_PROFILES, Profile};
+
+/// Tracks the activation combination of every profile across polls.
+#[derive(Default)]
+pub struct SwitchState {
+    /// When each profile's combination was completed, None while it is not held.
+    /// Indexed the same as `Config::profiles`.
+    held_since: [Option<Instant>; MAX_PROFILES],
+    /// Profiles that have been switched to since their combination was completed, a bitmask indexed the same as `held_since`.
+    switched: u32,
+    /// A bitmask indexed by the `PhysicalButton` discriminant.
+    /// A button is set from the poll a combination containing it is completed on until the button is released.
+    suppressed: u32,
+}
+
+impl SwitchState {
+    /// Updates the combinations for a new poll, returning the index of the profile to switch to, if any.
+    ///
+    /// A profile is switched to once per completion of its combination, after it has been held for `activation_hold_ms`.
+    /// When several profiles are switched to on the same poll, the first listed wins.
+    pub fn update(&mut self, profiles: &[Profile], timed: &TimedInput) -> Option<usize> {
+        let buttons = &timed.buttons;
+        for button in PHYSICAL_BUTTONS {
+            if !buttons.get_button_value(button) {
+                self.suppressed &= !(1 << button as u32);
+            }
+        }
+
+        let mut switch_to = None;
+        for (i, profile) in profiles.iter().enumerate() {
+            let bit = 1 << i;
+            let combination = &profile.activation_combination;
+            // Without a combination a profile can only be used by being the first profile.
+            let held = !combination.is_empty()
+                && combination
+                    .iter()
+                    .all(|button| buttons.get_button_value(*button));
+            if !held {
+                self.held_since[i] = None;
+                self.switched &= !bit;
+                continue;
+            }
+
+            for button in combination {
+                self.suppressed |= 1 << *button as u32;
+            }
+            let since = *self.held_since[i].get_or_insert(timed.now);
+            let hold = profile.activation_hold_ms as u64 * 1000;
+            if self.switched & bit == 0 && timed.now.micros_since(since) >= hold {
+                self.switched |= bit;
+                switch_to = switch_to.or(Some(i));
+            }
+        }
+        switch_to
+    }
+
+    /// Returns true while any button of a completed combination is still held.
+    pub fn suppressing(&self) -> bool {
+        self.suppressed != 0
+    }
+
+    /// Releases every button of a completed combination, so that combinations never reach the game.
+    pub fn suppress(&self, buttons: &mut ButtonInputResults) {
+        for button in PHYSICAL_BUTTONS {
+            if self.suppressed & (1 << button as u32) != 0 {
+                buttons.set_button_value(button, false);
+            }
+        }
+    }
+}
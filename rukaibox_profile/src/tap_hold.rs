@@ -0,0 +1,274 @@
+//! Tap-hold buttons activate one logical button when tapped and another when held.
+//!
+//! See [`rukaibox_config::TapHold`] for when a press is decided.
+
+use crate::{
+    input::{ButtonInputLogical, PHYSICAL_BUTTONS, TimedInput},
+    time::Instant,
+};
+use rukaibox_config::{LogicalButton, TapHold};
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum Press {
+    #[default]
+    Released,
+    /// Pressed at the given time, but not yet decided as a tap or hold.
+    Undecided(Instant),
+    Hold,
+}
+
+/// Tracks each tap-hold button across polls, along with when the logical buttons they activate were pressed and released.
+#[derive(Default)]
+pub struct TapHoldState {
+    /// Indexed the same as `Profile::tap_holds`.
+    presses: [Press; 4],
+    /// When the most recent tap of each tap-hold ends.
+    taps_until: [Option<Instant>; 4],
+    /// The logical buttons activated by tap-holds, a bitmask indexed by the `LogicalButton` discriminant.
+    active: u32,
+    pressed_at: [Option<Instant>; LogicalButton::VALUES.len()],
+    released_at: [Option<Instant>; LogicalButton::VALUES.len()],
+}
+
+impl TapHoldState {
+    /// Decides any undecided presses for a new poll.
+    pub fn update(&mut self, tap_holds: &[TapHold], timed: &TimedInput) {
+        let now = timed.now;
+        let mut active = 0;
+        for (i, tap_hold) in tap_holds.iter().enumerate() {
+            let held = timed.buttons.get_button_value(tap_hold.button);
+            // Includes buttons pressed on the same poll as the tap-hold button, since their order is unknown.
+            let other_pressed = tap_hold.hold_on_other_key_press
+                && PHYSICAL_BUTTONS.into_iter().any(|button| {
+                    button != tap_hold.button && timed.pressed_at(button) == Some(now)
+                });
+            let term = tap_hold.tapping_term_ms as u64 * 1000;
+            let tap = tap_hold.tap_ms as u64 * 1000;
+
+            let press = match self.presses[i] {
+                Press::Released if held => Press::Undecided(now),
+                press => press,
+            };
+            self.presses[i] = match press {
+                Press::Undecided(_) if !held => {
+                    self.taps_until[i] = Some(Instant::from_micros(now.micros() + tap));
+                    Press::Released
+                }
+                Press::Undecided(pressed) if now.micros_since(pressed) >= term || other_pressed => {
+                    Press::Hold
+                }
+                _ if !held => Press::Released,
+                press => press,
+            };
+
+            if self.presses[i] == Press::Hold {
+                active |= 1 << tap_hold.hold as u32;
+            }
+            if self.taps_until[i].is_some_and(|until| now < until) {
+                active |= 1 << tap_hold.tap as u32;
+            }
+        }
+
+        for logical in LogicalButton::VALUES {
+            let bit = 1 << *logical as u32;
+            match (self.active & bit != 0, active & bit != 0) {
+                (false, true) => self.pressed_at[*logical as usize] = Some(now),
+                (true, false) => self.released_at[*logical as usize] = Some(now),
+                _ => {}
+            }
+        }
+        self.active = active;
+    }
+
+    /// Presses every logical button activated by a tap-hold.
+    pub fn press(&self, buttons: &mut ButtonInputLogical) {
+        for logical in LogicalButton::VALUES {
+            if self.active & (1 << *logical as u32) != 0 {
+                *buttons.get_mut(*logical) = true;
+            }
+        }
+    }
+
+    /// When a tap-hold last activated the logical button, or None if it has not since the profile was loaded.
+    pub fn pressed_at(&self, logical: LogicalButton) -> Option<Instant> {
+        self.pressed_at[logical as usize]
+    }
+
+    /// When a tap-hold last deactivated the logical button, or None if it has not since the profile was loaded.
+    pub fn released_at(&self, logical: LogicalButton) -> Option<Instant> {
+        self.released_at[logical as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TapHoldState;
+    use crate::{
+        input::ButtonInputLogical,
+        profile::tests::{pressed, timed_sequence},
+        time::Instant,
+    };
+    use rukaibox_config::{LogicalButton, LogicalButtonToPhysicalButton, PhysicalButton, TapHold};
+
+    const THUMB: PhysicalButton = PhysicalButton::LeftThumbLeft;
+    const OTHER: PhysicalButton = PhysicalButton::RightRing;
+
+    /// Mod-x when held, dpad-up when tapped.
+    fn thumb(hold_on_other_key_press: bool) -> TapHold {
+        TapHold {
+            button: THUMB,
+            tap: LogicalButton::DpadUp,
+            hold: LogicalButton::ModX,
+            tapping_term_ms: 200,
+            hold_on_other_key_press,
+            tap_ms: TapHold::DEFAULT_TAP_MS,
+        }
+    }
+
+    /// Updates with each `(held buttons, millis since the previous poll)` in order,
+    /// returning whether the tap and hold buttons are active on each poll.
+    fn tap_hold_sequence(
+        tap_hold: TapHold,
+        polls: &[(&[PhysicalButton], u64)],
+    ) -> Vec<(bool, bool)> {
+        let mut state = TapHoldState::default();
+        let tap_holds = [tap_hold];
+        timed_sequence(polls, |timed| {
+            state.update(&tap_holds, timed);
+            let mut logical = pressed(&[]).to_gc(&LogicalButtonToPhysicalButton::default());
+            state.press(&mut logical);
+            let ButtonInputLogical { dpad_up, mod_x, .. } = logical;
+            (dpad_up, mod_x)
+        })
+    }
+
+    #[test]
+    fn tap() {
+        // Nothing is active while undecided, then the tap lasts for tap-ms after the release
+        let tap_millis = TapHold::DEFAULT_TAP_MS as u64;
+        assert_eq!(
+            tap_hold_sequence(
+                thumb(false),
+                &[
+                    (&[THUMB], 0),
+                    (&[THUMB], 100),
+                    (&[], 50),
+                    (&[], tap_millis - 1),
+                    (&[], 1)
+                ]
+            ),
+            [
+                (false, false),
+                (false, false),
+                (true, false),
+                (true, false),
+                (false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn hold() {
+        // Decided on the first poll at the tapping term, and no tap follows the release
+        assert_eq!(
+            tap_hold_sequence(
+                thumb(false),
+                &[
+                    (&[THUMB], 0),
+                    (&[THUMB], 199),
+                    (&[THUMB], 1),
+                    (&[THUMB], 500),
+                    (&[], 1)
+                ]
+            ),
+            [
+                (false, false),
+                (false, false),
+                (false, true),
+                (false, true),
+                (false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn tap_ms() {
+        let tap_hold = TapHold {
+            tap_ms: 100,
+            ..thumb(false)
+        };
+        assert_eq!(
+            tap_hold_sequence(tap_hold, &[(&[THUMB], 0), (&[], 10), (&[], 99), (&[], 1)]),
+            [(false, false), (true, false), (true, false), (false, false)]
+        );
+    }
+
+    #[test]
+    fn released_on_the_tapping_term() {
+        // The button was never seen held for the full tapping term, so it is a tap
+        assert_eq!(
+            tap_hold_sequence(thumb(false), &[(&[THUMB], 0), (&[], 200)]),
+            [(false, false), (true, false)]
+        );
+    }
+
+    #[test]
+    fn hold_on_other_key_press() {
+        assert_eq!(
+            tap_hold_sequence(
+                thumb(true),
+                &[
+                    (&[THUMB], 0),
+                    (&[THUMB, OTHER], 10),
+                    (&[THUMB], 10),
+                    (&[], 10)
+                ]
+            ),
+            [(false, false), (false, true), (false, true), (false, false)]
+        );
+        // Pressed on the same poll as the tap-hold button
+        assert_eq!(
+            tap_hold_sequence(thumb(true), &[(&[THUMB, OTHER], 0), (&[], 10)]),
+            [(false, true), (false, false)]
+        );
+        // Already held before the tap-hold button, so it is not another key press
+        assert_eq!(
+            tap_hold_sequence(
+                thumb(true),
+                &[(&[OTHER], 0), (&[OTHER, THUMB], 10), (&[], 10)]
+            ),
+            [(false, false), (false, false), (true, false)]
+        );
+    }
+
+    #[test]
+    fn other_key_press_ignored() {
+        // Without hold-on-other-key-press, only the tapping term decides a hold
+        assert_eq!(
+            tap_hold_sequence(
+                thumb(false),
+                &[(&[THUMB], 0), (&[THUMB, OTHER], 10), (&[OTHER], 10)]
+            ),
+            [(false, false), (false, false), (true, false)]
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut state = TapHoldState::default();
+        let tap_holds = [thumb(false)];
+        timed_sequence(&[(&[THUMB], 1), (&[THUMB], 300), (&[], 100)], |timed| {
+            state.update(&tap_holds, timed)
+        });
+        // The hold was pressed when it was decided, not when the physical button was pressed
+        assert_eq!(
+            state.pressed_at(LogicalButton::ModX),
+            Some(Instant::from_micros(301_000))
+        );
+        assert_eq!(
+            state.released_at(LogicalButton::ModX),
+            Some(Instant::from_micros(401_000))
+        );
+        assert_eq!(state.pressed_at(LogicalButton::DpadUp), None);
+    }
+}
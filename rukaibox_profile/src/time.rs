@@ -0,0 +1,42 @@
+/// A monotonic timestamp in microseconds since boot.
+///
+/// On the controller this comes from the RP2040 `Timer`, host tests use a [`SimulatedClock`] instead.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct Instant(pub u64);
+
+impl Instant {
+    pub const fn from_micros(micros: u64) -> Self {
+        Instant(micros)
+    }
+
+    pub const fn micros(self) -> u64 {
+        self.0
+    }
+
+    /// Microseconds elapsed between `earlier` and `self`, 0 if `earlier` is later.
+    pub const fn micros_since(self, earlier: Instant) -> u64 {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+/// A clock for host tests that only moves when told to.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct SimulatedClock {
+    now: Instant,
+}
+
+#[cfg(test)]
+impl SimulatedClock {
+    pub fn now(&self) -> Instant {
+        self.now
+    }
+
+    pub fn advance_micros(&mut self, micros: u64) {
+        self.now.0 += micros;
+    }
+
+    pub fn advance_millis(&mut self, millis: u64) {
+        self.advance_micros(millis * 1000);
+    }
+}
@@ -0,0 +1,132 @@
+//! Emulates the time a real analog stick takes to travel between positions.
+//!
+//! Digital inputs jump the stick from one position to another within a single poll,
+//! which some games and rulesets treat differently from a physical stick.
+
+use crate::time::Instant;
+
+/// Moves a stick in a straight line towards its target position over a fixed duration.
+pub struct StickTravel {
+    duration_micros: u64,
+    from: (u8, u8),
+    to: (u8, u8),
+    started: Instant,
+}
+
+impl StickTravel {
+    /// A duration of 0 disables travel, the target is always reported immediately.
+    pub fn new(duration_micros: u32) -> Self {
+        StickTravel {
+            duration_micros: duration_micros as u64,
+            from: (128, 128),
+            to: (128, 128),
+            started: Instant::default(),
+        }
+    }
+
+    /// Returns the position to report at `now` for a stick the profile has mapped to `target`.
+    ///
+    /// When the target changes mid travel, the stick heads for the new target from wherever it currently is.
+    pub fn update(&mut self, target: (u8, u8), now: Instant) -> (u8, u8) {
+        if self.duration_micros == 0 {
+            return target;
+        }
+        if target != self.to {
+            self.from = self.position(now);
+            self.to = target;
+            self.started = now;
+        }
+        self.position(now)
+    }
+
+    fn position(&self, now: Instant) -> (u8, u8) {
+        let elapsed = now.micros_since(self.started).min(self.duration_micros);
+        let axis = |from: u8, to: u8| {
+            let distance = to as i64 - from as i64;
+            let travelled = distance * elapsed as i64 / self.duration_micros as i64;
+            (from as i64 + travelled) as u8
+        };
+        (axis(self.from.0, self.to.0), axis(self.from.1, self.to.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StickTravel;
+    use crate::time::SimulatedClock;
+
+    /// Updates with each `(target, micros since the previous update)` in order, returning the reported positions.
+    fn travel_sequence(duration_micros: u32, updates: &[((u8, u8), u64)]) -> Vec<(u8, u8)> {
+        let mut travel = StickTravel::new(duration_micros);
+        let mut clock = SimulatedClock::default();
+        updates
+            .iter()
+            .map(|(target, micros)| {
+                clock.advance_micros(*micros);
+                travel.update(*target, clock.now())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn disabled() {
+        assert_eq!(
+            travel_sequence(0, &[((228, 128), 0), ((128, 28), 1000)]),
+            [(228, 128), (128, 28)]
+        );
+    }
+
+    #[test]
+    fn straight_line() {
+        assert_eq!(
+            travel_sequence(
+                4000,
+                &[
+                    ((228, 228), 0),
+                    ((228, 228), 1000),
+                    ((228, 228), 1000),
+                    ((228, 228), 1000),
+                    ((228, 228), 1000),
+                    ((228, 228), 1000)
+                ]
+            ),
+            [
+                (128, 128),
+                (153, 153),
+                (178, 178),
+                (203, 203),
+                (228, 228),
+                (228, 228)
+            ]
+        );
+    }
+
+    #[test]
+    fn retarget_mid_travel() {
+        // Halfway to the right, then back to neutral over a full duration from the halfway point
+        assert_eq!(
+            travel_sequence(
+                4000,
+                &[
+                    ((228, 128), 0),
+                    ((228, 128), 2000),
+                    ((128, 128), 0),
+                    ((128, 128), 2000),
+                    ((128, 128), 2000)
+                ]
+            ),
+            [(128, 128), (178, 128), (178, 128), (153, 128), (128, 128)]
+        );
+    }
+
+    #[test]
+    fn towards_negative() {
+        assert_eq!(
+            travel_sequence(
+                3000,
+                &[((28, 128), 0), ((28, 128), 1000), ((28, 128), 2000)]
+            ),
+            [(128, 128), (95, 128), (28, 128)]
+        );
+    }
+}